@@ -212,7 +212,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
             let tag_opts = TagOpts::builder().repo(repo).tag(tag).build();
 
-            let image = Image::new(&docker, name);
+            let image = Image::new(docker.clone(), name);
 
             if let Err(e) = image.tag(&tag_opts).await {
                 eprintln!("Error: {}", e)
@@ -1,5 +1,8 @@
 use serde::{Deserialize, Serialize};
 
+#[cfg(feature = "chrono")]
+use chrono::{DateTime, Utc};
+
 use std::collections::HashMap;
 
 pub type Labels = HashMap<String, String>;
@@ -36,3 +39,26 @@ pub struct Driver {
     pub name: String,
     pub options: Options,
 }
+
+/// The output stream a [`LogLine`](LogLine) was written to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LogStream {
+    StdOut,
+    StdErr,
+}
+
+/// A line of the logs of a container, service or task.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LogLine {
+    pub stream: LogStream,
+    /// When the line was logged, only set if the logs were requested with `timestamps`.
+    #[cfg(feature = "chrono")]
+    pub timestamp: Option<DateTime<Utc>>,
+    #[cfg(not(feature = "chrono"))]
+    pub timestamp: Option<String>,
+    /// Attributes added by the logging driver, like the labels and environment variables selected
+    /// with the `labels` and `env` log options. Only set if the logs were requested with `details`.
+    pub details: Option<Attributes>,
+    /// The line without the trailing newline.
+    pub message: Vec<u8>,
+}
@@ -1,5 +1,17 @@
+use crate::version::ApiVersion;
+
 impl_opts_builder!(url => Logs);
 
+impl LogsOpts {
+    pub(crate) fn timestamps(&self) -> bool {
+        self.params.get("timestamps").map(String::as_str) == Some("true")
+    }
+
+    pub(crate) fn details(&self) -> bool {
+        self.params.get("details").map(String::as_str) == Some("true")
+    }
+}
+
 impl LogsOptsBuilder {
     impl_url_bool_field!(
         /// Keep connection after returning logs.
@@ -21,6 +33,11 @@ impl LogsOptsBuilder {
         timestamps => "timestamps"
     );
 
+    impl_url_bool_field!(
+        /// Add the attributes provided by the logging driver to every log line.
+        details => "details" since (1, 25)
+    );
+
     impl_url_field!(
         /// Only return this number of log lines from the end of logs
         n_lines: usize => "tail"
@@ -52,19 +69,25 @@ impl LogsOptsBuilder {
 
     #[cfg(feature = "chrono")]
     /// Only return logs before this time.
+    ///
+    /// Requires API version 1.35 or newer.
     pub fn until<Tz>(&mut self, timestamp: &chrono::DateTime<Tz>) -> &mut Self
     where
         Tz: chrono::TimeZone,
     {
         self.params
             .insert("until", timestamp.timestamp().to_string());
+        self.versions.insert("until", ApiVersion::new(1, 35));
         self
     }
 
     #[cfg(not(feature = "chrono"))]
     /// Only return logs before this time, as a UNIX timestamp.
+    ///
+    /// Requires API version 1.35 or newer.
     pub fn until(&mut self, timestamp: i64) -> &mut Self {
         self.params.insert("until", timestamp.to_string());
+        self.versions.insert("until", ApiVersion::new(1, 35));
         self
     }
 }
@@ -6,19 +6,19 @@ use crate::{conn::Payload, Result};
 
 impl_api_ty!(Config => name);
 
-impl<'docker> Config<'docker> {
+impl Config {
     impl_api_ep! { cfg: Config, resp
-        Inspect -> &format!("/configs/{}", cfg.name)
-        Delete -> &format!("/configs/{}", cfg.name)
+        Inspect since (1, 30) -> &format!("/configs/{}", cfg.name)
+        Delete since (1, 30) -> &format!("/configs/{}", cfg.name)
     }
 
     // TODO: add Config::update
 }
 
-impl<'docker> Configs<'docker> {
+impl Configs {
     impl_api_ep! { __: Config, resp
-        List -> "/configs"
-        Create -> "/configs/create", resp.id
+        List since (1, 30) -> "/configs"
+        Create since (1, 30) -> "/configs/create", resp.id
     }
 }
 
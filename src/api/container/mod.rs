@@ -16,20 +16,32 @@ use hyper::Body;
 use crate::util::url::construct_ep;
 use crate::{
     api::{Exec, ExecContainerOpts},
-    conn::{Multiplexer as TtyMultiplexer, Payload, TtyChunk},
+    conn::{
+        tty::{DetachKeys, RunningCheck},
+        Multiplexer as TtyMultiplexer, Payload, TtyChunk,
+    },
     util::url::{append_query, encoded_pair},
+    version::Endpoint,
     Error, Result,
 };
 
 impl_api_ty!(Container => id);
 
-impl<'docker> Container<'docker> {
+impl Container {
     impl_api_ep! {container: Container, resp
         Inspect -> &format!("/containers/{}/json", container.id), Details
         Logs -> &format!("/containers/{}/logs", container.id)
         DeleteWithOpts -> &format!("/containers/{}", container.id), String, delete
     }
 
+    /// Returns whether the container has a TTY, which decides the format of its logs.
+    pub(crate) async fn tty(&self) -> Result<bool> {
+        Container::new(self.docker.untraced(), self.id.clone())
+            .inspect()
+            .await
+            .map(|details| matches!(details.config, Some(config) if config.tty))
+    }
+
     api_doc! { Container => Top
     /// Returns a `top` view of information about the container process.
     /// On Unix systems, this is done by running the ps command. This endpoint is not supported on Windows.
@@ -43,13 +55,10 @@ impl<'docker> Container<'docker> {
     }}
 
     /// Attaches a multiplexed TCP stream to the container that can be used to read Stdout, Stderr and write Stdin.
-    async fn attach_raw(&self) -> Result<impl AsyncRead + AsyncWrite + Send + 'docker> {
+    async fn attach_raw(&self, opts: &AttachOpts) -> Result<impl AsyncRead + AsyncWrite + Send> {
         self.docker
             .stream_post_upgrade(
-                format!(
-                    "/containers/{}/attach?stream=1&stdout=1&stderr=1&stdin=1",
-                    self.id
-                ),
+                construct_ep(format!("/containers/{}/attach", self.id), opts.serialize()),
                 Payload::empty(),
             )
             .await
@@ -61,9 +70,62 @@ impl<'docker> Container<'docker> {
     /// The [`TtyMultiplexer`](TtyMultiplexer) implements Stream for returning Stdout and Stderr chunks. It also implements [`AsyncWrite`](futures_util::io::AsyncWrite) for writing to Stdin.
     ///
     /// The multiplexer can be split into its read and write halves with the [`split`](TtyMultiplexer::split) method
+    ///
+    /// For interactive sessions create the container with [`tty`](ContainerOptsBuilder::tty) and
+    /// [`open_stdin`](ContainerOptsBuilder::open_stdin) and call [`resize`](Container::resize)
+    /// whenever the size of the local terminal changes.
+    |
+    pub async fn attach(&self) -> Result<TtyMultiplexer> {
+        self.attach_with_opts(
+            &AttachOpts::builder()
+                .stream(true)
+                .stdin(true)
+                .stdout(true)
+                .stderr(true)
+                .build(),
+        )
+        .await
+    }}
+
+    api_doc! { Container => Attach
+    /// Attaches a [`TtyMultiplexer`](TtyMultiplexer) to the selected streams of the container.
+    ///
+    /// With [`logs`](AttachOptsBuilder::logs) the output produced before attaching is replayed
+    /// first so nothing is lost between starting the container and attaching to it.
     |
-    pub async fn attach(&self) -> Result<TtyMultiplexer<'docker>> {
-        self.attach_raw().await.map(TtyMultiplexer::new)
+    pub async fn attach_with_opts(&self, opts: &AttachOpts) -> Result<TtyMultiplexer> {
+        let detach_keys = match opts.detach_keys() {
+            Some(keys) => DetachKeys::parse(keys)?,
+            None => DetachKeys::default(),
+        };
+        let container = Container::new(self.docker.untraced(), self.id.clone());
+        let is_running: RunningCheck = Box::new(move || {
+            Box::pin(async move {
+                container
+                    .inspect()
+                    .await
+                    .map(|details| details.state.running)
+                    .unwrap_or(false)
+            })
+        });
+        self.attach_raw(opts)
+            .await
+            .map(|connection| TtyMultiplexer::new(connection, detach_keys, is_running))
+    }}
+
+    api_doc! { Container => AttachWebsocket
+    /// Attaches to the streams of the container selected by `opts` over a
+    /// [`WebSocket`](crate::conn::WebSocket), for example to connect it to a terminal in a browser.
+    |
+    #[cfg(feature = "websocket")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "websocket")))]
+    pub async fn attach_websocket(&self, opts: &AttachOpts) -> Result<crate::conn::WebSocket> {
+        self.docker
+            .websocket(construct_ep(
+                format!("/containers/{}/attach/ws", self.id),
+                opts.serialize(),
+            ))
+            .await
     }}
 
     api_doc! { Container => Changes
@@ -78,7 +140,7 @@ impl<'docker> Container<'docker> {
     api_doc! { Container => Export
     /// Exports the current docker container into a tarball.
     |
-    pub fn export(&self) -> impl Stream<Item = Result<Vec<u8>>> + 'docker {
+    pub fn export(&self) -> impl Stream<Item = Result<Vec<u8>>> {
         self.docker
             .stream_get(format!("/containers/{}/export", self.id))
             .map_ok(|c| c.to_vec())
@@ -87,7 +149,7 @@ impl<'docker> Container<'docker> {
     api_doc! { Container => Stats
     /// Returns a stream of stats specific to this container instance.
     |
-    pub fn stats(&self) -> impl Stream<Item = Result<Stats>> + Unpin + 'docker {
+    pub fn stats(&self) -> impl Stream<Item = Result<Stats>> + Unpin {
         let codec = futures_codec::LinesCodec {};
 
         let reader = Box::pin(
@@ -109,6 +171,9 @@ impl<'docker> Container<'docker> {
 
     api_doc! { Container => Start
     /// Start the container instance.
+    ///
+    /// Fails with [`Error::NotModified`](crate::Error::NotModified) if the container is already
+    /// running.
     |
     pub async fn start(&self) -> Result<()> {
         self.docker
@@ -119,6 +184,9 @@ impl<'docker> Container<'docker> {
 
     api_doc! { Container => Stop
     /// Stop the container instance.
+    ///
+    /// Fails with [`Error::NotModified`](crate::Error::NotModified) if the container is already
+    /// stopped.
     |
     pub async fn stop(&self, wait: Option<Duration>) -> Result<()> {
         let mut ep = format!("/containers/{}/stop", self.id);
@@ -190,6 +258,20 @@ impl<'docker> Container<'docker> {
             .map(|_| ())
     }}
 
+    api_doc! { Container => Resize
+    /// Resizes the TTY of the container, for example when the terminal of an attached session
+    /// changes its size. The container has to be running.
+    |
+    pub async fn resize(&self, opts: &ContainerResizeOpts) -> Result<()> {
+        self.docker
+            .post(
+                &construct_ep(format!("/containers/{}/resize", self.id), opts.serialize()),
+                Payload::empty(),
+            )
+            .await
+            .map(|_| ())
+    }}
+
     api_doc! { Container => Wait
     /// Wait until the container stops.
     |
@@ -199,14 +281,27 @@ impl<'docker> Container<'docker> {
             .await
     }}
 
+    api_doc! { Container => Update
+    /// Changes the resource limits and restart policy of the container while it's running. Returns
+    /// the warnings reported by the daemon.
+    |
+    pub async fn update(&self, opts: &ContainerUpdateOpts) -> Result<Vec<String>> {
+        let ep = Endpoint::from(format!("/containers/{}/update", self.id))
+            .since(opts.min_api_version());
+        self.docker
+            .post_json(ep, Payload::Json(opts.serialize()?))
+            .await
+            .map(|info: ContainerUpdateInfo| info.warnings.unwrap_or_default())
+    }}
+
     api_doc! { Exec
     /// Execute a command in this container.
     |
     pub fn exec(
-        &'docker self,
+        &self,
         opts: &ExecContainerOpts,
-    ) -> impl Stream<Item = Result<TtyChunk>> + Unpin + 'docker {
-        Exec::create_and_start(self.docker, &self.id, opts)
+    ) -> impl Stream<Item = Result<TtyChunk>> + Unpin {
+        Exec::create_and_start(self.docker.clone(), &self.id, opts)
     }}
 
     api_doc! { Container => Archive
@@ -219,7 +314,7 @@ impl<'docker> Container<'docker> {
     /// ends in `/.`  then this indicates that only the contents of the path directory should be
     /// copied.  A symlink is always resolved to its target.
     |
-    pub fn copy_from(&self, path: &Path) -> impl Stream<Item = Result<Vec<u8>>> + 'docker {
+    pub fn copy_from(&self, path: &Path) -> impl Stream<Item = Result<Vec<u8>>> {
         self.docker
             .stream_get(format!(
                 "/containers/{}/archive?{}",
@@ -318,23 +413,361 @@ impl<'docker> Container<'docker> {
     }}
 }
 
-impl<'docker> Containers<'docker> {
+impl Containers {
     impl_api_ep! {__: Container, resp
         List -> "/containers/json"
-        Prune ->  "/containers/prune"
+        Prune since (1, 25) -> "/containers/prune"
     }
 
     api_doc! { Containers => Create
     /// Create a container
     |
-    pub async fn create(&self, opts: &ContainerCreateOpts) -> Result<Container<'docker>>
+    pub async fn create(&self, opts: &ContainerCreateOpts) -> Result<Container>
     {
         let ep = if let Some(name) = opts.name().as_ref() {
             construct_ep("/containers/create", Some(encoded_pair("name", name)))
         } else {
             "/containers/create".to_owned()
         };
-        self.docker.post_json(&ep, Payload::Json(opts.serialize()?)).await
-        .map(|resp: ContainerCreateInfo| Container::new(self.docker, resp.id))
+        let ep = Endpoint::from(ep).since(opts.min_api_version());
+        self.docker.post_json(ep, Payload::Json(opts.serialize()?)).await
+        .map(|resp: ContainerCreateInfo| Container::new(self.docker.clone(), resp.id))
     }}
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::{conn::Mock, ApiVersion, Docker};
+
+    use hyper::{header::HeaderValue, Method, StatusCode};
+
+    /// Response of `GET /containers/web/json` for a container with the given state and TTY.
+    pub(crate) fn details(running: bool, tty: bool) -> serde_json::Value {
+        const TIME: &str = "2021-06-01T12:30:00Z";
+        serde_json::json!({
+            "Id": "web",
+            "Created": TIME,
+            "Path": "sh",
+            "Args": [],
+            "State": {
+                "Status": if running { "running" } else { "exited" },
+                "Running": running,
+                "Paused": false,
+                "Restarting": false,
+                "OOMKilled": false,
+                "Dead": false,
+                "Pid": 0,
+                "ExitCode": 0,
+                "Error": "",
+                "StartedAt": TIME,
+                "FinishedAt": TIME,
+            },
+            "Image": "sha256:ubuntu",
+            "ResolvConfPath": "",
+            "HostnamePath": "",
+            "HostsPath": "",
+            "LogPath": "",
+            "Name": "/web",
+            "RestartCount": 0,
+            "Driver": "overlay2",
+            "Platform": "linux",
+            "MountLabel": "",
+            "ProcessLabel": "",
+            "AppArmorProfile": "",
+            "HostConfig": {
+                "BlkioWeight": 0,
+                "KernelMemory": 0,
+                "KernelMemoryTCP": 0,
+                "CpuCount": 0,
+                "CpuPercent": 0,
+                "IOMaximumIOps": 0,
+                "ContainerIDFile": "",
+                "LogConfig": {"Type": "json-file", "Config": {}},
+                "NetworkMode": "default",
+                "RestartPolicy": {"Name": "no", "MaximumRetryCount": 0},
+                "AutoRemove": false,
+                "VolumeDriver": "",
+                "IpcMode": "private",
+                "Cgroup": "",
+                "OomScoreAdj": 0,
+                "Privileged": false,
+                "PublishAllPorts": false,
+                "UTSMode": "",
+                "UsernsMode": "",
+                "ShmSize": 0,
+                "Isolation": "",
+            },
+            "GraphDriver": {"Name": "overlay2", "Data": {}},
+            "Mounts": [],
+            "Config": {
+                "Hostname": "web",
+                "Domainname": "",
+                "User": "",
+                "AttachStdin": false,
+                "AttachStdout": true,
+                "AttachStderr": true,
+                "Tty": tty,
+                "OpenStdin": false,
+                "StdinOnce": false,
+                "Env": [],
+                "Image": "ubuntu",
+                "WorkingDir": "",
+            },
+            "NetworkSettings": {
+                "Bridge": "",
+                "SandboxID": "",
+                "HairpinMode": false,
+                "LinkLocalIPv6Address": "",
+                "LinkLocalIPv6PrefixLen": 0,
+                "SandboxKey": "",
+                "EndpointID": "",
+                "Gateway": "",
+                "GlobalIPv6Address": "",
+                "GlobalIPv6PrefixLen": 0,
+                "IPAddress": "",
+                "IPPrefixLen": 0,
+                "IPv6Gateway": "",
+                "MacAddress": "",
+                "Networks": {},
+            },
+        })
+    }
+
+    #[tokio::test]
+    async fn updates_resources_of_a_running_container() {
+        let mock = Mock::new();
+        mock.on(Method::POST, "/containers/web/update").json(
+            StatusCode::OK,
+            &serde_json::json!({"Warnings": ["swap limit not supported"]}),
+        );
+        let docker = Docker::mock(mock.clone());
+
+        let warnings = docker
+            .containers()
+            .get("web")
+            .update(
+                &ContainerUpdateOpts::builder()
+                    .memory(64 << 20)
+                    .pids_limit(100)
+                    .restart_policy("on-failure", 3)
+                    .build(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(warnings, ["swap limit not supported"]);
+
+        let body: serde_json::Value = mock.requests()[0].json().unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "Memory": 64 << 20,
+                "PidsLimit": 100,
+                "RestartPolicy": {"Name": "on-failure", "MaximumRetryCount": 3}
+            })
+        );
+
+        let docker = Docker::builder()
+            .version(ApiVersion::new(1, 39))
+            .build_with_mock(mock.clone());
+        assert!(matches!(
+            docker
+                .containers()
+                .get("web")
+                .update(&ContainerUpdateOpts::builder().pids_limit(100).build())
+                .await,
+            Err(Error::UnsupportedApiVersion { .. })
+        ));
+        assert_eq!(mock.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn resizes_the_tty_of_a_container() {
+        let mock = Mock::new();
+        mock.on(Method::POST, "/containers/create").json(
+            StatusCode::CREATED,
+            &serde_json::json!({"Id": "4f2a", "Warnings": []}),
+        );
+        mock.on(Method::POST, "/containers/4f2a/resize")
+            .status(StatusCode::OK);
+        let docker = Docker::builder()
+            .version(ApiVersion::new(1, 42))
+            .build_with_mock(mock.clone());
+
+        let container = docker
+            .containers()
+            .create(
+                &ContainerCreateOpts::builder("ubuntu")
+                    .tty(true)
+                    .open_stdin(true)
+                    .stdin_once(true)
+                    .console_size(24, 80)
+                    .build(),
+            )
+            .await
+            .unwrap();
+        container
+            .resize(&ContainerResizeOpts::builder().height(40).width(120).build())
+            .await
+            .unwrap();
+
+        let requests = mock.requests();
+        let body: serde_json::Value = requests[0].json().unwrap();
+        assert_eq!(body["Tty"], true);
+        assert_eq!(body["OpenStdin"], true);
+        assert_eq!(body["StdinOnce"], true);
+        assert_eq!(
+            body["HostConfig"]["ConsoleSize"],
+            serde_json::json!([24, 80])
+        );
+        let mut query: Vec<_> = requests[1].query.as_deref().unwrap().split('&').collect();
+        query.sort_unstable();
+        assert_eq!(query, ["h=40", "w=120"]);
+
+        let docker = Docker::mock(mock.clone());
+        assert!(matches!(
+            docker
+                .containers()
+                .create(
+                    &ContainerCreateOpts::builder("ubuntu")
+                        .console_size(24, 80)
+                        .build()
+                )
+                .await,
+            Err(Error::UnsupportedApiVersion { .. })
+        ));
+    }
+
+    /// Attaches with the detach keys `ctrl-x,x`, writes them and lets the daemon close the stream.
+    async fn detach(mock: &Mock, running: bool) -> TtyMultiplexer {
+        use futures_util::{AsyncWriteExt, StreamExt};
+        use tokio::io::{AsyncReadExt, AsyncWriteExt as _};
+
+        let mut daemon = mock.on(Method::POST, "/containers/web/attach").upgrade();
+        mock.on(Method::GET, "/containers/web/json")
+            .json(StatusCode::OK, &details(running, false));
+        tokio::spawn(async move {
+            daemon
+                .write_all(&TtyChunk::StdOut(b"$ ".to_vec()).encode())
+                .await
+                .unwrap();
+            let mut keys = [0; 3];
+            daemon.read_exact(&mut keys).await.unwrap();
+            assert_eq!(&keys, b"\x18\x18x");
+        });
+
+        let mut session = Docker::mock(mock.clone())
+            .containers()
+            .get("web")
+            .attach_with_opts(
+                &AttachOpts::builder()
+                    .stream(true)
+                    .logs(true)
+                    .stdin(true)
+                    .stdout(true)
+                    .detach_keys("ctrl-x,x")
+                    .build(),
+            )
+            .await
+            .unwrap();
+        let chunk = session.next().await.unwrap().unwrap();
+        assert!(matches!(chunk, TtyChunk::StdOut(data) if data == b"$ "));
+        session.write_all(b"\x18\x18x").await.unwrap();
+        assert!(session.next().await.is_none());
+        session
+    }
+
+    #[tokio::test]
+    async fn detaches_only_from_running_containers() {
+        let mock = Mock::new();
+        assert!(detach(&mock, true).await.detached());
+        let mut query: Vec<_> = mock.requests()[0]
+            .query
+            .as_deref()
+            .unwrap()
+            .split('&')
+            .map(str::to_string)
+            .collect();
+        query.sort_unstable();
+        assert_eq!(
+            query,
+            [
+                "detachKeys=ctrl-x%2Cx",
+                "logs=true",
+                "stdin=true",
+                "stdout=true",
+                "stream=true"
+            ]
+        );
+
+        // without a TTY the daemon ignores the keys and the stream ends with the container
+        assert!(!detach(&mock, false).await.detached());
+        assert_eq!(mock.pending(), 0);
+    }
+
+    #[tokio::test]
+    async fn decodes_log_lines_by_content_type_or_tty() {
+        use crate::api::{LogStream, LogsOpts};
+
+        let frames = || {
+            vec![
+                TtyChunk::StdOut(b"hello\n".to_vec()).encode(),
+                TtyChunk::StdErr(b"oops\n".to_vec()).encode(),
+            ]
+        };
+        async fn lines(docker: Docker) -> Vec<(LogStream, Vec<u8>)> {
+            docker
+                .containers()
+                .get("web")
+                .log_lines(&LogsOpts::builder().stdout(true).stderr(true).build())
+                .map_ok(|line| (line.stream, line.message))
+                .try_collect()
+                .await
+                .unwrap()
+        }
+        let demultiplexed = [
+            (LogStream::StdOut, b"hello".to_vec()),
+            (LogStream::StdErr, b"oops".to_vec()),
+        ];
+
+        // since API 1.42 the content type tells multiplexed and raw logs apart
+        let mock = Mock::new();
+        mock.on(Method::GET, "/containers/web/logs")
+            .header(
+                hyper::header::CONTENT_TYPE,
+                HeaderValue::from_static("application/vnd.docker.multiplexed-stream"),
+            )
+            .chunks(StatusCode::OK, frames());
+        mock.on(Method::GET, "/containers/web/logs")
+            .header(
+                hyper::header::CONTENT_TYPE,
+                HeaderValue::from_static("application/vnd.docker.raw-stream"),
+            )
+            .chunks(StatusCode::OK, vec!["$ ls\r\n", "$ "]);
+        let docker = Docker::builder()
+            .version(ApiVersion::new(1, 42))
+            .build_with_mock(mock.clone());
+        assert_eq!(lines(docker.clone()).await, demultiplexed);
+        assert_eq!(
+            lines(docker).await,
+            [
+                (LogStream::StdOut, b"$ ls".to_vec()),
+                (LogStream::StdOut, b"$ ".to_vec())
+            ]
+        );
+        assert_eq!(mock.requests().len(), 2);
+
+        // older versions send all logs as a raw stream, the TTY of the container decides
+        let mock = Mock::new();
+        mock.on(Method::GET, "/containers/web/logs")
+            .header(
+                hyper::header::CONTENT_TYPE,
+                HeaderValue::from_static("application/vnd.docker.raw-stream"),
+            )
+            .chunks(StatusCode::OK, frames());
+        mock.on(Method::GET, "/containers/web/json")
+            .json(StatusCode::OK, &details(false, false));
+        assert_eq!(lines(Docker::mock(mock.clone())).await, demultiplexed);
+        assert_eq!(mock.pending(), 0);
+    }
+}
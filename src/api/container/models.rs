@@ -650,6 +650,12 @@ pub struct ContainerCreateInfo {
     pub warnings: Option<Vec<String>>,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ContainerUpdateInfo {
+    pub warnings: Option<Vec<String>>,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "PascalCase")]
 pub struct Exit {
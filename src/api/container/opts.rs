@@ -12,7 +12,10 @@ use std::{
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Map, Value};
 
-use crate::{Error, Result};
+use crate::{
+    version::{ApiVersion, FieldVersions},
+    Error, Result,
+};
 
 pub enum Health {
     Starting,
@@ -137,6 +140,8 @@ impl ContainerListOptsBuilder {
 pub struct ContainerCreateOpts {
     name: Option<String>,
     params: HashMap<&'static str, Value>,
+    #[serde(skip)]
+    versions: FieldVersions,
 }
 
 /// Function to insert a JSON value into a tree where the desired
@@ -203,12 +208,24 @@ impl ContainerCreateOpts {
     pub(crate) fn name(&self) -> &Option<String> {
         &self.name
     }
+
+    /// Returns the names of the set options that are not available in API `version`.
+    ///
+    /// Only options added after API 1.24 declare a minimum version.
+    pub fn unsupported_fields(&self, version: ApiVersion) -> Vec<&'static str> {
+        self.versions.unsupported(version)
+    }
+
+    pub(crate) fn min_api_version(&self) -> Option<ApiVersion> {
+        self.versions.required()
+    }
 }
 
 #[derive(Default)]
 pub struct ContainerOptsBuilder {
     name: Option<String>,
     params: HashMap<&'static str, Value>,
+    versions: FieldVersions,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -309,7 +326,11 @@ impl ContainerOptsBuilder {
         let mut params = HashMap::new();
 
         params.insert("Image", Value::String(image.to_owned()));
-        ContainerOptsBuilder { name: None, params }
+        ContainerOptsBuilder {
+            name: None,
+            params,
+            versions: FieldVersions::default(),
+        }
     }
 
     /// Set the name of the container.
@@ -416,7 +437,7 @@ impl ContainerOptsBuilder {
         ///
         /// For example, setting `nano_cpus` to `500_000_000` results in the container being allocated
         /// 50% of a single CPU, while `2_000_000_000` results in the container being allocated 2 CPUs.
-        nano_cpus: u64 => "HostConfig.NanoCpus"
+        nano_cpus: u64 => "HostConfig.NanoCpus" since (1, 25)
     );
 
     /// CPU quota in units of CPUs. This is a wrapper around `nano_cpus` to do the unit conversion.
@@ -451,6 +472,25 @@ impl ContainerOptsBuilder {
     /// Whether standard streams should be attached to a TTY.
     tty: bool => "Tty");
 
+    impl_field!(
+    /// Whether to open `stdin`, needed to write to the container after attaching to it.
+    open_stdin: bool => "OpenStdin");
+
+    impl_field!(
+    /// Close `stdin` after the first attached client disconnects.
+    stdin_once: bool => "StdinOnce");
+
+    /// Initial size of the TTY of the container in characters.
+    ///
+    /// Requires API version 1.42 or newer.
+    pub fn console_size(mut self, height: u64, width: u64) -> Self {
+        self.params
+            .insert("HostConfig.ConsoleSize", json!([height, width]));
+        self.versions
+            .insert("HostConfig.ConsoleSize", ApiVersion::new(1, 42));
+        self
+    }
+
     impl_vec_field!(extra_hosts: H => "HostConfig.ExtraHosts");
 
     impl_vec_field!(volumes_from: V => "HostConfig.VolumesFrom");
@@ -484,7 +524,7 @@ impl ContainerOptsBuilder {
         self
     }
 
-    impl_field!(auto_remove: bool => "HostConfig.AutoRemove");
+    impl_field!(auto_remove: bool => "HostConfig.AutoRemove" since (1, 25));
 
     impl_str_field!(
     /// Signal to stop a container as a string. Default is \"SIGTERM\"
@@ -496,7 +536,7 @@ impl ContainerOptsBuilder {
 
     impl_field!(
     /// Timeout to stop a container. Only seconds are counted. Default is 10s
-    stop_timeout: Duration => "StopTimeout");
+    stop_timeout: Duration => "StopTimeout" since (1, 25));
 
     impl_str_field!(userns_mode: M => "HostConfig.UsernsMode");
 
@@ -508,6 +548,7 @@ impl ContainerOptsBuilder {
         ContainerCreateOpts {
             name: self.name.clone(),
             params: self.params.clone(),
+            versions: self.versions.clone(),
         }
     }
 }
@@ -531,6 +572,140 @@ impl RmContainerOptsBuilder {
     );
 }
 
+impl_opts_builder!(json => ContainerUpdate);
+
+impl ContainerUpdateOptsBuilder {
+    impl_field!(
+    /// An integer value representing the container's relative CPU weight versus other containers.
+    cpu_shares: u32 => "CpuShares");
+
+    impl_field!(
+    /// The length of a CPU period in microseconds.
+    cpu_period: u64 => "CpuPeriod");
+
+    impl_field!(
+    /// Microseconds of CPU time that the container can get in a CPU period.
+    cpu_quota: i64 => "CpuQuota");
+
+    impl_field!(
+    /// CPU quota in units of 10<sup>-9</sup> CPUs.
+    nano_cpus: u64 => "NanoCpus");
+
+    impl_str_field!(
+    /// CPUs in which to allow execution, for example `0-3` or `0,1`.
+    cpuset_cpus: C => "CpusetCpus");
+
+    impl_str_field!(
+    /// Memory nodes (MEMs) in which to allow execution, for example `0-3` or `0,1`. Only effective
+    /// on NUMA systems.
+    cpuset_mems: M => "CpusetMems");
+
+    impl_field!(
+    /// Memory limit in bytes.
+    memory: u64 => "Memory");
+
+    impl_field!(
+    /// Memory soft limit in bytes.
+    memory_reservation: u64 => "MemoryReservation");
+
+    impl_field!(
+    /// Total memory limit (memory + swap) in bytes. Set to -1 to enable unlimited swap.
+    memory_swap: i64 => "MemorySwap");
+
+    impl_field!(
+    /// Kernel memory limit in bytes.
+    kernel_memory: i64 => "KernelMemory");
+
+    impl_field!(
+    /// Tune the container's PIDs limit. Set to 0 or -1 for unlimited.
+    pids_limit: i64 => "PidsLimit" since (1, 40));
+
+    impl_field!(
+    /// Block IO weight (relative weight) between 10 and 1000, 0 disables it.
+    blkio_weight: u16 => "BlkioWeight");
+
+    /// Block IO weight (relative device weight) of devices given as pairs of a device path and a
+    /// weight.
+    pub fn blkio_weight_devices<D, P>(mut self, devices: D) -> Self
+    where
+        D: IntoIterator<Item = (P, u16)>,
+        P: AsRef<str>,
+    {
+        self.params.insert(
+            "BlkioWeightDevice",
+            devices
+                .into_iter()
+                .map(|(path, weight)| json!({"Path": path.as_ref(), "Weight": weight}))
+                .collect(),
+        );
+        self
+    }
+
+    /// The behavior to apply when the container exits, one of `no`, `always`, `unless-stopped` or
+    /// `on-failure`. `maximum_retry_count` is only used by `on-failure`.
+    pub fn restart_policy(mut self, name: &str, maximum_retry_count: u64) -> Self {
+        let mut policy = json!({ "Name": name });
+        if name == "on-failure" {
+            policy["MaximumRetryCount"] = json!(maximum_retry_count);
+        }
+        self.params.insert("RestartPolicy", policy);
+        self
+    }
+}
+
+impl_opts_builder!(url => Attach);
+
+impl AttachOpts {
+    pub(crate) fn detach_keys(&self) -> Option<&str> {
+        self.params.get("detachKeys").map(String::as_str)
+    }
+}
+
+impl AttachOptsBuilder {
+    impl_url_bool_field!(
+        /// Stream output produced after attaching. Without it only the replayed logs are returned.
+        stream => "stream"
+    );
+
+    impl_url_bool_field!(
+        /// Replay the output the container produced before attaching.
+        logs => "logs"
+    );
+
+    impl_url_bool_field!(
+        /// Attach to `stdin`.
+        stdin => "stdin"
+    );
+
+    impl_url_bool_field!(
+        /// Attach to `stdout`.
+        stdout => "stdout"
+    );
+
+    impl_url_bool_field!(
+        /// Attach to `stderr`.
+        stderr => "stderr"
+    );
+
+    impl_url_str_field!(
+        /// Override the key sequence for detaching a container. Format is a single
+        /// character [a-Z] or ctrl-<value> where <value> is one of: a-z, @, ^, [, , or _.
+        detach_keys: K => "detachKeys"
+    );
+}
+
+impl_opts_builder!(url => ContainerResize);
+
+impl ContainerResizeOptsBuilder {
+    impl_url_field!(
+    /// Height of the TTY session in characters.
+    height: u64 => "h");
+
+    impl_url_field!(
+    /// Width of the TTY session in characters.
+    width: u64 => "w");
+}
+
 impl_opts_builder!(url => ContainerPrune);
 
 pub enum ContainerPruneFilter {
@@ -577,6 +752,19 @@ mod tests {
         };
     }
 
+    #[test]
+    fn update_container_opts() {
+        test_case!(
+            ContainerUpdateOptsBuilder::default().restart_policy("on-failure", 3),
+            r#"{"RestartPolicy":{"MaximumRetryCount":3,"Name":"on-failure"}}"#
+        );
+
+        test_case!(
+            ContainerUpdateOptsBuilder::default().blkio_weight_devices(vec![("/dev/sda", 200)]),
+            r#"{"BlkioWeightDevice":[{"Path":"/dev/sda","Weight":200}]}"#
+        );
+    }
+
     #[test]
     fn create_container_opts() {
         test_case!(
@@ -647,9 +835,48 @@ mod tests {
             r#"{"HostConfig":{"RestartPolicy":{"MaximumRetryCount":10,"Name":"on-failure"}},"Image":"test_image"}"#
         );
 
+        test_case!(
+            ContainerOptsBuilder::new("test_image").console_size(24, 80),
+            r#"{"HostConfig":{"ConsoleSize":[24,80]},"Image":"test_image"}"#
+        );
+
         test_case!(
             ContainerOptsBuilder::new("test_image").restart_policy("always", 0),
             r#"{"HostConfig":{"RestartPolicy":{"Name":"always"}},"Image":"test_image"}"#
         );
     }
+
+    /// Test that options newer than the API version are reported
+    #[test]
+    fn create_container_opts_unsupported_fields() {
+        let opts = ContainerOptsBuilder::new("test_image")
+            .auto_remove(true)
+            .privileged(true)
+            .build();
+        assert_eq!(opts.min_api_version(), Some(ApiVersion::new(1, 25)));
+        assert_eq!(
+            opts.unsupported_fields(ApiVersion::new(1, 24)),
+            vec!["HostConfig.AutoRemove"]
+        );
+        assert!(opts.unsupported_fields(ApiVersion::new(1, 25)).is_empty());
+
+        let opts = ContainerOptsBuilder::new("test_image")
+            .tty(true)
+            .console_size(24, 80)
+            .build();
+        assert_eq!(opts.min_api_version(), Some(ApiVersion::new(1, 42)));
+        assert_eq!(
+            opts.unsupported_fields(ApiVersion::new(1, 41)),
+            vec!["HostConfig.ConsoleSize"]
+        );
+    }
+
+    #[test]
+    fn update_container_opts_unsupported_fields() {
+        let opts = ContainerUpdateOptsBuilder::default().pids_limit(10).build();
+        assert_eq!(
+            opts.unsupported_fields(ApiVersion::new(1, 39)),
+            vec!["PidsLimit"]
+        );
+    }
 }
@@ -6,6 +6,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     conn::{tty, Headers, Payload},
+    version::Endpoint,
     Docker, Result,
 };
 
@@ -15,13 +16,13 @@ pub type ExecIdRef<'a> = &'a str;
 api_doc! { Exec
 /// Interface for docker exec instance
 |
-pub struct Exec<'docker> {
-    docker: &'docker Docker,
+pub struct Exec {
+    docker: Docker,
     id: ExecId,
 }}
 
-impl<'docker> Exec<'docker> {
-    fn new<ID>(docker: &'docker Docker, id: ID) -> Self
+impl Exec {
+    fn new<ID>(docker: Docker, id: ID) -> Self
     where
         ID: Into<ExecId>,
     {
@@ -39,10 +40,10 @@ impl<'docker> Exec<'docker> {
     /// Creates a new exec instance that will be executed in a container with id == container_id.
     |
     pub async fn create<C>(
-        docker: &'docker Docker,
+        docker: Docker,
         container_id: C,
         opts: &ExecContainerOpts,
-    ) -> Result<Exec<'docker>>
+    ) -> Result<Exec>
     where
         C: AsRef<str>,
     {
@@ -52,11 +53,10 @@ impl<'docker> Exec<'docker> {
             id: String,
         }
 
+        let ep = Endpoint::from(format!("/containers/{}/exec", container_id.as_ref()))
+            .since(opts.min_api_version());
         docker
-            .post_json(
-                &format!("/containers/{}/exec", container_id.as_ref()),
-                Payload::Json(opts.serialize()?),
-            )
+            .post_json(ep, Payload::Json(opts.serialize()?))
             .await
             .map(|resp: Response| Exec::new(docker, resp.id))
     }}
@@ -72,14 +72,11 @@ impl<'docker> Exec<'docker> {
     // into the stream and have the lifetimes work out as you would expect.
     //
     // Yes, it is sad that we can't do the easy method and thus have some duplicated code.
-    pub(crate) fn create_and_start<C>(
-        docker: &'docker Docker,
-        container_id: C,
+    pub(crate) fn create_and_start(
+        docker: Docker,
+        container_id: &str,
         opts: &ExecContainerOpts,
-    ) -> impl Stream<Item = Result<tty::TtyChunk>> + Unpin + 'docker
-    where
-        C: AsRef<str>,
-    {
+    ) -> impl Stream<Item = Result<tty::TtyChunk>> + Unpin {
         #[derive(serde::Deserialize)]
         #[serde(rename_all = "PascalCase")]
         struct Response {
@@ -93,12 +90,13 @@ impl<'docker> Exec<'docker> {
 
         // To not tie the lifetime of `container_id` to the stream, we convert it to an (owned)
         // endpoint outside of the stream.
-        let container_endpoint = format!("/containers/{}/exec", container_id.as_ref());
+        let container_endpoint = Endpoint::from(format!("/containers/{}/exec", container_id))
+            .since(opts.min_api_version());
 
         Box::pin(
             async move {
                 let exec_id = docker
-                    .post_json(&container_endpoint, Payload::Json(body_result?))
+                    .post_json(container_endpoint, Payload::Json(body_result?))
                     .await
                     .map(|resp: Response| resp.id)?;
 
@@ -119,7 +117,7 @@ impl<'docker> Exec<'docker> {
     /// It's in callers responsibility to ensure that exec instance with specified id actually
     /// exists. Use [Exec::create](Exec::create) to ensure that the exec instance is created
     /// beforehand.
-    pub fn get<ID>(docker: &'docker Docker, id: ID) -> Exec<'docker>
+    pub fn get<ID>(docker: Docker, id: ID) -> Exec
     where
         ID: Into<ExecId>,
     {
@@ -129,10 +127,10 @@ impl<'docker> Exec<'docker> {
     api_doc! { Exec => Start
     /// Starts this exec instance returning a multiplexed tty stream.
     |
-    pub fn start(&self) -> impl Stream<Item = Result<tty::TtyChunk>> + 'docker {
-        // We must take ownership of the docker reference to not needlessly tie the stream to the
+    pub fn start(&self) -> impl Stream<Item = Result<tty::TtyChunk>> {
+        // We clone the docker handle to not needlessly tie the stream to the
         // lifetime of `self`.
-        let docker = self.docker;
+        let docker = self.docker.clone();
         // We convert `self.id` into the (owned) endpoint outside of the stream to not needlessly
         // tie the stream to the lifetime of `self`.
         let endpoint = format!("/exec/{}/start", &self.id);
@@ -170,7 +168,7 @@ impl ExecContainerOptsBuilder {
 
     impl_vec_field!(
         /// A list of environment variables in the form 'VAR=value'.
-        env: E => "Env"
+        env: E => "Env" since (1, 25)
     );
 
     impl_field!(
@@ -207,7 +205,7 @@ impl ExecContainerOptsBuilder {
 
     impl_str_field!(
         /// The working directory for the exec process inside the container.
-        working_dir: D => "WorkingDir"
+        working_dir: D => "WorkingDir" since (1, 35)
     );
 }
 
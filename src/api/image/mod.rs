@@ -5,24 +5,27 @@ pub mod opts;
 pub use models::*;
 pub use opts::*;
 
-use std::io::Read;
+use std::io::{self, Read};
 
-use futures_util::{stream::Stream, TryFutureExt, TryStreamExt};
+use bytes::Bytes;
+use futures_util::{io::AsyncRead, stream::Stream, TryFutureExt, TryStreamExt};
+use hyper::Body;
 
 use crate::{
     conn::{Headers, Payload, AUTH_HEADER},
     util::{
-        tarball,
+        body, tarball,
         url::{construct_ep, encoded_pair, encoded_pairs},
     },
-    Result,
+    version::Endpoint,
+    ApiVersion, Result,
 };
 
 impl_api_ty!(Image => name);
 
 pub type DeleteStatus = Vec<Status>;
 
-impl<'docker> Image<'docker> {
+impl Image {
     impl_api_ep! {img: Image, resp
         Inspect -> &format!("/images/{}/json", img.name), Details
         DeleteWithOpts -> &format!("/images/{}", img.name), DeleteStatus, delete_json
@@ -40,7 +43,7 @@ impl<'docker> Image<'docker> {
     api_doc! { Image => Get
     /// Export this image to a tarball.
     |
-    pub fn export(&self) -> impl Stream<Item = Result<Vec<u8>>> + Unpin + 'docker {
+    pub fn export(&self) -> impl Stream<Item = Result<Vec<u8>>> + Unpin {
         Box::pin(
             self.docker
                 .stream_get(format!("/images/{}/get", self.name))
@@ -73,50 +76,57 @@ impl<'docker> Image<'docker> {
             .map(|_| ())
     }}
 
-    api_doc! { Distribution => Inspect
+    api_doc! { Distribution => Inspect since (1, 30)
     /// Return image digest and platform information by contacting the registry.
     |
     pub async fn distribution_inspect(&self) -> Result<DistributionInspectInfo> {
-        self.docker
-            .post_json(
-                &format!("/distribution/{}/json", self.name),
-                Payload::empty(),
-            )
-            .await
+        let ep = Endpoint::from(format!("/distribution/{}/json", self.name))
+            .since(ApiVersion::new(1, 30));
+        self.docker.post_json(ep, Payload::empty()).await
     }}
 }
 
-impl<'docker> Images<'docker> {
+impl Images {
     impl_api_ep! {img: Image, resp
         List -> "/images/json"
-        Prune ->  "/images/prune"
+        Prune since (1, 25) -> "/images/prune"
     }
 
     api_doc! { Image => Build
     /// Builds a new image build by reading a Dockerfile in a target directory.
+    ///
+    /// The directory is tarred and gzipped while it's being sent so the size of the build context
+    /// doesn't affect memory usage. The tarball is written on a blocking task of the configured
+    /// [`Runtime`](crate::conn::Runtime), one per build.
     |
     pub fn build(
         &self,
         opts: &BuildOpts,
-    ) -> impl Stream<Item = Result<ImageBuildChunk>> + Unpin + 'docker {
-        let ep = construct_ep("/build", opts.serialize());
+    ) -> impl Stream<Item = Result<ImageBuildChunk>> + Unpin {
+        let ep = Endpoint::from(construct_ep("/build", opts.serialize()))
+            .since(opts.min_api_version());
 
-        // To not tie the lifetime of `opts` to the 'stream, we do the tarring work outside of the
-        // stream. But for backwards compatability, we have to return the error inside of the
-        // stream.
-        let mut bytes = Vec::default();
-        let tar_result = tarball::dir(&mut bytes, &opts.path);
+        // The context is tarred on a blocking task of the runtime while the request is sent. A
+        // missing context is still detected before sending anything, but for backwards
+        // compatability, we have to return the error inside of the stream.
+        let path = opts.path.clone();
+        let path_result = path.canonicalize();
+        let runtime = self.docker.runtime();
+        let context = move || body::from_writer(runtime, move |buf| tarball::dir(buf, path));
 
-        // We must take ownership of the Docker reference. If we don't then the lifetime of 'stream
+        // We clone the Docker handle. If we don't then the lifetime of 'stream
         // is incorrectly tied to `self`.
-        let docker = self.docker;
+        let docker = self.docker.clone();
         Box::pin(
             async move {
                 // Bubble up error inside the stream for backwards compatability
-                tar_result?;
+                path_result?;
 
-                let value_stream =
-                    docker.stream_post_into(ep, Payload::Tar(bytes), Headers::none());
+                let value_stream = docker.stream_post_into(
+                    ep,
+                    Payload::Tar(Body::wrap_stream(context())),
+                    Headers::none(),
+                );
 
                 Ok(value_stream)
             }
@@ -142,7 +152,7 @@ impl<'docker> Images<'docker> {
     pub fn pull(
         &self,
         opts: &PullOpts,
-    ) -> impl Stream<Item = Result<ImageBuildChunk>> + Unpin + 'docker {
+    ) -> impl Stream<Item = Result<ImageBuildChunk>> + Unpin {
         let headers = opts
             .auth_header()
             .map(|a| Headers::single(AUTH_HEADER, a));
@@ -158,7 +168,7 @@ impl<'docker> Images<'docker> {
     /// Exports a collection of named images,
     /// either by name, name:tag, or image id, into a tarball.
     |
-    pub fn export(&self, names: Vec<&str>) -> impl Stream<Item = Result<Vec<u8>>> + 'docker {
+    pub fn export(&self, names: Vec<&str>) -> impl Stream<Item = Result<Vec<u8>>> {
         self.docker
             .stream_get(format!(
                 "/images/get?{}",
@@ -170,36 +180,64 @@ impl<'docker> Images<'docker> {
     api_doc! { Image => Load
     /// Imports an image or set of images from a given tarball source.
     /// Source can be uncompressed on compressed via gzip, bzip2 or xz.
+    ///
+    /// The tarball is read on a blocking task of the configured [`Runtime`](crate::conn::Runtime)
+    /// while it's being sent, one per import, which is why it has to be `'static`. This is a wrapper of [`Images::import_stream`](Images::import_stream), use it
+    /// directly to import from a stream of chunks or [`Images::import_reader`](Images::import_reader)
+    /// for an asynchronous reader.
     |
     pub fn import<R>(
         self,
         mut tarball: R,
-    ) -> impl Stream<Item = Result<ImageBuildChunk>> + Unpin + 'docker
+    ) -> impl Stream<Item = Result<ImageBuildChunk>> + Unpin
     where
-        R: Read + Send + 'docker,
+        R: Read + Send + 'static,
     {
-        Box::pin(
-            async move {
-                let mut bytes = Vec::default();
+        self.import_stream(body::from_writer(self.docker.runtime(), move |buf| {
+            io::copy(&mut tarball, buf).map(|_| ())
+        }))
+    }}
 
-                tarball.read_to_end(&mut bytes)?;
+    api_doc! { Image => Load
+    /// Imports an image or set of images from a tarball read from an asynchronous `reader`.
+    /// Source can be uncompressed on compressed via gzip, bzip2 or xz.
+    ///
+    /// The tarball is read in chunks while it's being sent, see
+    /// [`Images::import_stream`](Images::import_stream).
+    |
+    pub fn import_reader<R>(&self, reader: R) -> impl Stream<Item = Result<ImageBuildChunk>> + Unpin
+    where
+        R: AsyncRead + Send + Unpin + 'static,
+    {
+        self.import_stream(body::from_reader(reader))
+    }}
 
-                let value_stream = self.docker.stream_post_into(
-                    "/images/load",
-                    Payload::Tar(bytes),
-                    Headers::none(),
-                );
-                Ok(value_stream)
-            }
-            .try_flatten_stream(),
-        )
+    api_doc! { Image => Load
+    /// Imports an image or set of images from a tarball streamed in chunks.
+    /// Source can be uncompressed on compressed via gzip, bzip2 or xz.
+    ///
+    /// This is the streaming entry point [`Images::import`](Images::import) and
+    /// [`Images::import_reader`](Images::import_reader) are built on. Each chunk is sent as soon
+    /// as the stream yields it, so memory use doesn't depend on the size of the tarball. An error
+    /// in the stream aborts the request.
+    |
+    pub fn import_stream<S, E>(&self, stream: S) -> impl Stream<Item = Result<ImageBuildChunk>> + Unpin
+    where
+        S: Stream<Item = std::result::Result<Bytes, E>> + Send + 'static,
+        E: Into<Box<dyn std::error::Error + Send + Sync>> + 'static,
+    {
+        Box::pin(self.docker.stream_post_into(
+            "/images/load",
+            Payload::Tar(Body::wrap_stream(stream)),
+            Headers::none(),
+        ))
     }}
 
     api_doc! { Image => Push
     /// Push an image to registry.
     |
     pub async fn push(&self, name: impl Into<String>, opts: &ImagePushOpts) -> Result<()> {
-        let image = Image::new(self.docker, name);
+        let image = Image::new(self.docker.clone(), name);
         image.push(opts).await
     }}
 
@@ -207,12 +245,144 @@ impl<'docker> Images<'docker> {
     // /// Clear image build cache.
     // |
     pub async fn clear_cache(&self, opts: &ClearCacheOpts) -> Result<ClearCacheInfo> {
-        self.docker
-            .post_json(
-                construct_ep("/build/prune", opts.serialize()),
-                Payload::empty(),
-            )
-            .await
+        let ep = Endpoint::from(construct_ep("/build/prune", opts.serialize()))
+            .since(ApiVersion::new(1, 31))
+            .since(opts.min_api_version());
+        self.docker.post_json(ep, Payload::empty()).await
     }
     // }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{conn::Mock, Docker};
+
+    use hyper::{Method, StatusCode};
+
+    #[tokio::test]
+    async fn streams_build_context_and_imported_tarballs() {
+        let context = std::env::temp_dir().join(format!("docker-api-build-{}", std::process::id()));
+        std::fs::create_dir_all(&context).unwrap();
+        std::fs::write(context.join("Dockerfile"), "FROM ubuntu\n").unwrap();
+
+        let mock = Mock::new();
+        mock.on(Method::POST, "/build").chunks(
+            StatusCode::OK,
+            vec![r#"{"stream":"Step 1/1 : FROM ubuntu"}"#],
+        );
+        mock.on(Method::POST, "/images/load")
+            .chunks(StatusCode::OK, vec![r#"{"stream":"Loaded image: ubuntu"}"#]);
+        let docker = Docker::mock(mock.clone());
+
+        let build: Vec<_> = docker
+            .images()
+            .build(&BuildOpts::builder(&context).build())
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(build.len(), 1);
+        let load: Vec<_> = docker
+            .images()
+            .import_reader(futures_util::io::Cursor::new(b"image tarball".to_vec()))
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(load.len(), 1);
+        std::fs::remove_dir_all(&context).unwrap();
+
+        let requests = mock.requests();
+        let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(&requests[0].body[..]));
+        let mut entries = archive.entries().unwrap();
+        let mut dockerfile = entries.next().unwrap().unwrap();
+        assert_eq!(dockerfile.path().unwrap().to_str(), Some("Dockerfile"));
+        let mut content = String::new();
+        dockerfile.read_to_string(&mut content).unwrap();
+        assert_eq!(content, "FROM ubuntu\n");
+        assert_eq!(&requests[1].body[..], b"image tarball");
+    }
+
+    #[tokio::test]
+    async fn fails_import_when_reading_the_tarball_panics() {
+        struct Panicking;
+
+        impl Read for Panicking {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                panic!("corrupted tarball")
+            }
+        }
+
+        let mock = Mock::new();
+        mock.on(Method::POST, "/images/load")
+            .chunks(StatusCode::OK, vec![r#"{"stream":"Loaded image: ubuntu"}"#]);
+        let docker = Docker::mock(mock.clone());
+
+        let result: Result<Vec<_>> = docker.images().import(Panicking).try_collect().await;
+        assert!(result.is_err(), "{:?}", result);
+    }
+
+    #[cfg(feature = "runtime-tokio")]
+    #[tokio::test]
+    async fn writes_bodies_on_blocking_tasks_of_the_runtime() {
+        use crate::conn::{Connection, Runtime, Tokio};
+        use futures_util::future::BoxFuture;
+        use std::{
+            sync::atomic::{AtomicUsize, Ordering},
+            time::Duration,
+        };
+
+        static BLOCKING_TASKS: AtomicUsize = AtomicUsize::new(0);
+
+        #[derive(Debug)]
+        struct Counting;
+
+        impl Runtime for Counting {
+            fn spawn(&self, task: BoxFuture<'static, ()>) {
+                Tokio.spawn(task)
+            }
+
+            fn spawn_blocking(&self, task: Box<dyn FnOnce() + Send>) {
+                BLOCKING_TASKS.fetch_add(1, Ordering::SeqCst);
+                Tokio.spawn_blocking(task)
+            }
+
+            fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+                Tokio.sleep(duration)
+            }
+
+            fn connect_tcp(
+                &self,
+                host: &str,
+                port: u16,
+                keepalive: Option<Duration>,
+            ) -> BoxFuture<'static, io::Result<Box<dyn Connection>>> {
+                Tokio.connect_tcp(host, port, keepalive)
+            }
+
+            #[cfg(unix)]
+            fn connect_unix(
+                &self,
+                path: &std::path::Path,
+            ) -> BoxFuture<'static, io::Result<Box<dyn Connection>>> {
+                Tokio.connect_unix(path)
+            }
+        }
+
+        let mock = Mock::new();
+        mock.on(Method::POST, "/images/load")
+            .chunks(StatusCode::OK, vec![r#"{"stream":"Loaded image: ubuntu"}"#]);
+        let docker = Docker::builder()
+            .runtime(Counting)
+            .build_with_mock(mock.clone());
+
+        let import = docker
+            .images()
+            .import(io::Cursor::new(b"image tarball".to_vec()));
+        // the task is only spawned once the body is sent
+        assert_eq!(BLOCKING_TASKS.load(Ordering::SeqCst), 0);
+        let load: Vec<_> = import.try_collect().await.unwrap();
+        assert_eq!(load.len(), 1);
+        assert_eq!(BLOCKING_TASKS.load(Ordering::SeqCst), 1);
+        assert_eq!(&mock.requests()[0].body[..], b"image tarball");
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{api::Filter, util::url::encoded_pairs};
+use crate::{api::Filter, util::url::encoded_pairs, version::FieldVersions, ApiVersion};
 
 use std::{
     collections::HashMap,
@@ -202,6 +202,7 @@ impl PullOptsBuilder {
 pub struct BuildOpts {
     pub path: PathBuf,
     params: HashMap<&'static str, String>,
+    versions: FieldVersions,
 }
 
 impl BuildOpts {
@@ -223,12 +224,24 @@ impl BuildOpts {
             Some(encoded_pairs(&self.params))
         }
     }
+
+    /// Returns the names of the set options that are not available in API `version`.
+    ///
+    /// Only options added after API 1.24 declare a minimum version.
+    pub fn unsupported_fields(&self, version: ApiVersion) -> Vec<&'static str> {
+        self.versions.unsupported(version)
+    }
+
+    pub(crate) fn min_api_version(&self) -> Option<ApiVersion> {
+        self.versions.required()
+    }
 }
 
 #[derive(Default)]
 pub struct BuildOptsBuilder {
     path: PathBuf,
     params: HashMap<&'static str, String>,
+    versions: FieldVersions,
 }
 
 impl BuildOptsBuilder {
@@ -319,28 +332,28 @@ impl BuildOptsBuilder {
 
     impl_url_bool_field!(
         /// Squash the resulting images layers into a single layer. (Experimental release only.)
-        squash => "squash"
+        squash => "squash" since (1, 25)
     );
 
     // TODO: use an enum?
     impl_url_str_field!(
         /// bridge`, `host`, `none`, `container:<name|id>`, or a custom network name.
-        network_mode: M => "networkmode"
+        network_mode: M => "networkmode" since (1, 25)
     );
 
     impl_url_str_field!(
         /// Platform in the format os[/arch[/variant]].
-        platform: P => "platform"
+        platform: P => "platform" since (1, 32)
     );
 
     impl_url_str_field!(
         /// Target build stage.
-        target: T => "target"
+        target: T => "target" since (1, 29)
     );
 
     impl_url_str_field!(
         /// BuildKit output configuration.
-        outputs: C => "outputs"
+        outputs: C => "outputs" since (1, 40)
     );
 
     impl_map_field!(url
@@ -352,6 +365,7 @@ impl BuildOptsBuilder {
         BuildOpts {
             path: self.path.clone(),
             params: self.params.clone(),
+            versions: self.versions.clone(),
         }
     }
 }
@@ -540,11 +554,11 @@ impl Filter for CacheFilter {
 impl ClearCacheOptsBuilder {
     impl_url_field!(
         /// Amount of disk space in bytes to keep for cache.
-        keep_storage: i64 => "keep-storage"
+        keep_storage: i64 => "keep-storage" since (1, 39)
     );
     impl_url_bool_field!(
         /// Remove all types of build cache
-        all => "all"
+        all => "all" since (1, 39)
     );
     impl_filter_func!(
         /// Filter the builder cache with variants of the enum.
@@ -651,4 +665,20 @@ mod tests {
             opts.serialize()
         );
     }
+
+    /// Test that options newer than the API version are reported
+    #[test]
+    fn build_opts_unsupported_fields() {
+        let opts = BuildOpts::builder(".")
+            .tag("test")
+            .target("base")
+            .platform("linux/amd64")
+            .build();
+        assert_eq!(opts.min_api_version(), Some(ApiVersion::new(1, 32)));
+        assert_eq!(
+            opts.unsupported_fields(ApiVersion::new(1, 30)),
+            vec!["platform"]
+        );
+        assert!(opts.unsupported_fields(ApiVersion::new(1, 41)).is_empty());
+    }
 }
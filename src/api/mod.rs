@@ -5,6 +5,7 @@ pub mod exec;
 pub mod image;
 pub mod network;
 pub mod system;
+pub mod traits;
 pub mod volume;
 
 #[cfg(feature = "swarm")]
@@ -29,7 +30,7 @@ pub mod swarm;
 #[cfg_attr(docsrs, doc(cfg(feature = "swarm")))]
 pub mod task;
 
-pub use {common::*, container::*, exec::*, image::*, network::*, system::*, volume::*};
+pub use {common::*, container::*, exec::*, image::*, network::*, system::*, traits::*, volume::*};
 
 #[cfg(feature = "swarm")]
 #[cfg_attr(docsrs, doc(cfg(feature = "swarm")))]
@@ -6,14 +6,14 @@ pub mod opts;
 pub use models::*;
 pub use opts::*;
 
-use crate::{conn::Payload, Result};
+use crate::{conn::Payload, version::Endpoint, Result};
 
 impl_api_ty!(Network => id);
 
-impl<'docker> Network<'docker> {
+impl Network {
     impl_api_ep! { net: Network, resp
         Inspect -> &format!("/networks/{}", net.id)
-        Delete -> &format!("/network/{}", net.id)
+        Delete -> &format!("/networks/{}", net.id)
     }
 
     api_doc! { Network => Connect
@@ -44,10 +44,20 @@ impl<'docker> Network<'docker> {
     }
 }
 
-impl<'docker> Networks<'docker> {
+impl Networks {
     impl_api_ep! { __: Network, resp
         List -> "/networks"
-        Create -> "/networks/create", resp.id
-        Prune -> "/networks/prune"
+        Prune since (1, 25) -> "/networks/prune"
     }
+
+    api_doc! { Network => Create
+    /// Create a new Network.
+    |
+    pub async fn create(&self, opts: &NetworkCreateOpts) -> Result<Network> {
+        let ep = Endpoint::from("/networks/create").since(opts.min_api_version());
+        self.docker
+            .post_json(ep, Payload::Json(opts.serialize()?))
+            .await
+            .map(|resp: NetworkCreateInfo| Network::new(self.docker.clone(), resp.id))
+    }}
 }
@@ -1,4 +1,8 @@
-use crate::{api::Filter, Error, Result};
+use crate::{
+    api::Filter,
+    version::{ApiVersion, FieldVersions},
+    Error, Result,
+};
 
 use std::{collections::HashMap, convert::AsRef};
 
@@ -88,6 +92,8 @@ impl NetworkListOptsBuilder {
 #[derive(Serialize, Debug)]
 pub struct NetworkCreateOpts {
     params: HashMap<&'static str, Value>,
+    #[serde(skip)]
+    versions: FieldVersions,
 }
 
 impl NetworkCreateOpts {
@@ -103,18 +109,33 @@ impl NetworkCreateOpts {
     pub fn serialize(&self) -> Result<String> {
         serde_json::to_string(&self.params).map_err(Error::from)
     }
+
+    /// Returns the names of the set options that are not available in API `version`.
+    ///
+    /// Only options added after API 1.24 declare a minimum version.
+    pub fn unsupported_fields(&self, version: ApiVersion) -> Vec<&'static str> {
+        self.versions.unsupported(version)
+    }
+
+    pub(crate) fn min_api_version(&self) -> Option<ApiVersion> {
+        self.versions.required()
+    }
 }
 
 #[derive(Default)]
 pub struct NetworkCreateOptsBuilder {
     params: HashMap<&'static str, Value>,
+    versions: FieldVersions,
 }
 
 impl NetworkCreateOptsBuilder {
     pub(crate) fn new(name: &str) -> Self {
         let mut params = HashMap::new();
         params.insert("Name", json!(name));
-        NetworkCreateOptsBuilder { params }
+        NetworkCreateOptsBuilder {
+            params,
+            versions: FieldVersions::default(),
+        }
     }
 
     impl_field!(
@@ -139,12 +160,12 @@ impl NetworkCreateOptsBuilder {
     impl_field!(
         /// Globally scoped network is manually attachable by regular containers from workers
         /// in swarm mode.
-        attachable: bool => "Attachable"
+        attachable: bool => "Attachable" since (1, 25)
     );
 
     impl_field!(
         /// Ingress network is the network which provides the routing-mesh in swarm mode.
-        ingress: bool => "Ingress"
+        ingress: bool => "Ingress" since (1, 29)
     );
 
     impl_field!(
@@ -167,6 +188,7 @@ impl NetworkCreateOptsBuilder {
     pub fn build(&self) -> NetworkCreateOpts {
         NetworkCreateOpts {
             params: self.params.clone(),
+            versions: self.versions.clone(),
         }
     }
 }
@@ -9,6 +9,7 @@ pub use opts::*;
 use crate::{
     conn::Payload,
     util::url::{construct_ep, encoded_pair},
+    version::{ApiVersion, Endpoint},
     Result,
 };
 
@@ -16,47 +17,56 @@ use std::path::Path;
 
 impl_api_ty!(Plugin => name);
 
-impl<'docker> Plugin<'docker> {
+impl Plugin {
     impl_api_ep! {plug: Plugin, resp
-        Inspect -> &format!("/plugins/{}/json", plug.name)
-        ForceDelete -> &format!("/plugins/{}", plug.name), PluginInfo
+        Inspect since (1, 25) -> &format!("/plugins/{}/json", plug.name)
+        ForceDelete since (1, 25) -> &format!("/plugins/{}", plug.name), PluginInfo
     }
 
-    api_doc! { Plugin => Enable
+    api_doc! { Plugin => Enable since (1, 25)
     /// Enable a plugin.
     |
     pub async fn enable(&self, timeout: Option<u64>) -> Result<()> {
         let query = timeout.map(|timeout| encoded_pair("timeout", timeout));
         self.docker
             .post(
-                &construct_ep(format!("/plugins/{}/enable", self.name), query),
+                Endpoint::from(construct_ep(format!("/plugins/{}/enable", self.name), query))
+                    .since(ApiVersion::new(1, 25)),
                 Payload::empty(),
             )
             .await
             .map(|_| ())
     }}
 
-    api_doc! { Plugin => Disable
+    api_doc! { Plugin => Disable since (1, 25)
     /// Disable a plugin.
     |
     pub async fn disable(&self) -> Result<()> {
         self.docker
-            .post(&format!("/plugins/{}/disable", self.name), Payload::empty())
+            .post(
+                Endpoint::from(format!("/plugins/{}/disable", self.name))
+                    .since(ApiVersion::new(1, 25)),
+                Payload::empty(),
+            )
             .await
             .map(|_| ())
     }}
 
-    api_doc! { Plugin => Push
+    api_doc! { Plugin => Push since (1, 25)
     /// Push a plugin to the registry.
     |
     pub async fn push(&self) -> Result<()> {
         self.docker
-            .post(&format!("/plugins/{}/push", self.name), Payload::empty())
+            .post(
+                Endpoint::from(format!("/plugins/{}/push", self.name))
+                    .since(ApiVersion::new(1, 25)),
+                Payload::empty(),
+            )
             .await
             .map(|_| ())
     }}
 
-    api_doc! { Plugin => Create
+    api_doc! { Plugin => Create since (1, 25)
     /// Create a plugin from a tar archive on the file system. The `path` parameter is a path
     /// to the tar containing plugin rootfs and manifest.
     |
@@ -66,7 +76,8 @@ impl<'docker> Plugin<'docker> {
     {
         self.docker
             .post(
-                &format!("/plugins/{}/create", self.name),
+                Endpoint::from(format!("/plugins/{}/create", self.name))
+                    .since(ApiVersion::new(1, 25)),
                 Payload::Text(path.as_ref().to_string_lossy().to_string()),
             )
             .await
@@ -74,8 +85,8 @@ impl<'docker> Plugin<'docker> {
     }}
 }
 
-impl<'docker> Plugins<'docker> {
+impl Plugins {
     impl_api_ep! {plug: Plugin, resp
-        List -> "/plugins"
+        List since (1, 25) -> "/plugins"
     }
 }
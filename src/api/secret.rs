@@ -5,18 +5,18 @@ use crate::{conn::Payload, Result};
 
 impl_api_ty!(Secret => name);
 
-impl<'docker> Secret<'docker> {
+impl Secret {
     impl_api_ep! { secret: Secret, resp
-        Inspect -> &format!("/secrets/{}", secret.name)
-        Delete -> &format!("/secrets/{}", secret.name)
+        Inspect since (1, 25) -> &format!("/secrets/{}", secret.name)
+        Delete since (1, 25) -> &format!("/secrets/{}", secret.name)
     }
     // TODO: add Secret::update
 }
 
-impl<'docker> Secrets<'docker> {
+impl Secrets {
     impl_api_ep! { __: Secret, resp
-        List -> "/secrets"
-        Create -> "/secrets/create", resp.id
+        List since (1, 25) -> "/secrets"
+        Create since (1, 25) -> "/secrets/create", resp.id
     }
 }
 
@@ -13,7 +13,7 @@ use crate::{
 
 impl_api_ty!(Service => name);
 
-impl<'docker> Service<'docker> {
+impl Service {
     api_doc! { Service => Create
     /// Creates a new service from ServiceOpts.
     |
@@ -33,11 +33,27 @@ impl<'docker> Service<'docker> {
     impl_api_ep! { svc: Service, resp
         Inspect -> &format!("/services/{}", svc.name)
         Delete -> &format!("/services/{}", svc.name)
-        Logs -> &format!("/services/{}/logs", svc.name)
+        Logs since (1, 29) -> &format!("/services/{}/logs", svc.name)
+    }
+
+    /// Returns whether the containers of the service have a TTY, which decides the format of the
+    /// logs.
+    pub(crate) async fn tty(&self) -> Result<bool> {
+        Service::new(self.docker.untraced(), self.name.clone())
+            .inspect()
+            .await
+            .map(|service| {
+                service
+                    .spec
+                    .and_then(|spec| spec.task_template)
+                    .and_then(|template| template.container_spec)
+                    .and_then(|container| container.tty)
+                    .unwrap_or(false)
+            })
     }
 }
 
-impl<'docker> Services<'docker> {
+impl Services {
     impl_api_ep! { svc: Service, resp
         List -> "/services"
     }
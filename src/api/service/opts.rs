@@ -40,7 +40,7 @@ impl ServiceListOptsBuilder {
 
     impl_url_bool_field!(
         /// Include service status, with count of running and desired tasks.
-        status => "status"
+        status => "status" since (1, 41)
     );
 }
 
@@ -6,17 +6,21 @@ pub mod opts;
 pub use models::*;
 pub use opts::*;
 
-use crate::{conn::Payload, Docker, Result};
+use crate::{
+    conn::Payload,
+    version::{ApiVersion, Endpoint},
+    Docker, Result,
+};
 
 api_doc! { Swarm
 |
-pub struct Swarm<'docker> {
-    docker: &'docker Docker,
+pub struct Swarm {
+    docker: Docker,
 }
 }
 
-impl<'docker> Swarm<'docker> {
-    pub fn new(docker: &'docker Docker) -> Self {
+impl Swarm {
+    pub fn new(docker: Docker) -> Self {
         Self { docker }
     }
 
@@ -24,19 +28,21 @@ impl<'docker> Swarm<'docker> {
         Inspect -> "/swarm"
     }
 
-    api_doc! { Swarm => Unlockkey
+    api_doc! { Swarm => Unlockkey since (1, 25)
     /// Get the unlock key.
     |
     pub async fn get_unlock_key(&self) -> Result<UnlockKey> {
-        self.docker.get_json("/swarm/unlockkey").await
+        let ep = Endpoint::from("/swarm/unlockkey").since(ApiVersion::new(1, 25));
+        self.docker.get_json(ep).await
     }}
 
-    api_doc! { Swarm => Unlock
+    api_doc! { Swarm => Unlock since (1, 25)
     /// Unlock a locked manager.
     |
     pub async fn unlock_manager(&self, key: &UnlockKey) -> Result<()> {
+        let ep = Endpoint::from("/swarm/unlock").since(ApiVersion::new(1, 25));
         self.docker
-            .post("/swarm/unlock", Payload::Json(serde_json::to_string(key)?))
+            .post(ep, Payload::Json(serde_json::to_string(key)?))
             .await
             .map(|_| ())
     }}
@@ -45,8 +51,9 @@ impl<'docker> Swarm<'docker> {
     /// Initialize a new swarm.
     |
     pub async fn initialize(&self, opts: &SwarmInitOpts) -> Result<()> {
+        let ep = Endpoint::from("/swarm/init").since(opts.min_api_version());
         self.docker
-            .post("/swarm/init", Payload::Json(opts.serialize()?))
+            .post(ep, Payload::Json(opts.serialize()?))
             .await
             .map(|_| ())
     }}
@@ -55,8 +62,9 @@ impl<'docker> Swarm<'docker> {
     /// Join an existing swarm.
     |
     pub async fn join(&self, opts: &SwarmJoinOpts) -> Result<()> {
+        let ep = Endpoint::from("/swarm/join").since(opts.min_api_version());
         self.docker
-            .post("/swarm/join", Payload::Json(opts.serialize()?))
+            .post(ep, Payload::Json(opts.serialize()?))
             .await
             .map(|_| ())
     }}
@@ -19,7 +19,7 @@ impl SwarmJoinOptsBuilder {
 
     impl_str_field!(
         /// Address or interface to use for data path traffic.
-        data_path_addr: A => "DataPathAddr"
+        data_path_addr: A => "DataPathAddr" since (1, 31)
     );
 
     impl_vec_field!(
@@ -52,18 +52,18 @@ impl SwarmInitOptsBuilder {
 
     impl_str_field!(
         /// Address or interface to use for data path traffic.
-        data_path_addr: A => "DataPathAddr"
+        data_path_addr: A => "DataPathAddr" since (1, 31)
     );
 
     impl_field!(
         // Specifies the data path port number for data traffic. Acceptable port range is 1024 to 49151.
         // If no port is set or is set to 0, default port 4789 will be used.
-        data_path_port: u32 => "DataPathPort"
+        data_path_port: u32 => "DataPathPort" since (1, 40)
     );
 
     impl_vec_field!(
         // Default Address Pool specifies default subnet pools for global scope networks.
-        default_addr_pool: A => "DefaultAddrPool"
+        default_addr_pool: A => "DefaultAddrPool" since (1, 39)
     );
 
     impl_field!(
@@ -73,7 +73,7 @@ impl SwarmInitOptsBuilder {
 
     impl_field!(
         // SubnetSize specifies the subnet size of the networks created from the default subnet pool.
-        subnet_size: u32 => "SubnetSize"
+        subnet_size: u32 => "SubnetSize" since (1, 39)
     );
 
     impl_field!(
@@ -4,7 +4,11 @@ pub mod opts;
 pub use models::*;
 pub use opts::*;
 
-use crate::{util::url::construct_ep, Docker, Error, Result};
+use crate::{
+    util::url::construct_ep,
+    version::{ApiVersion, Endpoint},
+    Docker, Error, Result,
+};
 
 use futures_util::{Stream, TryStreamExt};
 
@@ -37,10 +41,10 @@ impl Docker {
     api_doc! { System => Events
     /// Returns a stream of Docker events
     |
-    pub fn events<'docker>(
-        &'docker self,
+    pub fn events(
+        &self,
         opts: &EventsOpts,
-    ) -> impl Stream<Item = Result<Event>> + Unpin + 'docker {
+    ) -> impl Stream<Item = Result<Event>> + Unpin {
         let ep = construct_ep("/events", opts.serialize());
         let reader = Box::pin(
             self.stream_get(ep)
@@ -57,10 +61,11 @@ impl Docker {
         )
     }}
 
-    api_doc! { System => DataUsage
+    api_doc! { System => DataUsage since (1, 25)
     /// Returns data usage of this Docker instance
     |
     pub async fn data_usage(&self) -> Result<DataUsage> {
-        self.get_json("/system/df").await
+        self.get_json(Endpoint::from("/system/df").since(ApiVersion::new(1, 25)))
+            .await
     }}
 }
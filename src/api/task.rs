@@ -6,14 +6,27 @@ use crate::Result;
 
 impl_api_ty!(Task => id);
 
-impl<'docker> Task<'docker> {
+impl Task {
     impl_api_ep! { task: Task, resp
         Inspect -> &format!("/tasks/{}", task.id)
-        Logs -> &format!("/tasks/{}/logs", task.id)
+        Logs since (1, 29) -> &format!("/tasks/{}/logs", task.id)
+    }
+
+    /// Returns whether the container of the task has a TTY, which decides the format of the logs.
+    pub(crate) async fn tty(&self) -> Result<bool> {
+        Task::new(self.docker.untraced(), self.id.clone())
+            .inspect()
+            .await
+            .map(|task| {
+                task.spec
+                    .container_spec
+                    .and_then(|container| container.tty)
+                    .unwrap_or(false)
+            })
     }
 }
 
-impl<'docker> Tasks<'docker> {
+impl Tasks {
     impl_api_ep! { task: Task, resp
         List -> "/tasks"
     }
@@ -0,0 +1,996 @@
+//! Object-safe traits over the API.
+//!
+//! Every interface like [`Container`](crate::Container) or [`Images`](crate::Images) implements
+//! a matching trait, so code can depend on `dyn ContainerApi` instead of a concrete type and be
+//! handed a fake, a caching layer or a router over multiple hosts instead. The methods mirror the
+//! inherent ones, futures and streams are boxed and generic parameters are replaced by concrete
+//! types. Collections return the handles they create or look up as trait objects too.
+//!
+//! The inherent methods take precedence over the trait methods when calling them on the concrete
+//! types, so importing the traits doesn't change existing code.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use docker_api::api::{ContainerApi, ContainersApi};
+//!
+//! async fn restart_all(containers: &dyn ContainersApi) -> docker_api::Result<()> {
+//!     for info in containers.list(&Default::default()).await? {
+//!         containers.get(&info.id).restart(None).await?;
+//!     }
+//!     Ok(())
+//! }
+//!
+//! # async fn run() -> docker_api::Result<()> {
+//! let docker = docker_api::Docker::new("tcp://127.0.0.1:80")?;
+//! restart_all(&docker.containers()).await
+//! # }
+//! ```
+
+use crate::{
+    api::{
+        AttachOpts, BuildOpts, Change, ClearCacheInfo, ClearCacheOpts, Container,
+        ContainerConnectionOpts, ContainerCreateOpts, ContainerDetails, ContainerInfo,
+        ContainerListOpts, ContainerPruneOpts, ContainerResizeOpts, ContainerUpdateOpts,
+        Containers, ContainersPruneInfo, DataUsage, DeleteStatus, DistributionInspectInfo, Event,
+        EventsOpts, Exec, ExecContainerOpts, ExecInfo, ExecResizeOpts, Exit, History, Image,
+        ImageBuildChunk, ImageDetails, ImageInfo, ImageListOpts, ImagePruneOpts, ImagePushOpts,
+        Images, ImagesPruneInfo, Info, LogLine, LogsOpts, Network, NetworkCreateOpts, NetworkInfo,
+        NetworkListOpts, NetworkPruneOpts, Networks, NetworksPruneInfo, PingInfo, PullOpts,
+        RmContainerOpts, RmImageOpts, SearchResult, Stats, TagOpts, Top, Version, Volume,
+        VolumeCreateOpts, VolumeInfo, VolumeListOpts, VolumePruneOpts, Volumes, VolumesInfo,
+        VolumesPruneInfo,
+    },
+    conn::{Multiplexer as TtyMultiplexer, TtyChunk},
+    Docker, Result,
+};
+
+use bytes::Bytes;
+use futures_util::{
+    future::BoxFuture,
+    stream::{BoxStream, StreamExt},
+};
+use hyper::Body;
+
+use std::{io::Read, path::Path, time::Duration};
+
+/// Implements API traits by forwarding every method to the inherent method of the same name.
+///
+/// Only the signatures are listed, the body is picked by the return type: futures are pinned,
+/// streams are boxed and handles are boxed into trait objects, awaiting them first when they're
+/// created. Methods that need more than that are written out with their body.
+macro_rules! forward_impls {
+    ($(impl $api:ident for $ty:ident { $($methods:tt)* })*) => {
+        $(
+            impl $api for $ty {
+                forward_impls!(@methods $ty; $($methods)*);
+            }
+        )*
+    };
+    (@methods $ty:ident;) => {};
+    (
+        @methods $ty:ident;
+        $(#[$attr:meta])*
+        fn $name:ident $(<$lt:lifetime>)? (
+            &$($self_lt:lifetime)? $self:ident $(, $arg:ident: $arg_ty:ty)* $(,)?
+        ) -> $ret:ty $body:block
+        $($rest:tt)*
+    ) => {
+        $(#[$attr])*
+        fn $name $(<$lt>)? (&$($self_lt)? $self $(, $arg: $arg_ty)*) -> $ret $body
+
+        forward_impls!(@methods $ty; $($rest)*);
+    };
+    (
+        @methods $ty:ident;
+        $(#[$attr:meta])*
+        fn $name:ident $(<$lt:lifetime>)? (
+            &$($self_lt:lifetime)? self $(, $arg:ident: $arg_ty:ty)* $(,)?
+        ) -> &str;
+        $($rest:tt)*
+    ) => {
+        $(#[$attr])*
+        fn $name $(<$lt>)? (&$($self_lt)? self $(, $arg: $arg_ty)*) -> &str {
+            $ty::$name(self $(, $arg)*)
+        }
+
+        forward_impls!(@methods $ty; $($rest)*);
+    };
+    (
+        @methods $ty:ident;
+        $(#[$attr:meta])*
+        fn $name:ident $(<$lt:lifetime>)? (
+            &$($self_lt:lifetime)? self $(, $arg:ident: $arg_ty:ty)* $(,)?
+        ) -> Box<dyn $handle:ident>;
+        $($rest:tt)*
+    ) => {
+        $(#[$attr])*
+        fn $name $(<$lt>)? (&$($self_lt)? self $(, $arg: $arg_ty)*) -> Box<dyn $handle> {
+            Box::new($ty::$name(self $(, $arg)*))
+        }
+
+        forward_impls!(@methods $ty; $($rest)*);
+    };
+    (
+        @methods $ty:ident;
+        $(#[$attr:meta])*
+        fn $name:ident $(<$lt:lifetime>)? (
+            &$($self_lt:lifetime)? self $(, $arg:ident: $arg_ty:ty)* $(,)?
+        ) -> BoxFuture<$fut_lt:lifetime, Result<Box<dyn $handle:ident>>>;
+        $($rest:tt)*
+    ) => {
+        $(#[$attr])*
+        fn $name $(<$lt>)? (&$($self_lt)? self $(, $arg: $arg_ty)*)
+            -> BoxFuture<$fut_lt, Result<Box<dyn $handle>>>
+        {
+            Box::pin(async move {
+                let handle = $ty::$name(self $(, $arg)*).await?;
+                Ok(Box::new(handle) as Box<dyn $handle>)
+            })
+        }
+
+        forward_impls!(@methods $ty; $($rest)*);
+    };
+    (
+        @methods $ty:ident;
+        $(#[$attr:meta])*
+        fn $name:ident $(<$lt:lifetime>)? (
+            &$($self_lt:lifetime)? self $(, $arg:ident: $arg_ty:ty)* $(,)?
+        ) -> BoxFuture<$fut_lt:lifetime, $out:ty>;
+        $($rest:tt)*
+    ) => {
+        $(#[$attr])*
+        fn $name $(<$lt>)? (&$($self_lt)? self $(, $arg: $arg_ty)*) -> BoxFuture<$fut_lt, $out> {
+            Box::pin($ty::$name(self $(, $arg)*))
+        }
+
+        forward_impls!(@methods $ty; $($rest)*);
+    };
+    (
+        @methods $ty:ident;
+        $(#[$attr:meta])*
+        fn $name:ident $(<$lt:lifetime>)? (
+            &$($self_lt:lifetime)? self $(, $arg:ident: $arg_ty:ty)* $(,)?
+        ) -> BoxStream<'static, $item:ty>;
+        $($rest:tt)*
+    ) => {
+        $(#[$attr])*
+        fn $name $(<$lt>)? (&$($self_lt)? self $(, $arg: $arg_ty)*) -> BoxStream<'static, $item> {
+            $ty::$name(self $(, $arg)*).boxed()
+        }
+
+        forward_impls!(@methods $ty; $($rest)*);
+    };
+}
+
+/// Operations on a single container, implemented by [`Container`](Container).
+pub trait ContainerApi: Send + Sync {
+    /// The ID of the container.
+    fn id(&self) -> &str;
+
+    /// Inspects the container.
+    fn inspect(&self) -> BoxFuture<'_, Result<ContainerDetails>>;
+
+    /// Streams the logs of the container.
+    fn logs(&self, opts: &LogsOpts) -> BoxStream<'static, Result<Bytes>>;
+
+    /// Streams the lines of the logs of the container.
+    fn log_lines(&self, opts: &LogsOpts) -> BoxStream<'static, Result<LogLine>>;
+
+    /// Returns the processes running inside the container.
+    fn top<'a>(&'a self, psargs: Option<&'a str>) -> BoxFuture<'a, Result<Top>>;
+
+    /// Attaches a multiplexed stream to the container.
+    fn attach(&self) -> BoxFuture<'_, Result<TtyMultiplexer>>;
+
+    /// Attaches to the streams of the container selected by `opts`.
+    fn attach_with_opts<'a>(
+        &'a self,
+        opts: &'a AttachOpts,
+    ) -> BoxFuture<'a, Result<TtyMultiplexer>>;
+
+    #[cfg(feature = "websocket")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "websocket")))]
+    /// Attaches to the streams of the container selected by `opts` over a WebSocket.
+    fn attach_websocket<'a>(
+        &'a self,
+        opts: &'a AttachOpts,
+    ) -> BoxFuture<'a, Result<crate::conn::WebSocket>>;
+
+    /// Returns the changes made to the filesystem of the container.
+    fn changes(&self) -> BoxFuture<'_, Result<Option<Vec<Change>>>>;
+
+    /// Exports the filesystem of the container as a tarball.
+    fn export(&self) -> BoxStream<'static, Result<Vec<u8>>>;
+
+    /// Streams the resource usage of the container.
+    fn stats(&self) -> BoxStream<'static, Result<Stats>>;
+
+    /// Starts the container.
+    fn start(&self) -> BoxFuture<'_, Result<()>>;
+
+    /// Stops the container, killing it after `wait` if it's still running.
+    fn stop(&self, wait: Option<Duration>) -> BoxFuture<'_, Result<()>>;
+
+    /// Restarts the container, killing it after `wait` if it's still running.
+    fn restart(&self, wait: Option<Duration>) -> BoxFuture<'_, Result<()>>;
+
+    /// Sends a signal to the container, `SIGKILL` by default.
+    fn kill<'a>(&'a self, signal: Option<&'a str>) -> BoxFuture<'a, Result<()>>;
+
+    /// Renames the container.
+    fn rename<'a>(&'a self, name: &'a str) -> BoxFuture<'a, Result<()>>;
+
+    /// Pauses the container.
+    fn pause(&self) -> BoxFuture<'_, Result<()>>;
+
+    /// Unpauses the container.
+    fn unpause(&self) -> BoxFuture<'_, Result<()>>;
+
+    /// Resizes the TTY of the container.
+    fn resize<'a>(&'a self, opts: &'a ContainerResizeOpts) -> BoxFuture<'a, Result<()>>;
+
+    /// Changes the resource limits and restart policy of the container, returns the warnings.
+    fn update<'a>(&'a self, opts: &'a ContainerUpdateOpts) -> BoxFuture<'a, Result<Vec<String>>>;
+
+    /// Waits until the container stops.
+    fn wait(&self) -> BoxFuture<'_, Result<Exit>>;
+
+    /// Runs a command in the container and streams its output.
+    fn exec(&self, opts: &ExecContainerOpts) -> BoxStream<'static, Result<TtyChunk>>;
+
+    /// Copies a file or directory out of the container as a tarball.
+    fn copy_from(&self, path: &Path) -> BoxStream<'static, Result<Vec<u8>>>;
+
+    /// Copies a file into the container at `path`.
+    fn copy_file_into<'a>(&'a self, path: &'a Path, bytes: &'a [u8]) -> BoxFuture<'a, Result<()>>;
+
+    /// Extracts a tarball into the container at `path`.
+    fn copy_to<'a>(&'a self, path: &'a Path, body: Body) -> BoxFuture<'a, Result<()>>;
+
+    /// Returns the stat information of a file in the container.
+    fn stat_file<'a>(&'a self, path: &'a Path) -> BoxFuture<'a, Result<String>>;
+
+    /// Deletes the container.
+    fn delete(&self) -> BoxFuture<'_, Result<String>>;
+
+    /// Removes the container with the given options.
+    fn remove<'a>(&'a self, opts: &'a RmContainerOpts) -> BoxFuture<'a, Result<String>>;
+}
+
+/// Operations on the containers of a host, implemented by [`Containers`](Containers).
+pub trait ContainersApi: Send + Sync {
+    /// Returns the container with the given ID or name.
+    fn get(&self, id: &str) -> Box<dyn ContainerApi>;
+
+    /// Lists the containers.
+    fn list<'a>(&'a self, opts: &'a ContainerListOpts)
+        -> BoxFuture<'a, Result<Vec<ContainerInfo>>>;
+
+    /// Creates a container.
+    fn create<'a>(
+        &'a self,
+        opts: &'a ContainerCreateOpts,
+    ) -> BoxFuture<'a, Result<Box<dyn ContainerApi>>>;
+
+    /// Deletes stopped containers.
+    fn prune<'a>(
+        &'a self,
+        opts: &'a ContainerPruneOpts,
+    ) -> BoxFuture<'a, Result<ContainersPruneInfo>>;
+}
+
+/// Operations on an exec instance, implemented by [`Exec`](Exec).
+pub trait ExecApi: Send + Sync {
+    /// Inspects the exec instance.
+    fn inspect(&self) -> BoxFuture<'_, Result<ExecInfo>>;
+
+    /// Starts the exec instance and streams its output.
+    fn start(&self) -> BoxStream<'static, Result<TtyChunk>>;
+
+    /// Resizes the TTY of the exec instance.
+    fn resize<'a>(&'a self, opts: &'a ExecResizeOpts) -> BoxFuture<'a, Result<()>>;
+}
+
+/// Operations on a single image, implemented by [`Image`](Image).
+pub trait ImageApi: Send + Sync {
+    /// The name or ID of the image.
+    fn name(&self) -> &str;
+
+    /// Inspects the image.
+    fn inspect(&self) -> BoxFuture<'_, Result<ImageDetails>>;
+
+    /// Returns the history of the image.
+    fn history(&self) -> BoxFuture<'_, Result<Vec<History>>>;
+
+    /// Exports the image as a tarball.
+    fn export(&self) -> BoxStream<'static, Result<Vec<u8>>>;
+
+    /// Tags the image.
+    fn tag<'a>(&'a self, opts: &'a TagOpts) -> BoxFuture<'a, Result<()>>;
+
+    /// Pushes the image to a registry.
+    fn push<'a>(&'a self, opts: &'a ImagePushOpts) -> BoxFuture<'a, Result<()>>;
+
+    /// Returns the manifest and platforms of the image from its registry.
+    fn distribution_inspect(&self) -> BoxFuture<'_, Result<DistributionInspectInfo>>;
+
+    /// Deletes the image.
+    fn delete(&self) -> BoxFuture<'_, Result<DeleteStatus>>;
+
+    /// Removes the image with the given options.
+    fn remove<'a>(&'a self, opts: &'a RmImageOpts) -> BoxFuture<'a, Result<DeleteStatus>>;
+}
+
+/// Operations on the images of a host, implemented by [`Images`](Images).
+pub trait ImagesApi: Send + Sync {
+    /// Returns the image with the given name or ID.
+    fn get(&self, name: &str) -> Box<dyn ImageApi>;
+
+    /// Lists the images.
+    fn list<'a>(&'a self, opts: &'a ImageListOpts) -> BoxFuture<'a, Result<Vec<ImageInfo>>>;
+
+    /// Deletes unused images.
+    fn prune<'a>(&'a self, opts: &'a ImagePruneOpts) -> BoxFuture<'a, Result<ImagesPruneInfo>>;
+
+    /// Builds an image.
+    fn build(&self, opts: &BuildOpts) -> BoxStream<'static, Result<ImageBuildChunk>>;
+
+    /// Searches for images on Docker Hub.
+    fn search<'a>(&'a self, term: &'a str) -> BoxFuture<'a, Result<Vec<SearchResult>>>;
+
+    /// Pulls an image.
+    fn pull(&self, opts: &PullOpts) -> BoxStream<'static, Result<ImageBuildChunk>>;
+
+    /// Exports the named images as a tarball.
+    fn export(&self, names: Vec<&str>) -> BoxStream<'static, Result<Vec<u8>>>;
+
+    /// Imports images from a tarball.
+    fn import(&self, tarball: Box<dyn Read + Send>) -> BoxStream<'static, Result<ImageBuildChunk>>;
+
+    /// Pushes the named image to a registry.
+    fn push<'a>(&'a self, name: &'a str, opts: &'a ImagePushOpts) -> BoxFuture<'a, Result<()>>;
+
+    /// Deletes the build cache.
+    fn clear_cache<'a>(&'a self, opts: &'a ClearCacheOpts)
+        -> BoxFuture<'a, Result<ClearCacheInfo>>;
+}
+
+/// Operations on a single network, implemented by [`Network`](Network).
+pub trait NetworkApi: Send + Sync {
+    /// The ID of the network.
+    fn id(&self) -> &str;
+
+    /// Inspects the network.
+    fn inspect(&self) -> BoxFuture<'_, Result<NetworkInfo>>;
+
+    /// Deletes the network.
+    fn delete(&self) -> BoxFuture<'_, Result<()>>;
+
+    /// Connects a container to the network.
+    fn connect<'a>(&'a self, opts: &'a ContainerConnectionOpts) -> BoxFuture<'a, Result<()>>;
+
+    /// Disconnects a container from the network.
+    fn disconnect<'a>(&'a self, opts: &'a ContainerConnectionOpts) -> BoxFuture<'a, Result<()>>;
+}
+
+/// Operations on the networks of a host, implemented by [`Networks`](Networks).
+pub trait NetworksApi: Send + Sync {
+    /// Returns the network with the given ID or name.
+    fn get(&self, id: &str) -> Box<dyn NetworkApi>;
+
+    /// Lists the networks.
+    fn list<'a>(&'a self, opts: &'a NetworkListOpts) -> BoxFuture<'a, Result<Vec<NetworkInfo>>>;
+
+    /// Creates a network.
+    fn create<'a>(
+        &'a self,
+        opts: &'a NetworkCreateOpts,
+    ) -> BoxFuture<'a, Result<Box<dyn NetworkApi>>>;
+
+    /// Deletes unused networks.
+    fn prune<'a>(&'a self, opts: &'a NetworkPruneOpts) -> BoxFuture<'a, Result<NetworksPruneInfo>>;
+}
+
+/// Operations on a single volume, implemented by [`Volume`](Volume).
+pub trait VolumeApi: Send + Sync {
+    /// The name of the volume.
+    fn name(&self) -> &str;
+
+    /// Inspects the volume.
+    fn inspect(&self) -> BoxFuture<'_, Result<VolumeInfo>>;
+
+    /// Deletes the volume.
+    fn delete(&self) -> BoxFuture<'_, Result<()>>;
+}
+
+/// Operations on the volumes of a host, implemented by [`Volumes`](Volumes).
+pub trait VolumesApi: Send + Sync {
+    /// Returns the volume with the given name.
+    fn get(&self, name: &str) -> Box<dyn VolumeApi>;
+
+    /// Lists the volumes.
+    fn list<'a>(&'a self, opts: &'a VolumeListOpts) -> BoxFuture<'a, Result<VolumesInfo>>;
+
+    /// Creates a volume.
+    fn create<'a>(
+        &'a self,
+        opts: &'a VolumeCreateOpts,
+    ) -> BoxFuture<'a, Result<Box<dyn VolumeApi>>>;
+
+    /// Deletes unused volumes.
+    fn prune<'a>(&'a self, opts: &'a VolumePruneOpts) -> BoxFuture<'a, Result<VolumesPruneInfo>>;
+}
+
+/// System wide operations, implemented by [`Docker`](Docker).
+pub trait SystemApi: Send + Sync {
+    /// Returns the version of the daemon.
+    fn version(&self) -> BoxFuture<'_, Result<Version>>;
+
+    /// Returns system wide information about the daemon.
+    fn info(&self) -> BoxFuture<'_, Result<Info>>;
+
+    /// Pings the daemon.
+    fn ping(&self) -> BoxFuture<'_, Result<PingInfo>>;
+
+    /// Streams the events of the daemon.
+    fn events(&self, opts: &EventsOpts) -> BoxStream<'static, Result<Event>>;
+
+    /// Returns the disk usage of the daemon.
+    fn data_usage(&self) -> BoxFuture<'_, Result<DataUsage>>;
+}
+
+forward_impls! {
+    impl ContainerApi for Container {
+        fn id(&self) -> &str;
+        fn inspect(&self) -> BoxFuture<'_, Result<ContainerDetails>>;
+        fn logs(&self, opts: &LogsOpts) -> BoxStream<'static, Result<Bytes>>;
+        fn log_lines(&self, opts: &LogsOpts) -> BoxStream<'static, Result<LogLine>>;
+        fn top<'a>(&'a self, psargs: Option<&'a str>) -> BoxFuture<'a, Result<Top>>;
+        fn attach(&self) -> BoxFuture<'_, Result<TtyMultiplexer>>;
+        fn attach_with_opts<'a>(
+            &'a self,
+            opts: &'a AttachOpts,
+        ) -> BoxFuture<'a, Result<TtyMultiplexer>>;
+        #[cfg(feature = "websocket")]
+        fn attach_websocket<'a>(
+            &'a self,
+            opts: &'a AttachOpts,
+        ) -> BoxFuture<'a, Result<crate::conn::WebSocket>>;
+        fn changes(&self) -> BoxFuture<'_, Result<Option<Vec<Change>>>>;
+        fn export(&self) -> BoxStream<'static, Result<Vec<u8>>>;
+        fn stats(&self) -> BoxStream<'static, Result<Stats>>;
+        fn start(&self) -> BoxFuture<'_, Result<()>>;
+        fn stop(&self, wait: Option<Duration>) -> BoxFuture<'_, Result<()>>;
+        fn restart(&self, wait: Option<Duration>) -> BoxFuture<'_, Result<()>>;
+        fn kill<'a>(&'a self, signal: Option<&'a str>) -> BoxFuture<'a, Result<()>>;
+        fn rename<'a>(&'a self, name: &'a str) -> BoxFuture<'a, Result<()>>;
+        fn pause(&self) -> BoxFuture<'_, Result<()>>;
+        fn unpause(&self) -> BoxFuture<'_, Result<()>>;
+        fn resize<'a>(&'a self, opts: &'a ContainerResizeOpts) -> BoxFuture<'a, Result<()>>;
+        fn update<'a>(
+            &'a self,
+            opts: &'a ContainerUpdateOpts,
+        ) -> BoxFuture<'a, Result<Vec<String>>>;
+        fn wait(&self) -> BoxFuture<'_, Result<Exit>>;
+        fn exec(&self, opts: &ExecContainerOpts) -> BoxStream<'static, Result<TtyChunk>>;
+        fn copy_from(&self, path: &Path) -> BoxStream<'static, Result<Vec<u8>>>;
+        fn copy_file_into<'a>(
+            &'a self,
+            path: &'a Path,
+            bytes: &'a [u8],
+        ) -> BoxFuture<'a, Result<()>>;
+        fn copy_to<'a>(&'a self, path: &'a Path, body: Body) -> BoxFuture<'a, Result<()>>;
+        fn stat_file<'a>(&'a self, path: &'a Path) -> BoxFuture<'a, Result<String>>;
+        fn delete(&self) -> BoxFuture<'_, Result<String>>;
+        fn remove<'a>(&'a self, opts: &'a RmContainerOpts) -> BoxFuture<'a, Result<String>>;
+    }
+
+    impl ContainersApi for Containers {
+        fn get(&self, id: &str) -> Box<dyn ContainerApi>;
+        fn list<'a>(
+            &'a self,
+            opts: &'a ContainerListOpts,
+        ) -> BoxFuture<'a, Result<Vec<ContainerInfo>>>;
+        fn create<'a>(
+            &'a self,
+            opts: &'a ContainerCreateOpts,
+        ) -> BoxFuture<'a, Result<Box<dyn ContainerApi>>>;
+        fn prune<'a>(
+            &'a self,
+            opts: &'a ContainerPruneOpts,
+        ) -> BoxFuture<'a, Result<ContainersPruneInfo>>;
+    }
+
+    impl ExecApi for Exec {
+        fn inspect(&self) -> BoxFuture<'_, Result<ExecInfo>>;
+        fn start(&self) -> BoxStream<'static, Result<TtyChunk>>;
+        fn resize<'a>(&'a self, opts: &'a ExecResizeOpts) -> BoxFuture<'a, Result<()>>;
+    }
+
+    impl ImageApi for Image {
+        fn name(&self) -> &str;
+        fn inspect(&self) -> BoxFuture<'_, Result<ImageDetails>>;
+        fn history(&self) -> BoxFuture<'_, Result<Vec<History>>>;
+        fn export(&self) -> BoxStream<'static, Result<Vec<u8>>>;
+        fn tag<'a>(&'a self, opts: &'a TagOpts) -> BoxFuture<'a, Result<()>>;
+        fn push<'a>(&'a self, opts: &'a ImagePushOpts) -> BoxFuture<'a, Result<()>>;
+        fn distribution_inspect(&self) -> BoxFuture<'_, Result<DistributionInspectInfo>>;
+        fn delete(&self) -> BoxFuture<'_, Result<DeleteStatus>>;
+        fn remove<'a>(&'a self, opts: &'a RmImageOpts) -> BoxFuture<'a, Result<DeleteStatus>>;
+    }
+
+    impl ImagesApi for Images {
+        fn get(&self, name: &str) -> Box<dyn ImageApi>;
+        fn list<'a>(&'a self, opts: &'a ImageListOpts) -> BoxFuture<'a, Result<Vec<ImageInfo>>>;
+        fn prune<'a>(&'a self, opts: &'a ImagePruneOpts) -> BoxFuture<'a, Result<ImagesPruneInfo>>;
+        fn build(&self, opts: &BuildOpts) -> BoxStream<'static, Result<ImageBuildChunk>>;
+        fn search<'a>(&'a self, term: &'a str) -> BoxFuture<'a, Result<Vec<SearchResult>>>;
+        fn pull(&self, opts: &PullOpts) -> BoxStream<'static, Result<ImageBuildChunk>>;
+        fn export(&self, names: Vec<&str>) -> BoxStream<'static, Result<Vec<u8>>>;
+        fn import(
+            &self,
+            tarball: Box<dyn Read + Send>,
+        ) -> BoxStream<'static, Result<ImageBuildChunk>> {
+            self.clone().import(tarball).boxed()
+        }
+        fn push<'a>(&'a self, name: &'a str, opts: &'a ImagePushOpts) -> BoxFuture<'a, Result<()>>;
+        fn clear_cache<'a>(
+            &'a self,
+            opts: &'a ClearCacheOpts,
+        ) -> BoxFuture<'a, Result<ClearCacheInfo>>;
+    }
+
+    impl NetworkApi for Network {
+        fn id(&self) -> &str;
+        fn inspect(&self) -> BoxFuture<'_, Result<NetworkInfo>>;
+        fn delete(&self) -> BoxFuture<'_, Result<()>>;
+        fn connect<'a>(&'a self, opts: &'a ContainerConnectionOpts) -> BoxFuture<'a, Result<()>>;
+        fn disconnect<'a>(&'a self, opts: &'a ContainerConnectionOpts) -> BoxFuture<'a, Result<()>>;
+    }
+
+    impl NetworksApi for Networks {
+        fn get(&self, id: &str) -> Box<dyn NetworkApi>;
+        fn list<'a>(&'a self, opts: &'a NetworkListOpts) -> BoxFuture<'a, Result<Vec<NetworkInfo>>>;
+        fn create<'a>(
+            &'a self,
+            opts: &'a NetworkCreateOpts,
+        ) -> BoxFuture<'a, Result<Box<dyn NetworkApi>>>;
+        fn prune<'a>(
+            &'a self,
+            opts: &'a NetworkPruneOpts,
+        ) -> BoxFuture<'a, Result<NetworksPruneInfo>>;
+    }
+
+    impl VolumeApi for Volume {
+        fn name(&self) -> &str;
+        fn inspect(&self) -> BoxFuture<'_, Result<VolumeInfo>>;
+        fn delete(&self) -> BoxFuture<'_, Result<()>>;
+    }
+
+    impl VolumesApi for Volumes {
+        fn get(&self, name: &str) -> Box<dyn VolumeApi>;
+        fn list<'a>(&'a self, opts: &'a VolumeListOpts) -> BoxFuture<'a, Result<VolumesInfo>>;
+        fn create<'a>(
+            &'a self,
+            opts: &'a VolumeCreateOpts,
+        ) -> BoxFuture<'a, Result<Box<dyn VolumeApi>>>;
+        fn prune<'a>(
+            &'a self,
+            opts: &'a VolumePruneOpts,
+        ) -> BoxFuture<'a, Result<VolumesPruneInfo>>;
+    }
+
+    impl SystemApi for Docker {
+        fn version(&self) -> BoxFuture<'_, Result<Version>>;
+        fn info(&self) -> BoxFuture<'_, Result<Info>>;
+        fn ping(&self) -> BoxFuture<'_, Result<PingInfo>>;
+        fn events(&self, opts: &EventsOpts) -> BoxStream<'static, Result<Event>>;
+        fn data_usage(&self) -> BoxFuture<'_, Result<DataUsage>>;
+    }
+}
+
+#[cfg(feature = "swarm")]
+pub use self::swarm::*;
+
+#[cfg(feature = "swarm")]
+mod swarm {
+    use super::*;
+    use crate::api::{
+        Config, ConfigCreateOpts, ConfigInfo, ConfigListOpts, Configs, Node, NodeInfo,
+        NodeListOpts, NodeUpdateOpts, Nodes, Plugin, PluginInfo, PluginListOpts, Plugins, Secret,
+        SecretCreateOpts, SecretInfo, SecretListOpts, Secrets, Service, ServiceCreateInfo,
+        ServiceInfo, ServiceListOpts, ServiceOpts, Services, Swarm, SwarmInfo, SwarmInitOpts,
+        SwarmJoinOpts, Task, TaskInfo, TaskListOpts, Tasks, UnlockKey,
+    };
+
+    /// Operations on the swarm a host is part of, implemented by [`Swarm`](Swarm).
+    pub trait SwarmApi: Send + Sync {
+        /// Inspects the swarm.
+        fn inspect(&self) -> BoxFuture<'_, Result<SwarmInfo>>;
+
+        /// Returns the key to unlock a locked manager.
+        fn get_unlock_key(&self) -> BoxFuture<'_, Result<UnlockKey>>;
+
+        /// Unlocks a locked manager.
+        fn unlock_manager<'a>(&'a self, key: &'a UnlockKey) -> BoxFuture<'a, Result<()>>;
+
+        /// Initializes a new swarm.
+        fn initialize<'a>(&'a self, opts: &'a SwarmInitOpts) -> BoxFuture<'a, Result<()>>;
+
+        /// Joins an existing swarm.
+        fn join<'a>(&'a self, opts: &'a SwarmJoinOpts) -> BoxFuture<'a, Result<()>>;
+
+        /// Leaves the swarm.
+        fn leave(&self) -> BoxFuture<'_, Result<()>>;
+
+        /// Leaves the swarm even if this node is the last manager.
+        fn force_leave(&self) -> BoxFuture<'_, Result<()>>;
+    }
+
+    /// Operations on a single service, implemented by [`Service`](Service).
+    pub trait ServiceApi: Send + Sync {
+        /// The name or ID of the service.
+        fn name(&self) -> &str;
+
+        /// Creates the service.
+        fn create<'a>(&'a self, opts: &'a ServiceOpts) -> BoxFuture<'a, Result<ServiceCreateInfo>>;
+
+        /// Inspects the service.
+        fn inspect(&self) -> BoxFuture<'_, Result<ServiceInfo>>;
+
+        /// Deletes the service.
+        fn delete(&self) -> BoxFuture<'_, Result<()>>;
+
+        /// Streams the logs of the service.
+        fn logs(&self, opts: &LogsOpts) -> BoxStream<'static, Result<Bytes>>;
+
+        /// Streams the lines of the logs of the service.
+        fn log_lines(&self, opts: &LogsOpts) -> BoxStream<'static, Result<LogLine>>;
+    }
+
+    /// Operations on the services of a swarm, implemented by [`Services`](Services).
+    pub trait ServicesApi: Send + Sync {
+        /// Returns the service with the given name or ID.
+        fn get(&self, name: &str) -> Box<dyn ServiceApi>;
+
+        /// Lists the services.
+        fn list<'a>(&'a self, opts: &'a ServiceListOpts)
+            -> BoxFuture<'a, Result<Vec<ServiceInfo>>>;
+    }
+
+    /// Operations on a single node, implemented by [`Node`](Node).
+    pub trait NodeApi: Send + Sync {
+        /// The name or ID of the node.
+        fn name(&self) -> &str;
+
+        /// Inspects the node.
+        fn inspect(&self) -> BoxFuture<'_, Result<NodeInfo>>;
+
+        /// Updates the node.
+        fn update<'a>(&'a self, opts: &'a NodeUpdateOpts) -> BoxFuture<'a, Result<()>>;
+
+        /// Deletes the node.
+        fn delete(&self) -> BoxFuture<'_, Result<()>>;
+
+        /// Deletes the node even if it's still part of the swarm.
+        fn force_delete(&self) -> BoxFuture<'_, Result<()>>;
+    }
+
+    /// Operations on the nodes of a swarm, implemented by [`Nodes`](Nodes).
+    pub trait NodesApi: Send + Sync {
+        /// Returns the node with the given name or ID.
+        fn get(&self, name: &str) -> Box<dyn NodeApi>;
+
+        /// Lists the nodes.
+        fn list<'a>(&'a self, opts: &'a NodeListOpts) -> BoxFuture<'a, Result<Vec<NodeInfo>>>;
+    }
+
+    /// Operations on a single plugin, implemented by [`Plugin`](Plugin).
+    pub trait PluginApi: Send + Sync {
+        /// The name of the plugin.
+        fn name(&self) -> &str;
+
+        /// Inspects the plugin.
+        fn inspect(&self) -> BoxFuture<'_, Result<PluginInfo>>;
+
+        /// Enables the plugin.
+        fn enable(&self, timeout: Option<u64>) -> BoxFuture<'_, Result<()>>;
+
+        /// Disables the plugin.
+        fn disable(&self) -> BoxFuture<'_, Result<()>>;
+
+        /// Pushes the plugin to a registry.
+        fn push(&self) -> BoxFuture<'_, Result<()>>;
+
+        /// Creates the plugin from a tarball at `path`.
+        fn create<'a>(&'a self, path: &'a Path) -> BoxFuture<'a, Result<()>>;
+
+        /// Deletes the plugin.
+        fn delete(&self) -> BoxFuture<'_, Result<PluginInfo>>;
+
+        /// Deletes the plugin even if it's enabled.
+        fn force_delete(&self) -> BoxFuture<'_, Result<PluginInfo>>;
+    }
+
+    /// Operations on the plugins of a host, implemented by [`Plugins`](Plugins).
+    pub trait PluginsApi: Send + Sync {
+        /// Returns the plugin with the given name.
+        fn get(&self, name: &str) -> Box<dyn PluginApi>;
+
+        /// Lists the plugins.
+        fn list<'a>(&'a self, opts: &'a PluginListOpts) -> BoxFuture<'a, Result<Vec<PluginInfo>>>;
+    }
+
+    /// Operations on a single config, implemented by [`Config`](Config).
+    pub trait ConfigApi: Send + Sync {
+        /// The name or ID of the config.
+        fn name(&self) -> &str;
+
+        /// Inspects the config.
+        fn inspect(&self) -> BoxFuture<'_, Result<ConfigInfo>>;
+
+        /// Deletes the config.
+        fn delete(&self) -> BoxFuture<'_, Result<()>>;
+    }
+
+    /// Operations on the configs of a swarm, implemented by [`Configs`](Configs).
+    pub trait ConfigsApi: Send + Sync {
+        /// Returns the config with the given name or ID.
+        fn get(&self, name: &str) -> Box<dyn ConfigApi>;
+
+        /// Lists the configs.
+        fn list<'a>(&'a self, opts: &'a ConfigListOpts) -> BoxFuture<'a, Result<Vec<ConfigInfo>>>;
+
+        /// Creates a config.
+        fn create<'a>(
+            &'a self,
+            opts: &'a ConfigCreateOpts,
+        ) -> BoxFuture<'a, Result<Box<dyn ConfigApi>>>;
+    }
+
+    /// Operations on a single secret, implemented by [`Secret`](Secret).
+    pub trait SecretApi: Send + Sync {
+        /// The name or ID of the secret.
+        fn name(&self) -> &str;
+
+        /// Inspects the secret.
+        fn inspect(&self) -> BoxFuture<'_, Result<SecretInfo>>;
+
+        /// Deletes the secret.
+        fn delete(&self) -> BoxFuture<'_, Result<()>>;
+    }
+
+    /// Operations on the secrets of a swarm, implemented by [`Secrets`](Secrets).
+    pub trait SecretsApi: Send + Sync {
+        /// Returns the secret with the given name or ID.
+        fn get(&self, name: &str) -> Box<dyn SecretApi>;
+
+        /// Lists the secrets.
+        fn list<'a>(&'a self, opts: &'a SecretListOpts) -> BoxFuture<'a, Result<Vec<SecretInfo>>>;
+
+        /// Creates a secret.
+        fn create<'a>(
+            &'a self,
+            opts: &'a SecretCreateOpts,
+        ) -> BoxFuture<'a, Result<Box<dyn SecretApi>>>;
+    }
+
+    /// Operations on a single task, implemented by [`Task`](Task).
+    pub trait TaskApi: Send + Sync {
+        /// The ID of the task.
+        fn id(&self) -> &str;
+
+        /// Inspects the task.
+        fn inspect(&self) -> BoxFuture<'_, Result<TaskInfo>>;
+
+        /// Streams the logs of the task.
+        fn logs(&self, opts: &LogsOpts) -> BoxStream<'static, Result<Bytes>>;
+
+        /// Streams the lines of the logs of the task.
+        fn log_lines(&self, opts: &LogsOpts) -> BoxStream<'static, Result<LogLine>>;
+    }
+
+    /// Operations on the tasks of a swarm, implemented by [`Tasks`](Tasks).
+    pub trait TasksApi: Send + Sync {
+        /// Returns the task with the given ID.
+        fn get(&self, id: &str) -> Box<dyn TaskApi>;
+
+        /// Lists the tasks.
+        fn list<'a>(&'a self, opts: &'a TaskListOpts) -> BoxFuture<'a, Result<Vec<TaskInfo>>>;
+    }
+
+    forward_impls! {
+        impl SwarmApi for Swarm {
+            fn inspect(&self) -> BoxFuture<'_, Result<SwarmInfo>>;
+            fn get_unlock_key(&self) -> BoxFuture<'_, Result<UnlockKey>>;
+            fn unlock_manager<'a>(&'a self, key: &'a UnlockKey) -> BoxFuture<'a, Result<()>>;
+            fn initialize<'a>(&'a self, opts: &'a SwarmInitOpts) -> BoxFuture<'a, Result<()>>;
+            fn join<'a>(&'a self, opts: &'a SwarmJoinOpts) -> BoxFuture<'a, Result<()>>;
+            fn leave(&self) -> BoxFuture<'_, Result<()>>;
+            fn force_leave(&self) -> BoxFuture<'_, Result<()>>;
+        }
+
+        impl ServiceApi for Service {
+            fn name(&self) -> &str;
+            fn create<'a>(
+                &'a self,
+                opts: &'a ServiceOpts,
+            ) -> BoxFuture<'a, Result<ServiceCreateInfo>>;
+            fn inspect(&self) -> BoxFuture<'_, Result<ServiceInfo>>;
+            fn delete(&self) -> BoxFuture<'_, Result<()>>;
+            fn logs(&self, opts: &LogsOpts) -> BoxStream<'static, Result<Bytes>>;
+            fn log_lines(&self, opts: &LogsOpts) -> BoxStream<'static, Result<LogLine>>;
+        }
+
+        impl ServicesApi for Services {
+            fn get(&self, name: &str) -> Box<dyn ServiceApi>;
+            fn list<'a>(
+                &'a self,
+                opts: &'a ServiceListOpts,
+            ) -> BoxFuture<'a, Result<Vec<ServiceInfo>>>;
+        }
+
+        impl NodeApi for Node {
+            fn name(&self) -> &str;
+            fn inspect(&self) -> BoxFuture<'_, Result<NodeInfo>>;
+            fn update<'a>(&'a self, opts: &'a NodeUpdateOpts) -> BoxFuture<'a, Result<()>>;
+            fn delete(&self) -> BoxFuture<'_, Result<()>>;
+            fn force_delete(&self) -> BoxFuture<'_, Result<()>>;
+        }
+
+        impl NodesApi for Nodes {
+            fn get(&self, name: &str) -> Box<dyn NodeApi>;
+            fn list<'a>(&'a self, opts: &'a NodeListOpts) -> BoxFuture<'a, Result<Vec<NodeInfo>>>;
+        }
+
+        impl PluginApi for Plugin {
+            fn name(&self) -> &str;
+            fn inspect(&self) -> BoxFuture<'_, Result<PluginInfo>>;
+            fn enable(&self, timeout: Option<u64>) -> BoxFuture<'_, Result<()>>;
+            fn disable(&self) -> BoxFuture<'_, Result<()>>;
+            fn push(&self) -> BoxFuture<'_, Result<()>>;
+            fn create<'a>(&'a self, path: &'a Path) -> BoxFuture<'a, Result<()>>;
+            fn delete(&self) -> BoxFuture<'_, Result<PluginInfo>>;
+            fn force_delete(&self) -> BoxFuture<'_, Result<PluginInfo>>;
+        }
+
+        impl PluginsApi for Plugins {
+            fn get(&self, name: &str) -> Box<dyn PluginApi>;
+            fn list<'a>(
+                &'a self,
+                opts: &'a PluginListOpts,
+            ) -> BoxFuture<'a, Result<Vec<PluginInfo>>>;
+        }
+
+        impl ConfigApi for Config {
+            fn name(&self) -> &str;
+            fn inspect(&self) -> BoxFuture<'_, Result<ConfigInfo>>;
+            fn delete(&self) -> BoxFuture<'_, Result<()>>;
+        }
+
+        impl ConfigsApi for Configs {
+            fn get(&self, name: &str) -> Box<dyn ConfigApi>;
+            fn list<'a>(
+                &'a self,
+                opts: &'a ConfigListOpts,
+            ) -> BoxFuture<'a, Result<Vec<ConfigInfo>>>;
+            fn create<'a>(
+                &'a self,
+                opts: &'a ConfigCreateOpts,
+            ) -> BoxFuture<'a, Result<Box<dyn ConfigApi>>>;
+        }
+
+        impl SecretApi for Secret {
+            fn name(&self) -> &str;
+            fn inspect(&self) -> BoxFuture<'_, Result<SecretInfo>>;
+            fn delete(&self) -> BoxFuture<'_, Result<()>>;
+        }
+
+        impl SecretsApi for Secrets {
+            fn get(&self, name: &str) -> Box<dyn SecretApi>;
+            fn list<'a>(
+                &'a self,
+                opts: &'a SecretListOpts,
+            ) -> BoxFuture<'a, Result<Vec<SecretInfo>>>;
+            fn create<'a>(
+                &'a self,
+                opts: &'a SecretCreateOpts,
+            ) -> BoxFuture<'a, Result<Box<dyn SecretApi>>>;
+        }
+
+        impl TaskApi for Task {
+            fn id(&self) -> &str;
+            fn inspect(&self) -> BoxFuture<'_, Result<TaskInfo>>;
+            fn logs(&self, opts: &LogsOpts) -> BoxStream<'static, Result<Bytes>>;
+            fn log_lines(&self, opts: &LogsOpts) -> BoxStream<'static, Result<LogLine>>;
+        }
+
+        impl TasksApi for Tasks {
+            fn get(&self, id: &str) -> Box<dyn TaskApi>;
+            fn list<'a>(&'a self, opts: &'a TaskListOpts) -> BoxFuture<'a, Result<Vec<TaskInfo>>>;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conn::Mock;
+
+    use hyper::{Method, StatusCode};
+
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Counts the containers created through it.
+    struct Counting {
+        inner: Box<dyn ContainersApi>,
+        created: AtomicUsize,
+    }
+
+    impl ContainersApi for Counting {
+        fn get(&self, id: &str) -> Box<dyn ContainerApi> {
+            self.inner.get(id)
+        }
+
+        fn list<'a>(
+            &'a self,
+            opts: &'a ContainerListOpts,
+        ) -> BoxFuture<'a, Result<Vec<ContainerInfo>>> {
+            self.inner.list(opts)
+        }
+
+        fn create<'a>(
+            &'a self,
+            opts: &'a ContainerCreateOpts,
+        ) -> BoxFuture<'a, Result<Box<dyn ContainerApi>>> {
+            self.created.fetch_add(1, Ordering::SeqCst);
+            self.inner.create(opts)
+        }
+
+        fn prune<'a>(
+            &'a self,
+            opts: &'a ContainerPruneOpts,
+        ) -> BoxFuture<'a, Result<ContainersPruneInfo>> {
+            self.inner.prune(opts)
+        }
+    }
+
+    #[tokio::test]
+    async fn api_types_can_be_used_as_trait_objects() {
+        let mock = Mock::new();
+        mock.on(Method::POST, "/containers/create").json(
+            StatusCode::CREATED,
+            &serde_json::json!({"Id": "4f2a", "Warnings": []}),
+        );
+        mock.on(Method::POST, "/containers/4f2a/start")
+            .status(StatusCode::NO_CONTENT);
+        mock.on(Method::POST, "/containers/4f2a/wait")
+            .json(StatusCode::OK, &serde_json::json!({"StatusCode": 0}));
+        let docker = Docker::mock(mock.clone());
+
+        let containers = Counting {
+            inner: Box::new(docker.containers()),
+            created: AtomicUsize::new(0),
+        };
+        let api: &dyn ContainersApi = &containers;
+        let container = api
+            .create(&ContainerCreateOpts::builder("ubuntu").build())
+            .await
+            .unwrap();
+        assert_eq!(container.id(), "4f2a");
+        container.start().await.unwrap();
+        assert_eq!(container.wait().await.unwrap().status_code, 0);
+        assert_eq!(containers.created.load(Ordering::SeqCst), 1);
+
+        let paths: Vec<_> = mock.requests().into_iter().map(|r| r.path).collect();
+        assert_eq!(
+            paths,
+            [
+                "/containers/create",
+                "/containers/4f2a/start",
+                "/containers/4f2a/wait"
+            ]
+        );
+    }
+}
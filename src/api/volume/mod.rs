@@ -9,17 +9,17 @@ use crate::{conn::Payload, Result};
 
 impl_api_ty!(Volume => name);
 
-impl<'docker> Volume<'docker> {
+impl Volume {
     impl_api_ep! {vol: Volume, resp
         Inspect -> &format!("/volumes/{}", vol.name)
         Delete -> &format!("/volumes/{}", vol.name)
     }
 }
 
-impl<'docker> Volumes<'docker> {
+impl Volumes {
     impl_api_ep! {__: Volume, resp
         Create -> "/volumes/create", resp.name
         List -> "/volumes", VolumesInfo
-        Prune -> "/volumes/prune"
+        Prune since (1, 25) -> "/volumes/prune"
     }
 }
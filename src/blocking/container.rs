@@ -0,0 +1,113 @@
+//! Blocking interfaces for containers.
+
+use crate::{
+    api::{
+        Change, ContainerCreateOpts, ContainerDetails, ContainerId, ContainerInfo,
+        ContainerListOpts, ContainerPruneOpts, ContainerResizeOpts, ContainerUpdateOpts,
+        ContainersPruneInfo, ExecContainerOpts, Exit, LogLine, LogsOpts, RmContainerOpts, Stats,
+        Top,
+    },
+    conn::TtyChunk,
+    Result,
+};
+
+use bytes::Bytes;
+use hyper::Body;
+use tokio::runtime::Runtime;
+
+use std::{path::Path, sync::Arc, time::Duration};
+
+/// Blocking interface for a single container, see [`Container`](crate::Container).
+#[derive(Clone, Debug)]
+pub struct Container {
+    inner: crate::Container,
+    runtime: Arc<Runtime>,
+}
+
+impl Container {
+    pub(crate) fn new(inner: crate::Container, runtime: Arc<Runtime>) -> Self {
+        Container { inner, runtime }
+    }
+
+    /// A getter for the container id.
+    pub fn id(&self) -> &str {
+        self.inner.id()
+    }
+
+    /// Returns the asynchronous interface wrapped by this one.
+    pub fn as_async(&self) -> &crate::Container {
+        &self.inner
+    }
+
+    impl_blocking! { Container;
+        call fn inspect(&self) -> ContainerDetails;
+        iter fn logs(&self, opts: &LogsOpts) -> Bytes;
+        iter fn log_lines(&self, opts: &LogsOpts) -> LogLine;
+        call fn top(&self, psargs: Option<&str>) -> Top;
+        call fn changes(&self) -> Option<Vec<Change>>;
+        iter fn export(&self) -> Vec<u8>;
+        iter fn stats(&self) -> Stats;
+        call fn start(&self) -> ();
+        call fn stop(&self, wait: Option<Duration>) -> ();
+        call fn restart(&self, wait: Option<Duration>) -> ();
+        call fn kill(&self, signal: Option<&str>) -> ();
+        call fn rename(&self, name: &str) -> ();
+        call fn pause(&self) -> ();
+        call fn unpause(&self) -> ();
+        call fn resize(&self, opts: &ContainerResizeOpts) -> ();
+        call fn update(&self, opts: &ContainerUpdateOpts) -> Vec<String>;
+        call fn wait(&self) -> Exit;
+        iter fn exec(&self, opts: &ExecContainerOpts) -> TtyChunk;
+        iter fn copy_from(&self, path: &Path) -> Vec<u8>;
+        call fn copy_to(&self, path: &Path, body: Body) -> ();
+        call fn delete(&self) -> String;
+        call fn remove(&self, opts: &RmContainerOpts) -> String;
+    }
+
+    /// Blocking version of [`Container::copy_file_into`](crate::Container::copy_file_into).
+    pub fn copy_file_into<P: AsRef<Path>>(&self, path: P, bytes: &[u8]) -> Result<()> {
+        self.runtime
+            .block_on(self.inner.copy_file_into(path, bytes))
+    }
+
+    /// Blocking version of [`Container::stat_file`](crate::Container::stat_file).
+    pub fn stat_file<P>(&self, path: P) -> Result<String>
+    where
+        P: AsRef<Path>,
+    {
+        self.runtime.block_on(self.inner.stat_file(path))
+    }
+}
+
+/// Blocking interface for the containers of a host, see [`Containers`](crate::Containers).
+#[derive(Clone, Debug)]
+pub struct Containers {
+    inner: crate::Containers,
+    runtime: Arc<Runtime>,
+}
+
+impl Containers {
+    pub(crate) fn new(inner: crate::Containers, runtime: Arc<Runtime>) -> Self {
+        Containers { inner, runtime }
+    }
+
+    /// Returns a reference to a set of operations available to a specific container.
+    pub fn get<ID>(&self, id: ID) -> Container
+    where
+        ID: Into<ContainerId>,
+    {
+        Container::new(self.inner.get(id), self.runtime.clone())
+    }
+
+    impl_blocking! { Containers;
+        call fn list(&self, opts: &ContainerListOpts) -> Vec<ContainerInfo>;
+        call fn prune(&self, opts: &ContainerPruneOpts) -> ContainersPruneInfo;
+    }
+
+    /// Blocking version of [`Containers::create`](crate::Containers::create).
+    pub fn create(&self, opts: &ContainerCreateOpts) -> Result<Container> {
+        self.runtime
+            .block_on(self.inner.create(opts))
+            .map(|container| Container::new(container, self.runtime.clone()))
+    }
+}
@@ -0,0 +1,55 @@
+//! Blocking interface for exec instances.
+
+use crate::{
+    api::{ExecContainerOpts, ExecId, ExecInfo, ExecResizeOpts},
+    blocking::Docker,
+    conn::TtyChunk,
+    Result,
+};
+
+use tokio::runtime::Runtime;
+
+use std::sync::Arc;
+
+/// Blocking interface for an exec instance, see [`Exec`](crate::Exec).
+pub struct Exec {
+    inner: crate::Exec,
+    runtime: Arc<Runtime>,
+}
+
+impl Exec {
+    /// Blocking version of [`Exec::create`](crate::Exec::create).
+    pub fn create<C>(docker: &Docker, container_id: C, opts: &ExecContainerOpts) -> Result<Exec>
+    where
+        C: AsRef<str>,
+    {
+        docker
+            .runtime
+            .block_on(crate::Exec::create(
+                docker.inner.clone(),
+                container_id,
+                opts,
+            ))
+            .map(|inner| Exec {
+                inner,
+                runtime: docker.runtime.clone(),
+            })
+    }
+
+    /// Returns a reference to the exec instance with the given `id`.
+    pub fn get<ID>(docker: &Docker, id: ID) -> Exec
+    where
+        ID: Into<ExecId>,
+    {
+        Exec {
+            inner: crate::Exec::get(docker.inner.clone(), id),
+            runtime: docker.runtime.clone(),
+        }
+    }
+
+    impl_blocking! { Exec;
+        iter fn start(&self) -> TtyChunk;
+        call fn inspect(&self) -> ExecInfo;
+        call fn resize(&self, opts: &ExecResizeOpts) -> ();
+    }
+}
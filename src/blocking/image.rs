@@ -0,0 +1,96 @@
+//! Blocking interfaces for images.
+
+use crate::{
+    api::{
+        BuildOpts, ClearCacheInfo, ClearCacheOpts, DeleteStatus, DistributionInspectInfo, History,
+        ImageBuildChunk, ImageDetails, ImageId, ImageInfo, ImageListOpts, ImagePruneOpts,
+        ImagePushOpts, ImagesPruneInfo, PullOpts, RmImageOpts, SearchResult, TagOpts,
+    },
+    blocking::Iter,
+    Result,
+};
+
+use tokio::runtime::Runtime;
+
+use std::{io::Read, sync::Arc};
+
+/// Blocking interface for a single image, see [`Image`](crate::Image).
+#[derive(Clone, Debug)]
+pub struct Image {
+    inner: crate::Image,
+    runtime: Arc<Runtime>,
+}
+
+impl Image {
+    pub(crate) fn new(inner: crate::Image, runtime: Arc<Runtime>) -> Self {
+        Image { inner, runtime }
+    }
+
+    /// A getter for the image name.
+    pub fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    impl_blocking! { Image;
+        call fn inspect(&self) -> ImageDetails;
+        call fn history(&self) -> Vec<History>;
+        iter fn export(&self) -> Vec<u8>;
+        call fn tag(&self, opts: &TagOpts) -> ();
+        call fn push(&self, opts: &ImagePushOpts) -> ();
+        call fn distribution_inspect(&self) -> DistributionInspectInfo;
+        call fn delete(&self) -> DeleteStatus;
+        call fn remove(&self, opts: &RmImageOpts) -> DeleteStatus;
+    }
+}
+
+/// Blocking interface for the images of a host, see [`Images`](crate::Images).
+#[derive(Clone, Debug)]
+pub struct Images {
+    inner: crate::Images,
+    runtime: Arc<Runtime>,
+}
+
+impl Images {
+    pub(crate) fn new(inner: crate::Images, runtime: Arc<Runtime>) -> Self {
+        Images { inner, runtime }
+    }
+
+    /// Returns a reference to a set of operations available to a specific image.
+    pub fn get<ID>(&self, name: ID) -> Image
+    where
+        ID: Into<ImageId>,
+    {
+        Image::new(self.inner.get(name), self.runtime.clone())
+    }
+
+    impl_blocking! { Images;
+        call fn list(&self, opts: &ImageListOpts) -> Vec<ImageInfo>;
+        call fn prune(&self, opts: &ImagePruneOpts) -> ImagesPruneInfo;
+        iter fn build(&self, opts: &BuildOpts) -> ImageBuildChunk;
+        iter fn pull(&self, opts: &PullOpts) -> ImageBuildChunk;
+        iter fn export(&self, names: Vec<&str>) -> Vec<u8>;
+        call fn clear_cache(&self, opts: &ClearCacheOpts) -> ClearCacheInfo;
+    }
+
+    /// Blocking version of [`Images::search`](crate::Images::search).
+    pub fn search<T>(&self, term: T) -> Result<Vec<SearchResult>>
+    where
+        T: AsRef<str>,
+    {
+        self.runtime.block_on(self.inner.search(term))
+    }
+
+    /// Blocking version of [`Images::import`](crate::Images::import), the stream is returned as
+    /// an iterator.
+    pub fn import<R>(&self, tarball: R) -> Iter<ImageBuildChunk>
+    where
+        R: Read + Send + 'static,
+    {
+        Iter::new(self.runtime.clone(), self.inner.clone().import(tarball))
+    }
+
+    /// Blocking version of [`Images::push`](crate::Images::push).
+    pub fn push(&self, name: impl Into<String>, opts: &ImagePushOpts) -> Result<()> {
+        self.runtime.block_on(self.inner.push(name, opts))
+    }
+}
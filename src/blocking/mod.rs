@@ -0,0 +1,263 @@
+//! A blocking client for synchronous code.
+//!
+//! [`Docker`](Docker) wraps an asynchronous [`Docker`](crate::Docker) together with its own
+//! runtime and blocks the calling thread until each request completes. It offers the same
+//! interfaces for containers, images, exec instances, networks, volumes and system wide
+//! operations, streams like logs, image pulls and events are returned as [`Iter`](Iter)ators.
+//! Attaching to containers needs a bidirectional stream and is only available asynchronously.
+//!
+//! The blocking client must not be used from within an async runtime, that panics. Use the
+//! asynchronous client there instead.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use docker_api::{api::LogsOpts, blocking::Docker, container::ContainerCreateOpts};
+//!
+//! # fn main() -> docker_api::Result<()> {
+//! let docker = Docker::new("unix:///var/run/docker.sock")?;
+//!
+//! let container = docker
+//!     .containers()
+//!     .create(&ContainerCreateOpts::builder("ubuntu").cmd(vec!["echo", "hi"]).build())?;
+//! container.start()?;
+//! container.wait()?;
+//!
+//! for chunk in container.logs(&LogsOpts::builder().stdout(true).build()) {
+//!     print!("{}", String::from_utf8_lossy(&chunk?));
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+/// Generates blocking versions of the methods of the async type wrapped by `$base`. Methods
+/// returning a future are declared with `call`, methods returning a stream with `iter`.
+macro_rules! impl_blocking {
+    (
+        $base:ident;
+        $(
+            $kind:ident fn $name:ident(&self $(, $arg:ident: $ty:ty)*) -> $ret:ty;
+        )*
+    ) => {
+        $(
+            impl_blocking!(@$kind $base $name ($($arg: $ty),*) -> $ret);
+        )*
+    };
+    (@call $base:ident $name:ident ($($arg:ident: $ty:ty),*) -> $ret:ty) => {
+        calculated_doc!{
+        #[doc = concat!("Blocking version of [`", stringify!($base), "::", stringify!($name), "`](crate::", stringify!($base), "::", stringify!($name), ").")]
+        pub fn $name(&self $(, $arg: $ty)*) -> crate::Result<$ret> {
+            self.runtime.block_on(self.inner.$name($($arg),*))
+        }
+        }
+    };
+    (@iter $base:ident $name:ident ($($arg:ident: $ty:ty),*) -> $ret:ty) => {
+        calculated_doc!{
+        #[doc = concat!("Blocking version of [`", stringify!($base), "::", stringify!($name), "`](crate::", stringify!($base), "::", stringify!($name), "), the stream is returned as an iterator.")]
+        pub fn $name(&self $(, $arg: $ty)*) -> crate::blocking::Iter<$ret> {
+            crate::blocking::Iter::new(self.runtime.clone(), self.inner.$name($($arg),*))
+        }
+        }
+    };
+}
+
+mod container;
+mod exec;
+mod image;
+mod network;
+mod volume;
+
+pub use container::{Container, Containers};
+pub use exec::Exec;
+pub use image::{Image, Images};
+pub use network::{Network, Networks};
+pub use volume::{Volume, Volumes};
+
+use crate::{
+    api::{DataUsage, Event, EventsOpts, Info, PingInfo, Version},
+    Result,
+};
+
+use futures_util::stream::{BoxStream, Stream, StreamExt};
+use tokio::runtime::{self, Runtime};
+
+#[cfg(unix)]
+use std::path::Path;
+use std::{fmt, sync::Arc};
+
+/// A blocking docker client. Clones share the underlying client and runtime.
+#[derive(Clone, Debug)]
+pub struct Docker {
+    inner: crate::Docker,
+    runtime: Arc<Runtime>,
+}
+
+impl Docker {
+    /// Creates a blocking client for the docker host at `uri`, see
+    /// [`Docker::new`](crate::Docker::new) for the supported schemes.
+    pub fn new<U>(uri: U) -> Result<Docker>
+    where
+        U: AsRef<str>,
+    {
+        crate::Docker::new(uri).and_then(Self::from_async)
+    }
+
+    #[cfg(unix)]
+    #[cfg_attr(docsrs, doc(cfg(unix)))]
+    /// Creates a blocking client for a docker host listening on the Unix socket at `socket_path`.
+    pub fn unix<P>(socket_path: P) -> Result<Docker>
+    where
+        P: AsRef<Path>,
+    {
+        Self::from_async(crate::Docker::unix(socket_path))
+    }
+
+    /// Creates a blocking client configured from the environment, see
+    /// [`Docker::from_env`](crate::Docker::from_env).
+    pub fn from_env() -> Result<Docker> {
+        crate::Docker::from_env().and_then(Self::from_async)
+    }
+
+    /// Creates a blocking client from an asynchronous one, for example one configured with
+    /// [`Docker::builder`](crate::Docker::builder). Fails if the runtime can't be started.
+    pub fn from_async(docker: crate::Docker) -> Result<Docker> {
+        let runtime = runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        Ok(Docker {
+            inner: docker,
+            runtime: Arc::new(runtime),
+        })
+    }
+
+    /// Returns the asynchronous client wrapped by this one.
+    pub fn as_async(&self) -> &crate::Docker {
+        &self.inner
+    }
+
+    /// Exports an interface for interacting with Docker images.
+    pub fn images(&self) -> Images {
+        Images::new(self.inner.images(), self.runtime.clone())
+    }
+
+    /// Exports an interface for interacting with Docker containers.
+    pub fn containers(&self) -> Containers {
+        Containers::new(self.inner.containers(), self.runtime.clone())
+    }
+
+    /// Exports an interface for interacting with Docker networks.
+    pub fn networks(&self) -> Networks {
+        Networks::new(self.inner.networks(), self.runtime.clone())
+    }
+
+    /// Exports an interface for interacting with Docker volumes.
+    pub fn volumes(&self) -> Volumes {
+        Volumes::new(self.inner.volumes(), self.runtime.clone())
+    }
+
+    impl_blocking! { Docker;
+        call fn version(&self) -> Version;
+        call fn info(&self) -> Info;
+        call fn ping(&self) -> PingInfo;
+        iter fn events(&self, opts: &EventsOpts) -> Event;
+        call fn data_usage(&self) -> DataUsage;
+    }
+}
+
+/// An iterator over a stream of the API, blocking until the next item arrives.
+pub struct Iter<T> {
+    runtime: Arc<Runtime>,
+    stream: BoxStream<'static, Result<T>>,
+}
+
+impl<T> Iter<T> {
+    pub(crate) fn new<S>(runtime: Arc<Runtime>, stream: S) -> Self
+    where
+        S: Stream<Item = Result<T>> + Send + 'static,
+    {
+        Iter {
+            runtime,
+            stream: stream.boxed(),
+        }
+    }
+}
+
+impl<T> Iterator for Iter<T> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.runtime.block_on(self.stream.next())
+    }
+}
+
+impl<T> fmt::Debug for Iter<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Iter").finish()
+    }
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use super::*;
+    use crate::{
+        api::{ContainerCreateOpts, ExecContainerOpts, LogsOpts},
+        testing::FakeEngine,
+    };
+
+    #[test]
+    fn blocks_on_requests_and_iterates_streams() {
+        let server = tokio::runtime::Runtime::new().unwrap();
+        let engine = server.block_on(FakeEngine::tcp()).unwrap();
+        engine.add_image("ubuntu");
+
+        let docker = Docker::new(engine.host()).unwrap();
+        docker.ping().unwrap();
+
+        let container = docker
+            .containers()
+            .create(
+                &ContainerCreateOpts::builder("ubuntu")
+                    .cmd(vec!["sh", "-c", "echo hello; echo oops >&2"])
+                    .build(),
+            )
+            .unwrap();
+        container.start().unwrap();
+        assert_eq!(container.wait().unwrap().status_code, 0);
+
+        let logs: Vec<_> = container
+            .logs(&LogsOpts::builder().stdout(true).stderr(true).build())
+            .collect::<Result<_>>()
+            .unwrap();
+        assert!(!logs.is_empty());
+
+        let sleeper = docker
+            .containers()
+            .create(
+                &ContainerCreateOpts::builder("ubuntu")
+                    .cmd(vec!["sleep", "infinity"])
+                    .build(),
+            )
+            .unwrap();
+        sleeper.start().unwrap();
+        let exec = Exec::create(
+            &docker,
+            sleeper.id(),
+            &ExecContainerOpts::builder()
+                .cmd(vec!["echo", "hi"])
+                .attach_stdout(true)
+                .build(),
+        )
+        .unwrap();
+        let output: Vec<_> = exec.start().collect::<Result<_>>().unwrap();
+        assert!(matches!(&output[..], [crate::conn::TtyChunk::StdOut(data)] if data == b"hi\n"));
+        sleeper.kill(None).unwrap();
+        sleeper.delete().unwrap();
+
+        container.delete().unwrap();
+        assert!(docker
+            .containers()
+            .list(&Default::default())
+            .unwrap()
+            .is_empty());
+    }
+}
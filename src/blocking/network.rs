@@ -0,0 +1,71 @@
+//! Blocking interfaces for networks.
+
+use crate::{
+    api::{
+        ContainerConnectionOpts, NetworkCreateOpts, NetworkId, NetworkInfo, NetworkListOpts,
+        NetworkPruneOpts, NetworksPruneInfo,
+    },
+    Result,
+};
+
+use tokio::runtime::Runtime;
+
+use std::sync::Arc;
+
+/// Blocking interface for a single network, see [`Network`](crate::Network).
+#[derive(Clone, Debug)]
+pub struct Network {
+    inner: crate::Network,
+    runtime: Arc<Runtime>,
+}
+
+impl Network {
+    pub(crate) fn new(inner: crate::Network, runtime: Arc<Runtime>) -> Self {
+        Network { inner, runtime }
+    }
+
+    /// A getter for the network id.
+    pub fn id(&self) -> &str {
+        self.inner.id()
+    }
+
+    impl_blocking! { Network;
+        call fn inspect(&self) -> NetworkInfo;
+        call fn delete(&self) -> ();
+        call fn connect(&self, opts: &ContainerConnectionOpts) -> ();
+        call fn disconnect(&self, opts: &ContainerConnectionOpts) -> ();
+    }
+}
+
+/// Blocking interface for the networks of a host, see [`Networks`](crate::Networks).
+#[derive(Clone, Debug)]
+pub struct Networks {
+    inner: crate::Networks,
+    runtime: Arc<Runtime>,
+}
+
+impl Networks {
+    pub(crate) fn new(inner: crate::Networks, runtime: Arc<Runtime>) -> Self {
+        Networks { inner, runtime }
+    }
+
+    /// Returns a reference to a set of operations available to a specific network.
+    pub fn get<ID>(&self, id: ID) -> Network
+    where
+        ID: Into<NetworkId>,
+    {
+        Network::new(self.inner.get(id), self.runtime.clone())
+    }
+
+    impl_blocking! { Networks;
+        call fn list(&self, opts: &NetworkListOpts) -> Vec<NetworkInfo>;
+        call fn prune(&self, opts: &NetworkPruneOpts) -> NetworksPruneInfo;
+    }
+
+    /// Blocking version of [`Networks::create`](crate::Networks::create).
+    pub fn create(&self, opts: &NetworkCreateOpts) -> Result<Network> {
+        self.runtime
+            .block_on(self.inner.create(opts))
+            .map(|network| Network::new(network, self.runtime.clone()))
+    }
+}
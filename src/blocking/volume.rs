@@ -0,0 +1,69 @@
+//! Blocking interfaces for volumes.
+
+use crate::{
+    api::{
+        VolumeCreateOpts, VolumeId, VolumeInfo, VolumeListOpts, VolumePruneOpts, VolumesInfo,
+        VolumesPruneInfo,
+    },
+    Result,
+};
+
+use tokio::runtime::Runtime;
+
+use std::sync::Arc;
+
+/// Blocking interface for a single volume, see [`Volume`](crate::Volume).
+#[derive(Clone, Debug)]
+pub struct Volume {
+    inner: crate::Volume,
+    runtime: Arc<Runtime>,
+}
+
+impl Volume {
+    pub(crate) fn new(inner: crate::Volume, runtime: Arc<Runtime>) -> Self {
+        Volume { inner, runtime }
+    }
+
+    /// A getter for the volume name.
+    pub fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    impl_blocking! { Volume;
+        call fn inspect(&self) -> VolumeInfo;
+        call fn delete(&self) -> ();
+    }
+}
+
+/// Blocking interface for the volumes of a host, see [`Volumes`](crate::Volumes).
+#[derive(Clone, Debug)]
+pub struct Volumes {
+    inner: crate::Volumes,
+    runtime: Arc<Runtime>,
+}
+
+impl Volumes {
+    pub(crate) fn new(inner: crate::Volumes, runtime: Arc<Runtime>) -> Self {
+        Volumes { inner, runtime }
+    }
+
+    /// Returns a reference to a set of operations available to a specific volume.
+    pub fn get<ID>(&self, name: ID) -> Volume
+    where
+        ID: Into<VolumeId>,
+    {
+        Volume::new(self.inner.get(name), self.runtime.clone())
+    }
+
+    impl_blocking! { Volumes;
+        call fn list(&self, opts: &VolumeListOpts) -> VolumesInfo;
+        call fn prune(&self, opts: &VolumePruneOpts) -> VolumesPruneInfo;
+    }
+
+    /// Blocking version of [`Volumes::create`](crate::Volumes::create).
+    pub fn create(&self, opts: &VolumeCreateOpts) -> Result<Volume> {
+        self.runtime
+            .block_on(self.inner.create(opts))
+            .map(|volume| Volume::new(volume, self.runtime.clone()))
+    }
+}
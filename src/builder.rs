@@ -1,15 +1,17 @@
 macro_rules! impl_vec_field {
-    ($(#[doc = $docs:expr])* $name:ident: $ty:tt => $docker_name:literal) => {
+    ($(#[doc = $docs:expr])* $name:ident: $ty:tt => $docker_name:literal $(since $since:tt)?) => {
         paste::item! {
             $(
                 #[doc= $docs]
             )*
+            $( #[doc = since_doc!([$since])] )?
             pub fn [< $name  >]<[< $ty >], S>(mut self, $name: $ty)-> Self
             where
                 $ty: IntoIterator<Item = S>,
                 S: AsRef<str> + serde::Serialize
             {
                 self.params.insert($docker_name, serde_json::json!($name.into_iter().collect::<Vec<_>>()));
+                $( self.versions.insert($docker_name, since_version!([$since]).unwrap()); )?
                 self
             }
         }
@@ -17,14 +19,16 @@ macro_rules! impl_vec_field {
 }
 
 macro_rules! impl_field {
-    ($(#[doc = $docs:expr])* $name:ident: $ty:ty => $docker_name:literal) => {
+    ($(#[doc = $docs:expr])* $name:ident: $ty:ty => $docker_name:literal $(since $since:tt)?) => {
         paste::item! {
             $(
                 #[doc= $docs]
             )*
+            $( #[doc = since_doc!([$since])] )?
             pub fn [< $name >](mut self, $name: $ty)-> Self
             {
                 self.params.insert($docker_name, serde_json::json!($name));
+                $( self.versions.insert($docker_name, since_version!([$since]).unwrap()); )?
                 self
             }
         }
@@ -32,16 +36,18 @@ macro_rules! impl_field {
 }
 
 macro_rules! impl_str_field {
-    ($(#[doc = $docs:expr])* $name:ident: $ty:tt => $docker_name:literal) => {
+    ($(#[doc = $docs:expr])* $name:ident: $ty:tt => $docker_name:literal $(since $since:tt)?) => {
         paste::item! {
             $(
                 #[doc= $docs]
             )*
+            $( #[doc = since_doc!([$since])] )?
             pub fn [< $name >]<[< $ty >]>(mut self, $name: $ty)-> Self
             where
                 $ty: AsRef<str> + serde::Serialize,
             {
                 self.params.insert($docker_name, serde_json::json!($name.as_ref()));
+                $( self.versions.insert($docker_name, since_version!([$since]).unwrap()); )?
                 self
             }
         }
@@ -49,16 +55,18 @@ macro_rules! impl_str_field {
 }
 
 macro_rules! impl_url_str_field {
-    ($(#[doc = $docs:expr])* $name:ident: $ty:tt => $docker_name:literal) => {
+    ($(#[doc = $docs:expr])* $name:ident: $ty:tt => $docker_name:literal $(since $since:tt)?) => {
         paste::item! {
             $(
                 #[doc= $docs]
             )*
+            $( #[doc = since_doc!([$since])] )?
             pub fn [< $name >]<[< $ty >]>(mut self, $name: $ty)-> Self
             where
                 $ty: Into<String>,
             {
                 self.params.insert($docker_name, $name.into());
+                $( self.versions.insert($docker_name, since_version!([$since]).unwrap()); )?
                 self
             }
         }
@@ -66,13 +74,15 @@ macro_rules! impl_url_str_field {
 }
 
 macro_rules! impl_url_field {
-    ($(#[doc = $docs:expr])* $name:ident : $ty:tt => $docker_name:literal) => {
+    ($(#[doc = $docs:expr])* $name:ident : $ty:tt => $docker_name:literal $(since $since:tt)?) => {
         paste::item! {
             $(
                 #[doc= $docs]
             )*
+            $( #[doc = since_doc!([$since])] )?
             pub fn [< $name >](mut self, $name: $ty)-> Self {
                 self.params.insert($docker_name, $name.to_string());
+                $( self.versions.insert($docker_name, since_version!([$since]).unwrap()); )?
                 self
             }
         }
@@ -80,13 +90,15 @@ macro_rules! impl_url_field {
 }
 
 macro_rules! impl_url_bool_field {
-    ($(#[doc = $docs:expr])* $name:ident => $docker_name:literal) => {
+    ($(#[doc = $docs:expr])* $name:ident => $docker_name:literal $(since $since:tt)?) => {
         paste::item! {
             $(
                 #[doc= $docs]
             )*
+            $( #[doc = since_doc!([$since])] )?
             pub fn [< $name >](mut self, $name: bool)-> Self {
                 self.params.insert($docker_name, $name.to_string());
+                $( self.versions.insert($docker_name, since_version!([$since]).unwrap()); )?
                 self
             }
         }
@@ -143,6 +155,8 @@ macro_rules! impl_opts_builder {
             #[derive(serde::Serialize, Debug, Default)]
             pub struct [< $name Opts >] {
                 params: std::collections::HashMap<&'static str, $ty>,
+                #[serde(skip)]
+                versions: crate::version::FieldVersions,
             }
             impl [< $name Opts >] {
                 calculated_doc!{
@@ -151,6 +165,18 @@ macro_rules! impl_opts_builder {
                     [< $name OptsBuilder >]::default()
                 }
                 }
+
+                /// Returns the names of the set options that are not available in API `version`.
+                ///
+                /// Only options added after API 1.24 declare a minimum version.
+                pub fn unsupported_fields(&self, version: crate::ApiVersion) -> Vec<&'static str> {
+                    self.versions.unsupported(version)
+                }
+
+                #[allow(dead_code)]
+                pub(crate) fn min_api_version(&self) -> Option<crate::ApiVersion> {
+                    self.versions.required()
+                }
             }
 
             calculated_doc!{
@@ -158,6 +184,7 @@ macro_rules! impl_opts_builder {
             #[derive(Default, Debug)]
             pub struct [< $name OptsBuilder >] {
                 params: std::collections::HashMap<&'static str, $ty>,
+                versions: crate::version::FieldVersions,
             }
             }
 
@@ -167,6 +194,7 @@ macro_rules! impl_opts_builder {
                 pub fn build(&self) -> [< $name Opts >] {
                     [< $name Opts >] {
                         params: self.params.clone(),
+                        versions: self.versions.clone(),
                     }
                 }
                 }
@@ -229,17 +257,17 @@ macro_rules! impl_api_ty {
 
             calculated_doc!{
             #[doc = concat!("Interface for accessing and manipulating Docker ", stringify!($name), ".\n", $($docs,)* "\n", api_url!($name))]
-            #[derive(Debug)]
-            pub struct [< $name >]<'docker> {
-                docker: &'docker crate::Docker,
+            #[derive(Clone, Debug)]
+            pub struct [< $name >] {
+                docker: crate::Docker,
                 $name_field: [< $name Id >],
             }
             }
-            impl<'docker> [< $name >]<'docker> {
+            impl [< $name >] {
                 // TODO: this is possible on nightly, figure out what to do
                 calculated_doc!{
                 #[doc = concat!("Exports an interface exposing operations against a ", stringify!($name), " instance.")]
-                pub fn new<ID>(docker: &'docker crate::Docker, $name_field: ID) -> Self
+                pub fn new<ID>(docker: crate::Docker, $name_field: ID) -> Self
                 where
                     ID: Into<[< $name Id>]>,
                 {
@@ -263,27 +291,27 @@ macro_rules! impl_api_ty {
 
             calculated_doc!{
             #[doc = concat!("Interface for Docker ", stringify!($name), "s.", stringify!($name), ">")]
-            #[derive(Debug)]
-            pub struct [< $name s >]<'docker> {
-                docker: &'docker crate::Docker,
+            #[derive(Clone, Debug)]
+            pub struct [< $name s >] {
+                docker: crate::Docker,
             }
             }
 
-            impl<'docker> [< $name s >]<'docker> {
+            impl [< $name s >] {
                 calculated_doc!{
                 #[doc = concat!("Exports an interface for interacting with Docker ", stringify!($name), "s.")]
-                pub fn new(docker: &'docker crate::Docker) -> Self {
+                pub fn new(docker: crate::Docker) -> Self {
                     [< $name s >] { docker }
                 }
                 }
 
                 calculated_doc!{
                 #[doc = concat!("Returns a reference to a set of operations available to a specific ", stringify!($name), ".")]
-                pub fn get<ID>(&self, $name_field: ID) -> [< $name >]<'docker>
+                pub fn get<ID>(&self, $name_field: ID) -> [< $name >]
                 where
                     ID: Into<[< $name Id >]>,
                 {
-                    [< $name >]::new(self.docker, $name_field)
+                    [< $name >]::new(self.docker.clone(), $name_field)
                 }
                 }
             }
@@ -332,9 +360,29 @@ macro_rules! api_url {
     };
 }
 
+/// Expands to the lowest API version declared with `since (major, minor)` if there is one.
+macro_rules! since_version {
+    ([]) => {
+        Option::<crate::ApiVersion>::None
+    };
+    ([($major:literal, $minor:literal)]) => {
+        Some(crate::ApiVersion::new($major, $minor))
+    };
+}
+
+/// Expands to a doc line about the lowest API version declared with `since (major, minor)`.
+macro_rules! since_doc {
+    ([]) => {
+        ""
+    };
+    ([($major:literal, $minor:literal)]) => {
+        concat!("\nRequires API version ", $major, ".", $minor, " or newer.")
+    };
+}
+
 macro_rules! api_doc {
     (
-        $base:ident => $op:ident
+        $base:ident => $op:ident $(since $since:tt)?
         $(#[doc = $doc:expr])*
         |
         $it:item
@@ -345,6 +393,7 @@ macro_rules! api_doc {
             $(
                 #[doc = $doc]
             )*
+            $( #[doc = since_doc!([$since])] )?
             $it
         }
     };
@@ -369,33 +418,35 @@ macro_rules! impl_api_ep {
     (
         $it:ident: $base:ident, $resp:ident
         $(
-            $op:ident -> $ep:expr $(,$extra:expr)*
+            $op:ident $(since $since:tt)? -> $ep:expr $(,$extra:expr)*
         )*
     ) => {
         $(
-        impl_api_ep! {$op $it: $base -> $resp $ep $(,$extra)* }
+        impl_api_ep! {$op [$($since)?] $it: $base -> $resp $ep $(,$extra)* }
         )*
     };
     (
-        Inspect $it:ident: $base:ident -> $resp:ident $ep:expr, $ret:tt $(,$extra:expr)*
+        Inspect $since:tt $it:ident: $base:ident -> $resp:ident $ep:expr, $ret:tt $(,$extra:expr)*
     ) => {
         paste::item! {
         api_doc! { $base => Inspect
         #[doc = concat!("Inspect this ", stringify!($base), ".")]
+        #[doc = since_doc!($since)]
         |
         pub async fn inspect(&self) -> Result<[< $base $ret >]> {
             let $it = self;
-            self.docker.get_json($ep).await
+            let ep = crate::version::Endpoint::from($ep).since(since_version!($since));
+            self.docker.get_json(ep).await
         }}
         }
     };
     (
-        Inspect $it:ident: $base:ident -> $resp:ident $ep:expr $(,$extra:expr)*
+        Inspect $since:tt $it:ident: $base:ident -> $resp:ident $ep:expr $(,$extra:expr)*
     ) => {
-        impl_api_ep! { Inspect $it: $base -> $resp $ep, Info }
+        impl_api_ep! { Inspect $since $it: $base -> $resp $ep, Info }
     };
     (
-        ForceDelete $it:ident: $base:ident -> $resp:ident $ep:expr, $ret:tt $(,$extra:expr)*
+        ForceDelete $since:tt $it:ident: $base:ident -> $resp:ident $ep:expr, $ret:tt $(,$extra:expr)*
     ) => {
 
         paste::item! {
@@ -410,13 +461,14 @@ macro_rules! impl_api_ep {
             let ep = crate::util::url::construct_ep($ep, query);
 
             self.docker
-                .delete_json(ep.as_ref())
+                .delete_json(crate::version::Endpoint::from(ep).since(since_version!($since)))
                 .await
         }
         }
         paste::item! {
         api_doc! { $base => Delete
         #[doc = concat!("Delete this ", stringify!($base), ".")]
+        #[doc = since_doc!($since)]
         |
         pub async fn force_delete(&self) -> Result<[< $ret >]> {
             self._delete(true).await
@@ -425,6 +477,7 @@ macro_rules! impl_api_ep {
         paste::item! {
         api_doc! { $base => Delete
         #[doc = concat!("Delete this ", stringify!($base), ".")]
+        #[doc = since_doc!($since)]
         |
         pub async fn delete(&self) -> Result<[< $ret >]> {
             self._delete(false).await
@@ -432,119 +485,165 @@ macro_rules! impl_api_ep {
         }
     };
     (
-        Delete $it:ident: $base:ident -> $resp:ident $ep:expr $(,$extra:expr)*
+        Delete $since:tt $it:ident: $base:ident -> $resp:ident $ep:expr $(,$extra:expr)*
     ) => {
         paste::item! {
         api_doc! { $base => Delete
         #[doc = concat!("Delete this ", stringify!($base), ".")]
+        #[doc = since_doc!($since)]
         |
         pub async fn delete(&self) -> Result<()> {
             let $it = self;
-            self.docker.delete($ep).await.map(|_| ())
+            let ep = crate::version::Endpoint::from($ep).since(since_version!($since));
+            self.docker.delete(ep).await.map(|_| ())
         }}
         }
     };
     (
-        DeleteWithOpts $it:ident: $base:ident -> $resp:ident $ep:expr, $ret:tt $(,$extra:expr)*
+        DeleteWithOpts $since:tt $it:ident: $base:ident -> $resp:ident $ep:expr, $ret:tt $(,$extra:expr)*
     ) => {
-        impl_api_ep! { DeleteWithOpts $it: $base -> $resp $ep, $ret => $($extra)* }
+        impl_api_ep! { DeleteWithOpts $since $it: $base -> $resp $ep, $ret => $($extra)* }
     };
     (
-        DeleteWithOpts $it:ident: $base:ident -> $resp:ident $ep:expr, $ret:tt => $fn:expr
+        DeleteWithOpts $since:tt $it:ident: $base:ident -> $resp:ident $ep:expr, $ret:tt => $fn:expr
     ) => {
         paste::item! {
         api_doc! { $base => Delete
         #[doc = concat!("Delete this ", stringify!($base), ".")]
         #[doc = concat!("Use [`delete`](", stringify!($base), "::delete) to delete without options.")]
+        #[doc = since_doc!($since)]
         |
         pub async fn remove(&self, opts: &[< Rm $base Opts >]) -> Result<[< $ret >]> {
             let $it = self;
-            let ep = crate::util::url::construct_ep($ep, opts.serialize());
-            self.docker.$fn(ep.as_ref()).await
+            let ep = crate::version::Endpoint::from(crate::util::url::construct_ep($ep, opts.serialize()))
+                .since(since_version!($since))
+                .since(opts.min_api_version());
+            self.docker.$fn(ep).await
         }}
         }
         paste::item! {
         api_doc! { $base => Delete
         #[doc = concat!("Delete this ", stringify!($base), ".")]
         #[doc = concat!("Use [`remove`](", stringify!($base), "::remove) to customize options.")]
+        #[doc = since_doc!($since)]
         |
         pub async fn delete(&self) -> Result<[< $ret >]> {
             let $it = self;
-            self.docker.$fn($ep).await
+            let ep = crate::version::Endpoint::from($ep).since(since_version!($since));
+            self.docker.$fn(ep).await
         }}
         }
     };
     (
-        List $it:ident: $base:ident -> $resp:ident $ep:expr, $ret:tt $(, $extra:expr)*
+        List $since:tt $it:ident: $base:ident -> $resp:ident $ep:expr, $ret:tt $(, $extra:expr)*
     ) => {
         paste::item! {
         api_doc! { $base => List
         #[doc = concat!("List available ", stringify!($base), "s.")]
+        #[doc = since_doc!($since)]
         |
         pub async fn list(&self, opts: &[< $base ListOpts >]) -> Result<$ret> {
-            let ep = crate::util::url::construct_ep($ep, opts.serialize());
-            self.docker.get_json(&ep).await
+            let ep = crate::version::Endpoint::from(crate::util::url::construct_ep($ep, opts.serialize()))
+                .since(since_version!($since))
+                .since(opts.min_api_version());
+            self.docker.get_json(ep).await
         }}
         }
     };
     (
-        List $it:ident: $base:ident -> $resp:ident $ep:expr $(, $extra:expr)*
+        List $since:tt $it:ident: $base:ident -> $resp:ident $ep:expr $(, $extra:expr)*
     ) => {
         paste::item! {
         api_doc! { $base => List
         #[doc = concat!("List available ", stringify!($base), "s.")]
+        #[doc = since_doc!($since)]
         |
         pub async fn list(&self, opts: &[< $base ListOpts >]) -> Result<Vec<[< $base Info >]>> {
-            let ep = crate::util::url::construct_ep($ep, opts.serialize());
-            self.docker.get_json(&ep).await
+            let ep = crate::version::Endpoint::from(crate::util::url::construct_ep($ep, opts.serialize()))
+                .since(since_version!($since))
+                .since(opts.min_api_version());
+            self.docker.get_json(ep).await
         }}
         }
     };
     (
-        Create $it:ident: $base:ident -> $resp:ident $ep:expr $(, $extra:expr)*
+        Create $since:tt $it:ident: $base:ident -> $resp:ident $ep:expr $(, $extra:expr)*
     ) => {
         paste::item! {
         api_doc! { $base => Create
         #[doc = concat!("Create a new ", stringify!($base), ".")]
+        #[doc = since_doc!($since)]
         |
-        pub async fn create(&self, opts: &[< $base CreateOpts >]) -> Result<[< $base >]<'_>> {
-            self.docker.post_json(&$ep, Payload::Json(opts.serialize()?)).await
-            .map(|$resp: [< $base CreateInfo >]| [< $base >]::new(self.docker, $($extra)*))
+        pub async fn create(&self, opts: &[< $base CreateOpts >]) -> Result<[< $base >]> {
+            let ep = crate::version::Endpoint::from($ep).since(since_version!($since));
+            self.docker.post_json(ep, Payload::Json(opts.serialize()?)).await
+            .map(|$resp: [< $base CreateInfo >]| [< $base >]::new(self.docker.clone(), $($extra)*))
         }}
         }
     };
     (
-        Prune $it:ident: $base:ident -> $resp:ident $ep:expr $(, $extra:expr)*
+        Prune $since:tt $it:ident: $base:ident -> $resp:ident $ep:expr $(, $extra:expr)*
     ) => {
         paste::item! {
         api_doc! { $base => Prune
         #[doc = concat!("Delete stopped/unused ", stringify!($base), "s.")]
+        #[doc = since_doc!($since)]
         |
         pub async fn prune(&self, opts: &[< $base PruneOpts >]) -> Result<[< $base sPruneInfo >]> {
+            let ep = crate::version::Endpoint::from(crate::util::url::construct_ep($ep, opts.serialize()))
+                .since(since_version!($since))
+                .since(opts.min_api_version());
             self.docker
-                .post_json(
-                    &crate::util::url::construct_ep($ep, opts.serialize()),
-                    crate::conn::Payload::empty()
-                ).await
+                .post_json(ep, crate::conn::Payload::empty())
+                .await
         }}
         }
     };
     (
-        Logs $it:ident: $base:ident -> $resp:ident $ep:expr $(, $extra:expr)*
+        Logs $since:tt $it:ident: $base:ident -> $resp:ident $ep:expr $(, $extra:expr)*
     ) => {
         paste::item! {
         api_doc! { $base => Logs
         #[doc = concat!("Returns a stream of logs from a ", stringify!($base), ".")]
+        #[doc = since_doc!($since)]
         |
         pub fn logs(
             &self,
             opts: &crate::api::LogsOpts
-        ) -> impl futures_util::Stream<Item = crate::Result<bytes::Bytes>> + Unpin + 'docker {
+        ) -> impl futures_util::Stream<Item = crate::Result<bytes::Bytes>> + Unpin {
             let $it = self;
-            let ep = crate::util::url::construct_ep($ep, opts.serialize());
+            let ep = crate::version::Endpoint::from(crate::util::url::construct_ep($ep, opts.serialize()))
+                .since(since_version!($since))
+                .since(opts.min_api_version());
 
             Box::pin(self.docker.stream_get(ep))
         }
+        }
+
+        api_doc! { $base => Logs
+        #[doc = concat!("Returns a stream of the lines of the logs of a ", stringify!($base), ". The lines are")]
+        #[doc = "demultiplexed into stdout and stderr, the timestamps and details requested in `opts` are parsed."]
+        #[doc = since_doc!($since)]
+        |
+        pub fn log_lines(
+            &self,
+            opts: &crate::api::LogsOpts
+        ) -> impl futures_util::Stream<Item = crate::Result<crate::api::LogLine>> + Unpin {
+            let $it = self;
+            let ep = crate::version::Endpoint::from(crate::util::url::construct_ep($ep, opts.serialize()))
+                .since(since_version!($since))
+                .since(opts.min_api_version());
+            let tty = {
+                let $it = $it.clone();
+                async move { $it.tty().await }
+            };
+
+            Box::pin(crate::conn::tty::decode_logs(
+                self.docker.stream_logs(ep, tty),
+                opts.timestamps(),
+                opts.details(),
+            ))
+        }
         }}
     };
 }
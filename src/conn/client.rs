@@ -0,0 +1,591 @@
+//! HTTP client sending requests to the docker daemon over a [`Transport`](crate::conn::Transport).
+
+#[cfg(feature = "tracing")]
+use crate::conn::trace;
+use crate::{
+    conn::{runtime, Headers, Middlewares, Payload, Retry, Runtime, Transport},
+    Error, Result,
+};
+
+use futures_util::{
+    io::{AsyncRead, AsyncWrite},
+    stream::{self, Stream},
+    StreamExt,
+};
+use hyper::{
+    body::{Bytes, HttpBody},
+    header::{self, HeaderMap},
+    Body, Method, Request, Response, StatusCode,
+};
+use pin_project::pin_project;
+use serde::{Deserialize, Serialize};
+
+use std::{
+    io,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+#[derive(Clone, Debug)]
+/// Sends requests to the docker daemon over a transport applying options common to all requests
+/// like default headers and timeouts.
+pub(crate) struct RequestClient {
+    transport: Transport,
+    runtime: Arc<dyn Runtime>,
+    headers: HeaderMap,
+    timeout: Option<Duration>,
+    middlewares: Middlewares,
+    retry: Option<Retry>,
+    /// Whether requests that are not idempotent are retried too.
+    retry_all: bool,
+    /// Whether requests open a span of their own, requests made on behalf of another API call
+    /// don't.
+    #[cfg(feature = "tracing")]
+    traced: bool,
+}
+
+impl RequestClient {
+    pub fn new(
+        transport: Transport,
+        runtime: Arc<dyn Runtime>,
+        headers: HeaderMap,
+        timeout: Option<Duration>,
+        middlewares: Middlewares,
+        retry: Option<Retry>,
+    ) -> Self {
+        RequestClient {
+            transport,
+            runtime,
+            headers,
+            timeout,
+            middlewares,
+            retry,
+            retry_all: false,
+            #[cfg(feature = "tracing")]
+            traced: true,
+        }
+    }
+
+    /// Returns a copy of this client that retries all requests, not only idempotent ones.
+    pub fn retrying(&self) -> Self {
+        RequestClient {
+            retry_all: true,
+            ..self.clone()
+        }
+    }
+
+    /// Returns the runtime the requests of this client run on.
+    pub fn runtime(&self) -> &Arc<dyn Runtime> {
+        &self.runtime
+    }
+
+    /// Returns a copy of this client whose requests don't open a span of their own.
+    pub fn untraced(&self) -> Self {
+        RequestClient {
+            #[cfg(feature = "tracing")]
+            traced: false,
+            ..self.clone()
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn transport(&self) -> &Transport {
+        &self.transport
+    }
+
+    pub(crate) async fn request<B>(
+        &self,
+        method: Method,
+        endpoint: impl AsRef<str>,
+        body: Payload<B>,
+        headers: Option<Headers>,
+    ) -> Result<Response<Body>>
+    where
+        B: Into<Body>,
+    {
+        let req = self.build_request(method, endpoint, body, headers, Request::builder())?;
+
+        self.send_request(req).await
+    }
+
+    pub(crate) async fn request_string<B>(
+        &self,
+        method: Method,
+        endpoint: impl AsRef<str>,
+        body: Payload<B>,
+        headers: Option<Headers>,
+    ) -> Result<String>
+    where
+        B: Into<Body>,
+    {
+        let body = self.get_body(method, endpoint, body, headers).await?;
+        let bytes = hyper::body::to_bytes(body).await?;
+        String::from_utf8(bytes.to_vec()).map_err(Error::from)
+    }
+
+    pub(crate) async fn stream_upgrade<B>(
+        &self,
+        method: Method,
+        endpoint: impl AsRef<str>,
+        body: Payload<B>,
+    ) -> Result<impl AsyncRead + AsyncWrite + Send + 'static>
+    where
+        B: Into<Body>,
+    {
+        self.stream_upgrade_tokio(method, endpoint, body)
+            .await
+            .map(Compat::new)
+            .map_err(Error::from)
+    }
+
+    async fn get_body<B>(
+        &self,
+        method: Method,
+        endpoint: impl AsRef<str>,
+        body: Payload<B>,
+        headers: Option<Headers>,
+    ) -> Result<Body>
+    where
+        B: Into<Body>,
+    {
+        self.get_response(method, endpoint, body, headers)
+            .await
+            .map(Response::into_body)
+    }
+
+    /// Sends a request and returns the response if it was successful.
+    async fn get_response<B>(
+        &self,
+        method: Method,
+        endpoint: impl AsRef<str>,
+        body: Payload<B>,
+        headers: Option<Headers>,
+    ) -> Result<Response<Body>>
+    where
+        B: Into<Body>,
+    {
+        let response = self
+            .request(method.clone(), &endpoint, body, headers)
+            .await?;
+        log::trace!(
+            "got response {} {:?}",
+            response.status(),
+            response.headers()
+        );
+
+        let status = response.status();
+
+        match status {
+            // Success case: pass on the response
+            StatusCode::OK
+            | StatusCode::CREATED
+            | StatusCode::SWITCHING_PROTOCOLS
+            | StatusCode::NO_CONTENT => Ok(response),
+            _ => Err(Self::error_from_response(response, &method, endpoint.as_ref()).await),
+        }
+    }
+
+    /// Converts an unsuccessful response to a request with `method` to `endpoint` into an error.
+    async fn error_from_response(
+        response: Response<Body>,
+        method: &Method,
+        endpoint: &str,
+    ) -> Error {
+        let status = response.status();
+        let message_body = match hyper::body::to_bytes(response.into_body()).await {
+            Ok(bytes) => match String::from_utf8(bytes.to_vec()) {
+                Ok(body) => body,
+                Err(e) => return e.into(),
+            },
+            Err(e) => return e.into(),
+        };
+
+        let message = Self::get_error_message(&message_body).unwrap_or_else(|| {
+            status
+                .canonical_reason()
+                .unwrap_or("unknown error code")
+                .to_owned()
+        });
+        Error::from_status(status, message, method, endpoint)
+    }
+
+    pub(crate) async fn get_chunk_stream<B>(
+        &self,
+        method: Method,
+        endpoint: impl AsRef<str>,
+        body: Payload<B>,
+        headers: Option<Headers>,
+    ) -> Result<impl Stream<Item = Result<Bytes>> + Send + 'static>
+    where
+        B: Into<Body>,
+    {
+        self.get_body(method, endpoint, body, headers)
+            .await
+            .map(stream_body)
+    }
+
+    /// Like [`get_chunk_stream`](RequestClient::get_chunk_stream) but also returns the headers of
+    /// the response.
+    pub(crate) async fn get_chunk_stream_with_headers<B>(
+        &self,
+        method: Method,
+        endpoint: impl AsRef<str>,
+        body: Payload<B>,
+        headers: Option<Headers>,
+    ) -> Result<(
+        HeaderMap,
+        impl Stream<Item = Result<Bytes>> + Send + 'static,
+    )>
+    where
+        B: Into<Body>,
+    {
+        let (parts, body) = self
+            .get_response(method, endpoint, body, headers)
+            .await?
+            .into_parts();
+        Ok((parts.headers, stream_body(body)))
+    }
+
+    pub(crate) async fn get_json_chunk_stream<B>(
+        &self,
+        method: Method,
+        endpoint: impl AsRef<str>,
+        body: Payload<B>,
+        headers: Option<Headers>,
+    ) -> Result<impl Stream<Item = Result<Bytes>> + Send + 'static>
+    where
+        B: Into<Body>,
+    {
+        self.get_body(method, endpoint, body, headers)
+            .await
+            .map(stream_json_body)
+    }
+
+    /// Builds an HTTP request.
+    fn build_request<B>(
+        &self,
+        method: Method,
+        endpoint: impl AsRef<str>,
+        body: Payload<B>,
+        headers: Option<Headers>,
+        builder: hyper::http::request::Builder,
+    ) -> Result<Request<Body>>
+    where
+        B: Into<Body>,
+    {
+        let uri = self.transport.make_uri(endpoint.as_ref())?;
+        let req = builder.method(method).uri(&uri);
+        let mut req = req.header(header::HOST, "");
+
+        if let Some(h) = headers {
+            for (k, v) in h.into_iter() {
+                req = req.header(k, v);
+            }
+        }
+
+        if let Some(req_headers) = req.headers_mut() {
+            for (k, v) in self.headers.iter() {
+                if !req_headers.contains_key(k) {
+                    req_headers.insert(k, v.clone());
+                }
+            }
+        }
+
+        // early return
+        if body.is_none() {
+            return Ok(req.body(Body::empty())?);
+        }
+
+        let mime = body.mime_type();
+        if let Some(c) = mime {
+            req = req.header(header::CONTENT_TYPE, &c.to_string());
+        }
+
+        // it's ok to unwrap, we check that the body is not none
+        req.body(body.into_inner().unwrap().into())
+            .map_err(Error::from)
+    }
+
+    /// Send the given request to the docker daemon retrying it according to the retry policy.
+    async fn send_request(&self, req: Request<Body>) -> Result<Response<Body>> {
+        #[cfg(feature = "tracing")]
+        if self.traced {
+            let span = trace::request_span(&req);
+            return trace::instrument(span, self.send_request_retrying(req)).await;
+        }
+        self.send_request_retrying(req).await
+    }
+
+    async fn send_request_retrying(&self, req: Request<Body>) -> Result<Response<Body>> {
+        // streamed bodies are sent once, retrying would require buffering all of them
+        let retry = match &self.retry {
+            Some(retry)
+                if retry.applies_to(req.method(), self.retry_all)
+                    && HttpBody::size_hint(req.body()).exact().is_some() =>
+            {
+                retry
+            }
+            _ => return self.send_request_once(req).await,
+        };
+        retry.deposit();
+
+        // the body has to be buffered to be sent again
+        let (parts, body) = req.into_parts();
+        let body = hyper::body::to_bytes(body).await?;
+        let copy_request = || {
+            let mut builder = Request::builder()
+                .method(parts.method.clone())
+                .uri(parts.uri.clone())
+                .version(parts.version);
+            if let Some(headers) = builder.headers_mut() {
+                *headers = parts.headers.clone();
+            }
+            builder.body(Body::from(body.clone()))
+        };
+
+        let mut retries = 0;
+        loop {
+            let result = self.send_request_once(copy_request()?).await;
+            if retries < retry.policy.max_retries
+                && retry.policy.should_retry(&result)
+                && retry.withdraw()
+            {
+                retries += 1;
+                #[cfg(feature = "tracing")]
+                trace::record_attempts(retries + 1);
+                let backoff = retry.policy.backoff(retries);
+                log::debug!(
+                    "retrying {} {} in {:?} ({}/{})",
+                    parts.method,
+                    parts.uri,
+                    backoff,
+                    retries,
+                    retry.policy.max_retries
+                );
+                self.runtime.sleep(backoff).await;
+                continue;
+            }
+            return result;
+        }
+    }
+
+    /// Send the given request through the middleware to the docker daemon and return a Future of
+    /// the response. Fails with [`Error::RequestTimeout`](crate::Error::RequestTimeout) if the
+    /// response doesn't arrive within the configured timeout.
+    async fn send_request_once(&self, req: Request<Body>) -> Result<Response<Body>> {
+        log::trace!("sending request {} {}", req.method(), req.uri());
+        let response = self.middlewares.send_request(&self.transport, req);
+        match self.timeout {
+            Some(timeout) => runtime::timeout(self.runtime.as_ref(), timeout, response)
+                .await
+                .ok_or(Error::RequestTimeout(timeout))?,
+            None => response.await,
+        }
+    }
+
+    /// Makes an HTTP request, upgrading the connection to a TCP
+    /// stream on success.
+    ///
+    /// This method can be used for operations such as viewing
+    /// docker container logs interactively.
+    async fn stream_upgrade_tokio<B>(
+        &self,
+        method: Method,
+        endpoint: impl AsRef<str>,
+        body: Payload<B>,
+    ) -> Result<Box<dyn Connection>>
+    where
+        B: Into<Body>,
+    {
+        let builder = Request::builder()
+            .header(header::CONNECTION, "Upgrade")
+            .header(header::UPGRADE, "tcp");
+        self.upgrade(method, endpoint, body, builder)
+            .await
+            .map(|(_, connection)| connection)
+    }
+
+    /// Makes a GET request upgrading the connection to the WebSocket protocol.
+    #[cfg(feature = "websocket")]
+    pub(crate) async fn websocket_upgrade(
+        &self,
+        endpoint: impl AsRef<str>,
+    ) -> Result<crate::conn::WebSocket> {
+        use crate::conn::websocket;
+
+        let key = tokio_tungstenite::tungstenite::handshake::client::generate_key();
+        let builder = Request::builder()
+            .header(header::CONNECTION, "Upgrade")
+            .header(header::UPGRADE, "websocket")
+            .header(header::SEC_WEBSOCKET_VERSION, "13")
+            .header(header::SEC_WEBSOCKET_KEY, &key);
+        let (headers, connection) = self
+            .upgrade(Method::GET, endpoint, Payload::empty(), builder)
+            .await?;
+        websocket::verify_accept_key(&key, &headers)?;
+        Ok(websocket::WebSocket::client(connection).await)
+    }
+
+    /// Sends a request asking to upgrade the connection with `builder` and returns the headers
+    /// of the response and the upgraded connection.
+    async fn upgrade<B>(
+        &self,
+        method: Method,
+        endpoint: impl AsRef<str>,
+        body: Payload<B>,
+        builder: hyper::http::request::Builder,
+    ) -> Result<(HeaderMap, Box<dyn Connection>)>
+    where
+        B: Into<Body>,
+    {
+        let req = self.build_request(method.clone(), &endpoint, body, Headers::none(), builder)?;
+
+        let response = self.send_request(req).await?;
+        match response.status() {
+            StatusCode::SWITCHING_PROTOCOLS => {
+                let mut response = response;
+                let headers = response.headers().clone();
+                let connection = match response.extensions_mut().remove::<UpgradedConnection>() {
+                    Some(connection) => connection.into_inner(),
+                    None => Box::new(hyper::upgrade::on(response).await?),
+                };
+                Ok((headers, connection))
+            }
+            status if status.is_client_error() || status.is_server_error() => {
+                Err(Self::error_from_response(response, &method, endpoint.as_ref()).await)
+            }
+            _ => Err(Error::ConnectionNotUpgraded),
+        }
+    }
+
+    /// Extract the error message content from an HTTP response that
+    /// contains a Docker JSON error structure.
+    fn get_error_message(body: &str) -> Option<String> {
+        serde_json::from_str::<ErrorResponse>(body)
+            .map(|e| e.message)
+            .ok()
+    }
+}
+
+/// A bidirectional byte stream to the docker daemon, like a TCP connection or a connection that
+/// was upgraded from HTTP.
+pub trait Connection:
+    tokio::io::AsyncRead + tokio::io::AsyncWrite + Send + Unpin + 'static
+{
+}
+
+impl<T> Connection for T where
+    T: tokio::io::AsyncRead + tokio::io::AsyncWrite + Send + Unpin + 'static
+{
+}
+
+/// Response extension carrying an already upgraded connection, set by transports that don't
+/// upgrade connections through hyper.
+pub(crate) struct UpgradedConnection(std::sync::Mutex<Box<dyn Connection>>);
+
+impl UpgradedConnection {
+    #[cfg(any(test, feature = "testing"))]
+    pub fn new<C: Connection>(connection: C) -> Self {
+        UpgradedConnection(std::sync::Mutex::new(Box::new(connection)))
+    }
+
+    pub fn into_inner(self) -> Box<dyn Connection> {
+        self.0.into_inner().expect("upgraded connection lock")
+    }
+}
+
+#[pin_project]
+struct Compat<S> {
+    #[pin]
+    tokio_multiplexer: S,
+}
+
+impl<S> Compat<S> {
+    fn new(tokio_multiplexer: S) -> Self {
+        Self { tokio_multiplexer }
+    }
+}
+
+impl<S> AsyncRead for Compat<S>
+where
+    S: tokio::io::AsyncRead,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let mut readbuf = tokio::io::ReadBuf::new(buf);
+        match self.project().tokio_multiplexer.poll_read(cx, &mut readbuf) {
+            Poll::Pending => Poll::Pending,
+            Poll::Ready(Ok(())) => Poll::Ready(Ok(readbuf.filled().len())),
+            Poll::Ready(Err(e)) => Poll::Ready(Err(e)),
+        }
+    }
+}
+
+impl<S> AsyncWrite for Compat<S>
+where
+    S: tokio::io::AsyncWrite,
+{
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.project().tokio_multiplexer.poll_write(cx, buf)
+    }
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.project().tokio_multiplexer.poll_flush(cx)
+    }
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.project().tokio_multiplexer.poll_shutdown(cx)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct ErrorResponse {
+    message: String,
+}
+
+fn stream_body(body: Body) -> impl Stream<Item = Result<Bytes>> {
+    async fn unfold(mut body: Body) -> Option<(Result<Bytes>, Body)> {
+        body.next()
+            .await
+            .map(|chunk| (chunk.map_err(Error::from), body))
+    }
+
+    stream::unfold(body, unfold)
+}
+
+static JSON_WHITESPACE: &[u8] = b"\r\n";
+
+fn stream_json_body(body: Body) -> impl Stream<Item = Result<Bytes>> {
+    async fn unfold(mut body: Body) -> Option<(Result<Bytes>, Body)> {
+        let mut chunk = Vec::new();
+        while let Some(chnk) = body.next().await {
+            match chnk {
+                Ok(chnk) => {
+                    chunk.extend(chnk.to_vec());
+                    if chnk.ends_with(JSON_WHITESPACE) {
+                        break;
+                    }
+                }
+                Err(e) => {
+                    return Some((Err(Error::from(e)), body));
+                }
+            }
+        }
+
+        if chunk.is_empty() {
+            // the body ended
+            return None;
+        }
+
+        Some((Ok(Bytes::from(chunk)), body))
+    }
+
+    stream::unfold(body, unfold)
+}
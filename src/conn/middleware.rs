@@ -0,0 +1,211 @@
+//! Middleware wrapping the requests sent to the docker daemon.
+//!
+//! A [`Middleware`](Middleware) sees every request before it is sent over the
+//! [`Transport`](crate::conn::Transport) and the response returned for it, including streaming
+//! requests and requests that upgrade the connection. Middleware is registered with
+//! [`DockerBuilder::middleware`](crate::DockerBuilder::middleware) and runs in the order it was
+//! added, the first one added sees the request first and the response last.
+
+use crate::{conn::Transport, Result};
+
+use futures_util::future::BoxFuture;
+use hyper::{Body, Request, Response};
+
+use std::{fmt, sync::Arc};
+
+/// A layer around the transport that can inspect or modify requests and responses.
+///
+/// # Example
+///
+/// ```no_run
+/// use docker_api::conn::{Middleware, Next};
+/// use futures_util::future::BoxFuture;
+/// use hyper::{Body, Request, Response};
+/// use std::time::Instant;
+///
+/// struct Latency;
+///
+/// impl Middleware for Latency {
+///     fn handle<'a>(
+///         &'a self,
+///         req: Request<Body>,
+///         next: Next<'a>,
+///     ) -> BoxFuture<'a, docker_api::Result<Response<Body>>> {
+///         Box::pin(async move {
+///             let (method, path) = (req.method().clone(), req.uri().path().to_string());
+///             let start = Instant::now();
+///             let response = next.run(req).await;
+///             println!("{} {} took {:?}", method, path, start.elapsed());
+///             response
+///         })
+///     }
+/// }
+///
+/// # fn main() -> docker_api::Result<()> {
+/// let docker = docker_api::Docker::builder().middleware(Latency).build()?;
+/// # Ok(())
+/// # }
+/// ```
+pub trait Middleware: Send + Sync + 'static {
+    /// Handles a request. To continue sending the request pass it to [`Next::run`](Next::run),
+    /// otherwise a response or an error can be returned directly.
+    ///
+    /// For streaming requests the returned future completes once the response headers arrive, the
+    /// body is streamed afterwards.
+    fn handle<'a>(
+        &'a self,
+        req: Request<Body>,
+        next: Next<'a>,
+    ) -> BoxFuture<'a, Result<Response<Body>>>;
+}
+
+impl<M: Middleware> Middleware for Arc<M> {
+    fn handle<'a>(
+        &'a self,
+        req: Request<Body>,
+        next: Next<'a>,
+    ) -> BoxFuture<'a, Result<Response<Body>>> {
+        self.as_ref().handle(req, next)
+    }
+}
+
+/// The remaining middleware and the transport a request is sent over.
+pub struct Next<'a> {
+    middlewares: &'a [Arc<dyn Middleware>],
+    transport: &'a Transport,
+}
+
+impl<'a> Next<'a> {
+    /// Passes the request on to the next middleware or, if this was the last one, sends it to the
+    /// docker daemon.
+    pub async fn run(self, req: Request<Body>) -> Result<Response<Body>> {
+        match self.middlewares.split_first() {
+            Some((middleware, rest)) => {
+                middleware
+                    .handle(
+                        req,
+                        Next {
+                            middlewares: rest,
+                            transport: self.transport,
+                        },
+                    )
+                    .await
+            }
+            None => self.transport.send_request(req).await,
+        }
+    }
+}
+
+impl fmt::Debug for Next<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Next")
+            .field("middlewares", &self.middlewares.len())
+            .field("transport", &self.transport)
+            .finish()
+    }
+}
+
+#[derive(Clone, Default)]
+/// Ordered list of middleware applied to every request.
+pub(crate) struct Middlewares(Vec<Arc<dyn Middleware>>);
+
+impl Middlewares {
+    pub fn push<M: Middleware>(&mut self, middleware: M) {
+        self.0.push(Arc::new(middleware));
+    }
+
+    /// Sends `req` through all middleware and then over `transport`.
+    pub async fn send_request(
+        &self,
+        transport: &Transport,
+        req: Request<Body>,
+    ) -> Result<Response<Body>> {
+        Next {
+            middlewares: &self.0,
+            transport,
+        }
+        .run(req)
+        .await
+    }
+}
+
+impl fmt::Debug for Middlewares {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Middlewares({})", self.0.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Docker;
+
+    use futures_util::TryStreamExt;
+    use std::sync::Mutex;
+
+    /// Records the requests passing through and tags them with a header.
+    struct Audit(Mutex<Vec<String>>);
+
+    impl Middleware for Audit {
+        fn handle<'a>(
+            &'a self,
+            mut req: Request<Body>,
+            next: Next<'a>,
+        ) -> BoxFuture<'a, Result<Response<Body>>> {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("{} {}", req.method(), req.uri().path()));
+            req.headers_mut()
+                .insert("x-audit", hyper::header::HeaderValue::from_static("1"));
+            Box::pin(next.run(req))
+        }
+    }
+
+    /// Answers every request without sending it to the transport.
+    struct Respond;
+
+    impl Middleware for Respond {
+        fn handle<'a>(
+            &'a self,
+            req: Request<Body>,
+            _: Next<'a>,
+        ) -> BoxFuture<'a, Result<Response<Body>>> {
+            let body = if req.headers().contains_key("x-audit") {
+                "audited"
+            } else {
+                "not audited"
+            };
+            Box::pin(async move { Ok(Response::new(Body::from(body))) })
+        }
+    }
+
+    #[tokio::test]
+    async fn runs_middleware_in_order() {
+        let audit = Arc::new(Audit(Mutex::new(vec![])));
+        let docker = Docker::builder()
+            .host("tcp://127.0.0.1:1")
+            .middleware(audit.clone())
+            .middleware(Respond)
+            .build()
+            .unwrap();
+
+        docker.volumes().get("data").delete().await.unwrap();
+        let logs: Vec<_> = docker
+            .containers()
+            .get("app")
+            .logs(&Default::default())
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(logs.concat(), b"audited");
+        assert_eq!(
+            *audit.0.lock().unwrap(),
+            vec![
+                "DELETE /v1.41/volumes/data".to_string(),
+                "GET /v1.41/containers/app/logs".to_string()
+            ]
+        );
+    }
+}
@@ -0,0 +1,368 @@
+//! An in-memory transport answering requests with canned responses, for testing code built on
+//! this crate without a docker daemon.
+//!
+//! # Example
+//!
+//! ```
+//! use docker_api::{conn::Mock, Docker};
+//! use hyper::{Method, StatusCode};
+//!
+//! # async fn run() -> docker_api::Result<()> {
+//! let mock = Mock::new();
+//! mock.on(Method::POST, "/containers/create").json(
+//!     StatusCode::CREATED,
+//!     &serde_json::json!({"Id": "4f2a", "Warnings": []}),
+//! );
+//! mock.on(Method::POST, "/containers/4f2a/start")
+//!     .status(StatusCode::NO_CONTENT);
+//!
+//! let docker = Docker::mock(mock.clone());
+//! let container = docker
+//!     .containers()
+//!     .create(&docker_api::container::ContainerCreateOpts::builder("ubuntu").build())
+//!     .await?;
+//! container.start().await?;
+//!
+//! let requests = mock.requests();
+//! assert_eq!(requests[1].path, "/containers/4f2a/start");
+//! # Ok(())
+//! # }
+//! # tokio::runtime::Runtime::new().unwrap().block_on(run()).unwrap();
+//! ```
+
+use crate::{
+    conn::{client::UpgradedConnection, TtyChunk},
+    util::url::strip_version,
+    Result,
+};
+
+use futures_util::stream;
+use hyper::{
+    body::Bytes,
+    header::{self, HeaderMap, HeaderValue},
+    Body, Method, Request, Response, StatusCode,
+};
+use serde::Serialize;
+use tokio::io::DuplexStream;
+
+use std::{
+    collections::VecDeque,
+    fmt, io,
+    sync::{Arc, Mutex},
+};
+
+/// Size of the buffer of in-memory connections created by [`MockRoute::upgrade`](MockRoute::upgrade).
+const CONNECTION_BUFFER_SIZE: usize = 64 * 1024;
+
+#[derive(Clone, Default)]
+/// Transport serving responses registered with [`on`](Mock::on) and recording all requests it
+/// receives. Clones share the registered responses and recorded requests.
+///
+/// Endpoints are matched by method and path without the API version prefix and query, so a
+/// response registered for `/containers/json` answers `GET /v1.41/containers/json?all=true`.
+/// Each registered response answers a single request, responses registered for the same endpoint
+/// are returned in order. Requests without a registered response are answered with
+/// `501 Not Implemented`.
+pub struct Mock {
+    state: Arc<Mutex<MockState>>,
+}
+
+#[derive(Default)]
+struct MockState {
+    responses: Vec<(Method, String, MockResponse)>,
+    requests: Vec<MockRequest>,
+}
+
+enum MockResponse {
+    Body {
+        status: StatusCode,
+        headers: HeaderMap,
+        chunks: VecDeque<Bytes>,
+    },
+    Upgrade(DuplexStream),
+}
+
+#[derive(Clone, Debug)]
+/// A request received by a [`Mock`](Mock) transport.
+pub struct MockRequest {
+    pub method: Method,
+    /// Path of the request without the API version prefix.
+    pub path: String,
+    pub query: Option<String>,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+}
+
+impl MockRequest {
+    /// Deserializes the JSON body of the request.
+    pub fn json<T: serde::de::DeserializeOwned>(&self) -> Result<T> {
+        Ok(serde_json::from_slice(&self.body)?)
+    }
+}
+
+impl Mock {
+    /// Creates a mock transport without any registered responses.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a response for the next request to `endpoint` with `method`.
+    pub fn on<E>(&self, method: Method, endpoint: E) -> MockRoute
+    where
+        E: Into<String>,
+    {
+        MockRoute {
+            mock: self.clone(),
+            method,
+            endpoint: endpoint.into(),
+            headers: HeaderMap::new(),
+        }
+    }
+
+    /// Returns all requests received so far in the order they arrived.
+    pub fn requests(&self) -> Vec<MockRequest> {
+        self.state().requests.clone()
+    }
+
+    /// Returns the number of registered responses that weren't requested yet.
+    pub fn pending(&self) -> usize {
+        self.state().responses.len()
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, MockState> {
+        self.state.lock().expect("mock state lock")
+    }
+
+    pub(crate) async fn send_request(&self, req: Request<Body>) -> Result<Response<Body>> {
+        let (parts, body) = req.into_parts();
+        let body = hyper::body::to_bytes(body).await?;
+        #[cfg(feature = "websocket")]
+        let websocket_accept = crate::conn::websocket::accept_key(&parts.headers);
+        let method = parts.method;
+        let path = strip_version(parts.uri.path()).to_string();
+
+        let response = {
+            let mut state = self.state();
+            state.requests.push(MockRequest {
+                method: method.clone(),
+                path: path.clone(),
+                query: parts.uri.query().map(str::to_string),
+                headers: parts.headers,
+                body,
+            });
+            state
+                .responses
+                .iter()
+                .position(|(m, endpoint, _)| *m == method && *endpoint == path)
+                .map(|i| state.responses.remove(i).2)
+        };
+
+        match response {
+            Some(MockResponse::Body {
+                status,
+                headers,
+                chunks,
+            }) => {
+                let mut response = Response::new(Body::wrap_stream(stream::iter(
+                    chunks.into_iter().map(Ok::<_, io::Error>),
+                )));
+                *response.status_mut() = status;
+                *response.headers_mut() = headers;
+                Ok(response)
+            }
+            Some(MockResponse::Upgrade(connection)) => {
+                let mut response = Response::new(Body::empty());
+                *response.status_mut() = StatusCode::SWITCHING_PROTOCOLS;
+                #[cfg(feature = "websocket")]
+                if let Some(accept) = websocket_accept {
+                    response
+                        .headers_mut()
+                        .insert(header::SEC_WEBSOCKET_ACCEPT, accept);
+                }
+                response
+                    .extensions_mut()
+                    .insert(UpgradedConnection::new(connection));
+                Ok(response)
+            }
+            None => {
+                let mut response = Response::new(Body::from(
+                    serde_json::json!({
+                        "message": format!("no mock response for {} {}", method, path)
+                    })
+                    .to_string(),
+                ));
+                *response.status_mut() = StatusCode::NOT_IMPLEMENTED;
+                Ok(response)
+            }
+        }
+    }
+}
+
+impl fmt::Debug for Mock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.state();
+        f.debug_struct("Mock")
+            .field("pending", &state.responses.len())
+            .field("requests", &state.requests.len())
+            .finish()
+    }
+}
+
+/// A response being registered with a [`Mock`](Mock) transport.
+pub struct MockRoute {
+    mock: Mock,
+    method: Method,
+    endpoint: String,
+    headers: HeaderMap,
+}
+
+impl MockRoute {
+    /// Adds a header to the response.
+    pub fn header(mut self, key: header::HeaderName, value: HeaderValue) -> Self {
+        self.headers.insert(key, value);
+        self
+    }
+
+    /// Responds with `status` and an empty body.
+    pub fn status(self, status: StatusCode) {
+        self.chunks(status, Vec::<Bytes>::new())
+    }
+
+    /// Responds with `status` and `body` serialized as JSON.
+    pub fn json<T>(mut self, status: StatusCode, body: &T)
+    where
+        T: Serialize,
+    {
+        let body = serde_json::to_vec(body).expect("serializable mock response");
+        self.headers.insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/json"),
+        );
+        self.chunks(status, vec![body])
+    }
+
+    /// Responds with `status` and a body streamed in `chunks`.
+    pub fn chunks<C, B>(self, status: StatusCode, chunks: C)
+    where
+        C: IntoIterator<Item = B>,
+        B: Into<Bytes>,
+    {
+        let response = MockResponse::Body {
+            status,
+            headers: self.headers,
+            chunks: chunks.into_iter().map(Into::into).collect(),
+        };
+        self.mock
+            .state()
+            .responses
+            .push((self.method, self.endpoint, response));
+    }
+
+    /// Responds with a multiplexed stream of `frames` as used for logs and attach when the
+    /// container has no TTY. Each frame is sent as a separate chunk.
+    pub fn tty<F>(self, frames: F)
+    where
+        F: IntoIterator<Item = TtyChunk>,
+    {
+        let frames: Vec<_> = frames.into_iter().map(|frame| frame.encode()).collect();
+        self.chunks(StatusCode::OK, frames)
+    }
+
+    /// Upgrades the connection and returns the other end of it. Bytes written to the returned
+    /// stream are read by the client and the other way around. Use
+    /// [`TtyChunk::encode`](TtyChunk::encode) to write multiplexed frames.
+    pub fn upgrade(self) -> DuplexStream {
+        let (client, server) = tokio::io::duplex(CONNECTION_BUFFER_SIZE);
+        self.mock.state().responses.push((
+            self.method,
+            self.endpoint,
+            MockResponse::Upgrade(client),
+        ));
+        server
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Docker;
+
+    use futures_util::{AsyncWriteExt, StreamExt, TryStreamExt};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt as _};
+
+    #[tokio::test]
+    async fn serves_registered_responses() {
+        let mock = Mock::new();
+        mock.on(Method::POST, "/images/create").chunks(
+            StatusCode::OK,
+            vec![
+                r#"{"status":"Pulling from library/ubuntu","id":"latest"}"#,
+                r#"{"status":"Digest: sha256:abc"}"#,
+            ],
+        );
+        mock.on(Method::GET, "/containers/web/logs").tty(vec![
+            TtyChunk::StdOut(b"hello\n".to_vec()),
+            TtyChunk::StdErr(b"oops\n".to_vec()),
+        ]);
+        let docker = Docker::mock(mock.clone());
+
+        let pull: Vec<_> = docker
+            .images()
+            .pull(&crate::image::PullOpts::builder().image("ubuntu").build())
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(pull.len(), 2);
+
+        let logs: Vec<_> = docker
+            .containers()
+            .get("web")
+            .logs(&Default::default())
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(
+            logs.concat(),
+            [
+                TtyChunk::StdOut(b"hello\n".to_vec()).encode(),
+                TtyChunk::StdErr(b"oops\n".to_vec()).encode()
+            ]
+            .concat()
+        );
+
+        let err = docker.containers().get("web").start().await.unwrap_err();
+        assert_eq!(err.status_code(), Some(StatusCode::NOT_IMPLEMENTED));
+
+        let requests = mock.requests();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[0].path, "/images/create");
+        assert!(requests[0]
+            .query
+            .as_deref()
+            .unwrap()
+            .contains("fromImage=ubuntu"));
+        assert_eq!(mock.pending(), 0);
+    }
+
+    #[tokio::test]
+    async fn upgrades_connections() {
+        let mock = Mock::new();
+        let mut server = mock.on(Method::POST, "/containers/web/attach").upgrade();
+        let docker = Docker::mock(mock);
+
+        let mut multiplexer = docker.containers().get("web").attach().await.unwrap();
+        server
+            .write_all(&TtyChunk::StdOut(b"ready".to_vec()).encode())
+            .await
+            .unwrap();
+        match multiplexer.next().await {
+            Some(Ok(TtyChunk::StdOut(out))) => assert_eq!(out, b"ready"),
+            chunk => panic!("unexpected chunk {:?}", chunk),
+        }
+
+        multiplexer.write_all(b"ls\n").await.unwrap();
+        let mut stdin = [0; 3];
+        server.read_exact(&mut stdin).await.unwrap();
+        assert_eq!(&stdin, b"ls\n");
+    }
+}
@@ -1,45 +1,49 @@
 //! Connection related items
 
+pub(crate) mod client;
+pub(crate) mod middleware;
+#[cfg(any(test, feature = "testing"))]
+pub(crate) mod mock;
+#[cfg(any(test, feature = "testing"))]
+pub(crate) mod record;
+pub(crate) mod retry;
+pub(crate) mod runtime;
+pub(crate) mod ssh;
+#[cfg(any(feature = "tls", feature = "rustls-tls"))]
+pub(crate) mod tls;
+#[cfg(feature = "tracing")]
+pub(crate) mod trace;
 pub(crate) mod transport;
 pub(crate) mod tty;
-
+#[cfg(feature = "websocket")]
+pub(crate) mod websocket;
+
+pub use client::Connection;
+pub(crate) use client::RequestClient;
+pub(crate) use middleware::Middlewares;
+pub use middleware::{Middleware, Next};
+#[cfg(any(test, feature = "testing"))]
+#[cfg_attr(docsrs, doc(cfg(feature = "testing")))]
+pub use mock::{Mock, MockRequest, MockRoute};
+#[cfg(any(test, feature = "testing"))]
+#[cfg_attr(docsrs, doc(cfg(feature = "testing")))]
+pub use record::{
+    Content, Fixture, Frame, Interaction, RecordedRequest, RecordedResponse, Recorder, Replay,
+};
+pub(crate) use retry::Retry;
+pub use retry::{RetryPolicy, RetryPolicyBuilder};
+#[cfg(feature = "runtime-async-std")]
+pub use runtime::AsyncStd;
+#[cfg(feature = "runtime-tokio")]
+pub use runtime::Tokio;
+pub use runtime::{FuturesIo, Runtime, RuntimeConnector, RuntimeStream};
+pub use ssh::*;
+#[cfg(any(feature = "tls", feature = "rustls-tls"))]
+pub use tls::*;
 pub use transport::*;
 pub use tty::*;
+#[cfg(feature = "websocket")]
+#[cfg_attr(docsrs, doc(cfg(feature = "websocket")))]
+pub use websocket::WebSocket;
 
 pub(crate) const AUTH_HEADER: &str = "X-Registry-Auth";
-
-use hyper::client::HttpConnector;
-
-#[cfg(feature = "tls")]
-use {
-    crate::{Error, Result},
-    hyper_openssl::HttpsConnector,
-    openssl::ssl::{SslConnector, SslFiletype, SslMethod},
-    std::path::Path,
-};
-
-pub(crate) fn get_http_connector() -> HttpConnector {
-    let mut http = HttpConnector::new();
-    http.enforce_http(false);
-
-    http
-}
-
-#[cfg(feature = "tls")]
-pub(crate) fn get_https_connector(
-    cert_path: &Path,
-    verify: bool,
-) -> Result<HttpsConnector<HttpConnector>> {
-    let mut ssl = SslConnector::builder(SslMethod::tls())?;
-    ssl.set_cipher_list("DEFAULT")?;
-    ssl.set_certificate_file(&cert_path.join("cert.pem"), SslFiletype::PEM)?;
-    ssl.set_private_key_file(&cert_path.join("key.pem"), SslFiletype::PEM)?;
-    verify.then(|| ssl.set_ca_file(&cert_path.join("ca.pem")));
-
-    HttpsConnector::with_connector(get_http_connector(), ssl).map_err(Error::from)
-}
-
-#[cfg(unix)]
-pub(crate) fn get_unix_connector() -> hyperlocal::UnixConnector {
-    hyperlocal::UnixConnector
-}
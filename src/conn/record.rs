@@ -0,0 +1,904 @@
+//! Recording of the traffic with a docker daemon into fixtures and replaying it later, for
+//! deterministic tests against responses of a real docker engine.
+//!
+//! A [`Recorder`](Recorder) registered with [`DockerBuilder::record`](crate::DockerBuilder::record)
+//! captures every request and its response, including each chunk of streamed responses and the
+//! bytes exchanged over upgraded connections. The recorded [`Fixture`](Fixture) is saved as JSON
+//! and served back by a [`Replay`](Replay) transport.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use docker_api::{
+//!     conn::{Recorder, Replay},
+//!     Docker,
+//! };
+//!
+//! # async fn run() -> docker_api::Result<()> {
+//! // record a session against a real docker engine once
+//! let recorder = Recorder::new();
+//! let docker = Docker::builder().record(recorder.clone()).build()?;
+//! docker.version().await?;
+//! recorder.save("tests/fixtures/version.json")?;
+//!
+//! // and replay it in tests without one
+//! let docker = Docker::replay(Replay::load("tests/fixtures/version.json")?);
+//! docker.version().await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    conn::{
+        client::{Connection, UpgradedConnection},
+        Middleware, Next,
+    },
+    util::url::strip_version,
+    Result,
+};
+
+use futures_util::{future::BoxFuture, stream, StreamExt};
+use hyper::{
+    body::Bytes,
+    header::{self, HeaderMap, HeaderName, HeaderValue},
+    Body, Request, Response, StatusCode,
+};
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fmt, fs, io,
+    path::Path,
+    pin::Pin,
+    sync::{Arc, Mutex, MutexGuard},
+    task::{Context, Poll, Waker},
+};
+
+/// Headers carrying credentials, their values are not recorded.
+const REDACTED_HEADERS: &[&str] = &["authorization", "x-registry-auth", "x-registry-config"];
+const REDACTED: &str = "<redacted>";
+
+/// Callback scrubbing an interaction before it's added to a fixture.
+type Redaction = Arc<dyn Fn(&mut Interaction) + Send + Sync>;
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+/// Requests and responses exchanged with a docker daemon in the order the requests were sent.
+pub struct Fixture {
+    pub interactions: Vec<Interaction>,
+}
+
+impl Fixture {
+    /// Reads a fixture from a JSON file.
+    pub fn load<P>(path: P) -> Result<Fixture>
+    where
+        P: AsRef<Path>,
+    {
+        let file = fs::File::open(path)?;
+        Ok(serde_json::from_reader(io::BufReader::new(file))?)
+    }
+
+    /// Writes this fixture to a JSON file, replacing it if it exists.
+    pub fn save<P>(&self, path: P) -> Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        let file = fs::File::create(path)?;
+        serde_json::to_writer_pretty(io::BufWriter::new(file), self)?;
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+/// A single request and the response the docker daemon sent for it.
+pub struct Interaction {
+    pub request: RecordedRequest,
+    pub response: RecordedResponse,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+/// A request sent to the docker daemon.
+pub struct RecordedRequest {
+    pub method: String,
+    /// Path of the request including the API version prefix.
+    pub path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub query: Option<String>,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Content::is_empty")]
+    pub body: Content,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+/// A response of the docker daemon.
+pub struct RecordedResponse {
+    pub status: u16,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// The body in the chunks it was received in.
+    #[serde(default)]
+    pub chunks: Vec<Content>,
+    /// Data exchanged over the connection after it was upgraded, in the order it was sent.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub upgrade: Vec<Frame>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+/// Data exchanged over an upgraded connection.
+pub enum Frame {
+    /// Data the client read from the docker daemon.
+    Read(Content),
+    /// Data the client wrote to the docker daemon.
+    Write(Content),
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(from = "ContentRepr", into = "ContentRepr")]
+/// Raw data stored as a string when it is valid UTF-8 and base64 encoded otherwise.
+pub struct Content(pub Vec<u8>);
+
+impl Content {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum ContentRepr {
+    Text(String),
+    Base64 { base64: String },
+}
+
+impl From<ContentRepr> for Content {
+    fn from(repr: ContentRepr) -> Self {
+        match repr {
+            ContentRepr::Text(text) => Content(text.into_bytes()),
+            // invalid base64 can only come from a hand edited fixture, keep it as it is
+            ContentRepr::Base64 { base64 } => {
+                Content(base64::decode(&base64).unwrap_or_else(|_| base64.into_bytes()))
+            }
+        }
+    }
+}
+
+impl From<Content> for ContentRepr {
+    fn from(content: Content) -> Self {
+        match String::from_utf8(content.0) {
+            Ok(text) => ContentRepr::Text(text),
+            Err(e) => ContentRepr::Base64 {
+                base64: base64::encode(e.as_bytes()),
+            },
+        }
+    }
+}
+
+fn headers_map(headers: &HeaderMap) -> BTreeMap<String, String> {
+    let mut map = BTreeMap::<String, String>::new();
+    for (key, value) in headers {
+        let value = if REDACTED_HEADERS.contains(&key.as_str()) {
+            REDACTED
+        } else {
+            value.to_str().unwrap_or_default()
+        };
+        map.entry(key.as_str().to_string())
+            .and_modify(|v| {
+                v.push_str(", ");
+                v.push_str(value)
+            })
+            .or_insert_with(|| value.to_string());
+    }
+    map
+}
+
+/// Removes the credentials and secret data recorded by default from `interaction`: the
+/// request bodies of `/auth` and of the `/secrets` endpoints and the identity token returned by
+/// `/auth`.
+fn redact_secrets(interaction: &mut Interaction) {
+    let path = strip_version(&interaction.request.path);
+    let is_auth = path == "/auth";
+    if (is_auth || path.starts_with("/secrets/")) && !interaction.request.body.is_empty() {
+        interaction.request.body = Content(REDACTED.into());
+    }
+    if !is_auth {
+        return;
+    }
+    for chunk in &mut interaction.response.chunks {
+        let mut response = match serde_json::from_slice::<serde_json::Value>(&chunk.0) {
+            Ok(response) => response,
+            Err(_) => continue,
+        };
+        match response.get_mut("IdentityToken") {
+            Some(token) if token.as_str().is_some_and(|token| !token.is_empty()) => {
+                *token = REDACTED.into();
+                *chunk = Content(response.to_string().into_bytes());
+            }
+            _ => {}
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+/// Middleware recording all requests sent to the docker daemon and the responses to them into a
+/// [`Fixture`](Fixture). Clones share the recorded interactions.
+///
+/// Streamed responses and upgraded connections are recorded while they are read, so the fixture
+/// is complete once all streams returned by the API are consumed or dropped.
+///
+/// Values of the `Authorization`, `X-Registry-Auth` and `X-Registry-Config` headers, the request
+/// bodies of `/auth` and the `/secrets` endpoints and the identity token returned by `/auth` are
+/// replaced with `<redacted>`. All other bodies are recorded verbatim, so a fixture can still
+/// contain sensitive data like environment variables of created containers, commands of execs,
+/// logs or the data of configs. Scrub them with [`redact`](Recorder::redact) and review fixtures
+/// before checking them in.
+pub struct Recorder {
+    interactions: Arc<Mutex<Vec<Interaction>>>,
+    redactions: Vec<Redaction>,
+}
+
+impl Recorder {
+    /// Creates a recorder without any recorded interactions.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Runs `redaction` on every interaction before it's added to a fixture, after the default
+    /// redactions. Use it to remove sensitive data the recorder doesn't know about.
+    ///
+    /// ```
+    /// use docker_api::conn::{Content, Recorder};
+    ///
+    /// let recorder = Recorder::new().redact(|interaction| {
+    ///     if interaction.request.path.ends_with("/containers/create") {
+    ///         interaction.request.body = Content(b"{}".to_vec());
+    ///     }
+    /// });
+    /// ```
+    pub fn redact<F>(mut self, redaction: F) -> Self
+    where
+        F: Fn(&mut Interaction) + Send + Sync + 'static,
+    {
+        self.redactions.push(Arc::new(redaction));
+        self
+    }
+
+    /// Returns a fixture with the interactions recorded so far with credentials and secrets
+    /// redacted. Requests that failed without a response, for example because the connection
+    /// was refused, are left out.
+    pub fn fixture(&self) -> Fixture {
+        let mut interactions: Vec<_> = self
+            .interactions()
+            .iter()
+            .filter(|i| i.response.status != 0)
+            .cloned()
+            .collect();
+        for interaction in &mut interactions {
+            redact_secrets(interaction);
+            for redaction in &self.redactions {
+                redaction(interaction);
+            }
+        }
+        Fixture { interactions }
+    }
+
+    /// Writes the interactions recorded so far to a JSON file, see [`Fixture::save`](Fixture::save).
+    pub fn save<P>(&self, path: P) -> Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        self.fixture().save(path)
+    }
+
+    fn interactions(&self) -> MutexGuard<'_, Vec<Interaction>> {
+        self.interactions.lock().expect("recorder lock")
+    }
+
+    /// Runs `f` on the interaction at `index`.
+    fn update(&self, index: usize, f: impl FnOnce(&mut Interaction)) {
+        if let Some(interaction) = self.interactions().get_mut(index) {
+            f(interaction)
+        }
+    }
+
+    async fn record(&self, req: Request<Body>, next: Next<'_>) -> Result<Response<Body>> {
+        let (parts, body) = req.into_parts();
+        let index = {
+            let mut interactions = self.interactions();
+            interactions.push(Interaction {
+                request: RecordedRequest {
+                    method: parts.method.to_string(),
+                    path: parts.uri.path().to_string(),
+                    query: parts.uri.query().map(str::to_string),
+                    headers: headers_map(&parts.headers),
+                    body: Content::default(),
+                },
+                response: RecordedResponse {
+                    status: 0,
+                    headers: BTreeMap::new(),
+                    chunks: vec![],
+                    upgrade: vec![],
+                },
+            });
+            interactions.len() - 1
+        };
+
+        // the request body is recorded while the transport sends it
+        let recorder = self.clone();
+        let body = Body::wrap_stream(body.inspect(move |chunk| {
+            if let Ok(chunk) = chunk {
+                recorder.update(index, |i| i.request.body.0.extend_from_slice(chunk));
+            }
+        }));
+        let mut response = match next.run(Request::from_parts(parts, body)).await {
+            Ok(response) => response,
+            // the interaction keeps status 0 and is left out of the fixture
+            Err(e) => return Err(e),
+        };
+
+        self.update(index, |i| {
+            i.response.status = response.status().as_u16();
+            i.response.headers = headers_map(response.headers());
+        });
+
+        if response.status() == StatusCode::SWITCHING_PROTOCOLS {
+            let connection: Box<dyn Connection> =
+                match response.extensions_mut().remove::<UpgradedConnection>() {
+                    Some(connection) => connection.into_inner(),
+                    None => Box::new(hyper::upgrade::on(&mut response).await?),
+                };
+            response
+                .extensions_mut()
+                .insert(UpgradedConnection::new(RecordingConnection {
+                    inner: connection,
+                    recorder: self.clone(),
+                    index,
+                }));
+            return Ok(response);
+        }
+
+        let recorder = self.clone();
+        let (parts, body) = response.into_parts();
+        let body = Body::wrap_stream(body.inspect(move |chunk| {
+            if let Ok(chunk) = chunk {
+                recorder.update(index, |i| i.response.chunks.push(Content(chunk.to_vec())));
+            }
+        }));
+        Ok(Response::from_parts(parts, body))
+    }
+}
+
+impl Middleware for Recorder {
+    fn handle<'a>(
+        &'a self,
+        req: Request<Body>,
+        next: Next<'a>,
+    ) -> BoxFuture<'a, Result<Response<Body>>> {
+        Box::pin(self.record(req, next))
+    }
+}
+
+impl fmt::Debug for Recorder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Recorder")
+            .field("interactions", &self.interactions().len())
+            .field("redactions", &self.redactions.len())
+            .finish()
+    }
+}
+
+/// An upgraded connection recording the data passing through it.
+struct RecordingConnection {
+    inner: Box<dyn Connection>,
+    recorder: Recorder,
+    index: usize,
+}
+
+impl RecordingConnection {
+    /// Records `data`, merging it with the previous frame if it went in the same direction.
+    fn record(&self, data: &[u8], read: bool) {
+        if data.is_empty() {
+            return;
+        }
+        self.recorder.update(self.index, |i| {
+            match (i.response.upgrade.last_mut(), read) {
+                (Some(Frame::Read(content)), true) | (Some(Frame::Write(content)), false) => {
+                    content.0.extend_from_slice(data)
+                }
+                (_, true) => i.response.upgrade.push(Frame::Read(Content(data.to_vec()))),
+                (_, false) => i
+                    .response
+                    .upgrade
+                    .push(Frame::Write(Content(data.to_vec()))),
+            }
+        });
+    }
+}
+
+impl AsyncRead for RecordingConnection {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let filled = buf.filled().len();
+        let poll = Pin::new(&mut self.inner).poll_read(cx, buf);
+        if let Poll::Ready(Ok(())) = poll {
+            self.record(&buf.filled()[filled..], true);
+        }
+        poll
+    }
+}
+
+impl AsyncWrite for RecordingConnection {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let poll = Pin::new(&mut self.inner).poll_write(cx, buf);
+        if let Poll::Ready(Ok(n)) = poll {
+            self.record(&buf[..n], false);
+        }
+        poll
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+#[derive(Clone)]
+/// Transport answering requests with the responses of a [`Fixture`](Fixture). Clones share the
+/// remaining responses.
+///
+/// Each request is answered with the first interaction not replayed yet that has the same
+/// method, path and query parameters, the API version prefix and the order of query parameters
+/// are ignored. Responses to the same endpoint are thus served in the order they were recorded.
+/// Requests without a matching interaction are answered with `501 Not Implemented`.
+///
+/// Upgraded connections replay the recorded frames in order, frames the client wrote are awaited
+/// before the data read after them is sent.
+pub struct Replay {
+    interactions: Arc<Mutex<Vec<Interaction>>>,
+}
+
+impl Replay {
+    /// Creates a transport replaying `fixture`.
+    pub fn new(fixture: Fixture) -> Self {
+        Replay {
+            interactions: Arc::new(Mutex::new(fixture.interactions)),
+        }
+    }
+
+    /// Creates a transport replaying the fixture saved at `path`.
+    pub fn load<P>(path: P) -> Result<Replay>
+    where
+        P: AsRef<Path>,
+    {
+        Fixture::load(path).map(Replay::new)
+    }
+
+    /// Returns the number of recorded interactions that weren't replayed yet.
+    pub fn pending(&self) -> usize {
+        self.interactions().len()
+    }
+
+    fn interactions(&self) -> MutexGuard<'_, Vec<Interaction>> {
+        self.interactions.lock().expect("replay lock")
+    }
+
+    pub(crate) async fn send_request(&self, req: Request<Body>) -> Result<Response<Body>> {
+        let method = req.method().to_string();
+        let path = strip_version(req.uri().path()).to_string();
+        let query = query_pairs(req.uri().query());
+        #[cfg(feature = "websocket")]
+        let websocket_accept = crate::conn::websocket::accept_key(req.headers());
+        // drain the body like a docker daemon would
+        hyper::body::to_bytes(req.into_body()).await?;
+
+        let interaction = {
+            let mut interactions = self.interactions();
+            interactions
+                .iter()
+                .position(|i| {
+                    i.request.method == method
+                        && strip_version(&i.request.path) == path
+                        && query_pairs(i.request.query.as_deref()) == query
+                })
+                .map(|i| interactions.remove(i))
+        };
+        let interaction = match interaction {
+            Some(interaction) => interaction,
+            None => {
+                let mut response = Response::new(Body::from(
+                    serde_json::json!({
+                        "message": format!("no recorded response for {} {}", method, path)
+                    })
+                    .to_string(),
+                ));
+                *response.status_mut() = StatusCode::NOT_IMPLEMENTED;
+                return Ok(response);
+            }
+        };
+
+        let recorded = interaction.response;
+        let mut response = if recorded.status == StatusCode::SWITCHING_PROTOCOLS.as_u16() {
+            let mut response = Response::new(Body::empty());
+            response
+                .extensions_mut()
+                .insert(UpgradedConnection::new(ReplayConnection::new(
+                    recorded.upgrade,
+                )));
+            response
+        } else {
+            Response::new(Body::wrap_stream(stream::iter(
+                recorded
+                    .chunks
+                    .into_iter()
+                    .map(|chunk| Ok::<_, io::Error>(Bytes::from(chunk.0))),
+            )))
+        };
+        *response.status_mut() =
+            StatusCode::from_u16(recorded.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        for (key, value) in recorded.headers {
+            // the body is replayed as recorded chunks so its original framing doesn't apply
+            if key == header::CONTENT_LENGTH.as_str() || key == header::TRANSFER_ENCODING.as_str() {
+                continue;
+            }
+            if let (Ok(key), Ok(value)) = (
+                HeaderName::from_bytes(key.as_bytes()),
+                HeaderValue::from_str(&value),
+            ) {
+                response.headers_mut().append(key, value);
+            }
+        }
+        // the recorded handshake answered a different key
+        #[cfg(feature = "websocket")]
+        if let (Some(accept), StatusCode::SWITCHING_PROTOCOLS) =
+            (websocket_accept, response.status())
+        {
+            response
+                .headers_mut()
+                .insert(header::SEC_WEBSOCKET_ACCEPT, accept);
+        }
+        Ok(response)
+    }
+}
+
+/// Query parameters in a canonical order.
+fn query_pairs(query: Option<&str>) -> Vec<(String, String)> {
+    let mut pairs: Vec<_> = query
+        .map(|q| {
+            url::form_urlencoded::parse(q.as_bytes())
+                .into_owned()
+                .collect()
+        })
+        .unwrap_or_default();
+    pairs.sort();
+    pairs
+}
+
+/// Plays the daemon side of a recorded upgraded connection. Data the daemon sent is read in the
+/// recorded order, reads wait until the client wrote as many bytes as it did at that point of the
+/// recording. The connection ends after the last frame.
+struct ReplayConnection {
+    frames: VecDeque<Frame>,
+    /// Bytes of the first frame already read or written.
+    offset: usize,
+    /// Bytes written by the client not yet matched to a recorded write.
+    written: usize,
+    reader: Option<Waker>,
+}
+
+impl ReplayConnection {
+    fn new(frames: Vec<Frame>) -> Self {
+        ReplayConnection {
+            frames: frames.into(),
+            offset: 0,
+            written: 0,
+            reader: None,
+        }
+    }
+
+    /// Skips the recorded writes covered by what the client wrote.
+    fn consume_writes(&mut self) {
+        while let Some(Frame::Write(data)) = self.frames.front() {
+            let remaining = data.0.len() - self.offset;
+            if self.written < remaining {
+                self.offset += self.written;
+                self.written = 0;
+                return;
+            }
+            self.written -= remaining;
+            self.offset = 0;
+            self.frames.pop_front();
+        }
+    }
+}
+
+impl AsyncRead for ReplayConnection {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = &mut *self;
+        this.consume_writes();
+        match this.frames.front() {
+            None => Poll::Ready(Ok(())),
+            Some(Frame::Read(data)) => {
+                let len = buf.remaining().min(data.0.len() - this.offset);
+                buf.put_slice(&data.0[this.offset..this.offset + len]);
+                this.offset += len;
+                if this.offset == data.0.len() {
+                    this.offset = 0;
+                    this.frames.pop_front();
+                }
+                Poll::Ready(Ok(()))
+            }
+            Some(Frame::Write(_)) => {
+                this.reader = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl AsyncWrite for ReplayConnection {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.written += buf.len();
+        if let Some(reader) = self.reader.take() {
+            reader.wake();
+        }
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl fmt::Debug for Replay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Replay")
+            .field("pending", &self.interactions().len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{conn::Mock, conn::TtyChunk, Docker};
+
+    use hyper::Method;
+
+    use futures_util::{AsyncWriteExt as _, TryStreamExt};
+    use tokio::io::{AsyncReadExt as _, AsyncWriteExt as _};
+
+    async fn session(docker: &Docker) -> (String, Vec<String>, Vec<u8>, String) {
+        let volumes = docker.volumes().list(&Default::default()).await.unwrap();
+        let pull: Vec<_> = docker
+            .images()
+            .pull(&crate::image::PullOpts::builder().image("ubuntu").build())
+            .map_ok(|info| format!("{:?}", info))
+            .try_collect()
+            .await
+            .unwrap();
+        let logs: Vec<_> = docker
+            .containers()
+            .get("web")
+            .logs(&Default::default())
+            .try_collect()
+            .await
+            .unwrap();
+
+        let (mut reader, mut writer) = docker
+            .containers()
+            .get("web")
+            .attach()
+            .await
+            .unwrap()
+            .split();
+        writer.write_all(b"ls\n").await.unwrap();
+        let chunk = reader.try_next().await.unwrap().unwrap();
+
+        (
+            format!("{:?}", volumes),
+            pull,
+            logs.concat(),
+            String::from_utf8_lossy(&chunk).into_owned(),
+        )
+    }
+
+    #[tokio::test]
+    async fn replays_recorded_session() {
+        let mock = Mock::new();
+        mock.on(Method::GET, "/volumes").json(
+            StatusCode::OK,
+            &serde_json::json!({"Volumes": [], "Warnings": null}),
+        );
+        mock.on(Method::POST, "/images/create").chunks(
+            StatusCode::OK,
+            vec![
+                "{\"status\":\"Pulling from library/ubuntu\",\"id\":\"latest\"}\r\n",
+                "{\"status\":\"Digest: sha256:abc\"}\r\n",
+            ],
+        );
+        mock.on(Method::GET, "/containers/web/logs").chunks(
+            StatusCode::OK,
+            vec![
+                TtyChunk::StdOut(b"hello\n".to_vec()).encode(),
+                vec![0xff, 0xfe],
+            ],
+        );
+        let mut daemon = mock.on(Method::POST, "/containers/web/attach").upgrade();
+        tokio::spawn(async move {
+            let mut command = [0; 3];
+            daemon.read_exact(&mut command).await.unwrap();
+            daemon
+                .write_all(&TtyChunk::StdOut(b"bin\n".to_vec()).encode())
+                .await
+                .unwrap();
+        });
+
+        let recorder = Recorder::new();
+        let docker = Docker::builder()
+            .record(recorder.clone())
+            .build_with_mock(mock);
+        let recorded = session(&docker).await;
+
+        let fixture = recorder.fixture();
+        assert_eq!(fixture.interactions.len(), 4);
+        assert_eq!(fixture.interactions[1].response.chunks.len(), 2);
+        assert_eq!(
+            fixture.interactions[3].response.upgrade[0],
+            Frame::Write(Content(b"ls\n".to_vec()))
+        );
+
+        let json = serde_json::to_string(&fixture).unwrap();
+        assert!(json.contains(r#"{"base64":"//4="}"#));
+        let fixture: Fixture = serde_json::from_str(&json).unwrap();
+
+        let replay = Replay::new(fixture);
+        let docker = Docker::replay(replay.clone());
+        assert_eq!(session(&docker).await, recorded);
+        assert_eq!(replay.pending(), 0);
+    }
+
+    #[tokio::test]
+    async fn redacts_credentials() {
+        let mock = Mock::new();
+        mock.on(Method::POST, "/images/ubuntu/push")
+            .status(StatusCode::OK);
+        let recorder = Recorder::new();
+        let docker = Docker::builder()
+            .record(recorder.clone())
+            .build_with_mock(mock);
+
+        docker
+            .images()
+            .push(
+                "ubuntu",
+                &crate::image::ImagePushOpts::builder()
+                    .auth(
+                        crate::image::RegistryAuth::builder()
+                            .username("user")
+                            .password("secret")
+                            .build(),
+                    )
+                    .build(),
+            )
+            .await
+            .unwrap();
+
+        let request = &recorder.fixture().interactions[0].request;
+        assert_eq!(request.headers["x-registry-auth"], REDACTED);
+    }
+
+    #[tokio::test]
+    async fn redacts_auth_bodies() {
+        let mock = Mock::new();
+        mock.on(Method::POST, "/auth").json(
+            StatusCode::OK,
+            &serde_json::json!({"Status": "Login Succeeded", "IdentityToken": "token"}),
+        );
+        mock.on(Method::POST, "/containers/create").json(
+            StatusCode::CREATED,
+            &serde_json::json!({"Id": "web", "Warnings": []}),
+        );
+        let recorder = Recorder::new().redact(|interaction| {
+            if interaction.request.path.ends_with("/containers/create") {
+                interaction.request.body = Content(b"{}".to_vec());
+            }
+        });
+        let docker = Docker::builder()
+            .record(recorder.clone())
+            .build_with_mock(mock);
+
+        docker
+            .post_json::<_, serde_json::Value>(
+                "/auth",
+                crate::conn::Payload::Json(
+                    r#"{"username":"user","password":"secret"}"#.to_string(),
+                ),
+            )
+            .await
+            .unwrap();
+        docker
+            .containers()
+            .create(
+                &crate::container::ContainerCreateOpts::builder("ubuntu")
+                    .env(["PASSWORD=secret"])
+                    .build(),
+            )
+            .await
+            .unwrap();
+
+        let json = serde_json::to_string(&recorder.fixture()).unwrap();
+        assert!(!json.contains("secret"), "{}", json);
+        assert!(!json.contains(r#""token""#), "{}", json);
+        let fixture: Fixture = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            fixture.interactions[0].request.body,
+            Content(REDACTED.into())
+        );
+        assert_eq!(
+            fixture.interactions[1].request.body,
+            Content(b"{}".to_vec())
+        );
+    }
+
+    #[cfg(feature = "testing")]
+    #[tokio::test]
+    async fn records_connections_upgraded_by_the_daemon() {
+        use crate::{container::ContainerCreateOpts, testing::FakeEngine};
+
+        async fn echo(docker: &Docker) -> Vec<u8> {
+            let container = docker
+                .containers()
+                .create(
+                    &ContainerCreateOpts::builder("ubuntu")
+                        .cmd(vec!["cat"])
+                        .attach_stdin(true)
+                        .build(),
+                )
+                .await
+                .unwrap();
+            container.start().await.unwrap();
+            let (mut reader, mut writer) = container.attach().await.unwrap().split();
+            writer.write_all(b"ping\n").await.unwrap();
+            let chunk = reader.try_next().await.unwrap().unwrap();
+            chunk.to_vec()
+        }
+
+        let engine = FakeEngine::tcp().await.unwrap();
+        engine.add_image("ubuntu");
+        let recorder = Recorder::new();
+        let docker = Docker::builder()
+            .host(engine.host())
+            .record(recorder.clone())
+            .build()
+            .unwrap();
+        let recorded = echo(&docker).await;
+        assert_eq!(recorded, b"ping\n");
+
+        let replay = Replay::new(recorder.fixture());
+        assert_eq!(echo(&Docker::replay(replay.clone())).await, recorded);
+        assert_eq!(replay.pending(), 0);
+    }
+}
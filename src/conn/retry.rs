@@ -0,0 +1,286 @@
+//! Retrying requests that failed because of transient errors.
+
+use crate::{Error, Result};
+
+use hyper::{Body, Method, Response, StatusCode};
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+#[derive(Clone, Debug)]
+/// Determines which failed requests are retried and how long to wait between the attempts.
+///
+/// Requests are retried when the connection to the docker host fails, for example while the
+/// daemon restarts, when a connection breaks before the response arrives, when the request times
+/// out and when the docker host answers with one of the retried status codes (by default
+/// `500 Internal Server Error`, `502 Bad Gateway` and `504 Gateway Timeout`).
+///
+/// Only idempotent `GET` and `HEAD` requests are retried, to also retry other requests use
+/// [`Docker::retrying`](crate::Docker::retrying).
+pub struct RetryPolicy {
+    pub(crate) max_retries: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    jitter: bool,
+    budget_ratio: f32,
+    budget_max: f32,
+    statuses: Vec<StatusCode>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+            jitter: true,
+            budget_ratio: 0.2,
+            budget_max: 10.,
+            statuses: vec![
+                StatusCode::INTERNAL_SERVER_ERROR,
+                StatusCode::BAD_GATEWAY,
+                StatusCode::GATEWAY_TIMEOUT,
+            ],
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Returns a builder for a retry policy.
+    pub fn builder() -> RetryPolicyBuilder {
+        RetryPolicyBuilder::default()
+    }
+
+    /// Returns the time to wait before the `retry`th retry, starting at 1.
+    pub(crate) fn backoff(&self, retry: u32) -> Duration {
+        let exp = 2u32.saturating_pow(retry.saturating_sub(1));
+        let backoff = self
+            .initial_backoff
+            .checked_mul(exp)
+            .unwrap_or(self.max_backoff)
+            .min(self.max_backoff);
+
+        if self.jitter {
+            // wait at least half of the backoff so that retries don't happen back to back
+            backoff / 2 + (backoff / 2).mul_f64(fastrand::f64())
+        } else {
+            backoff
+        }
+    }
+
+    /// Checks if the outcome of a request is worth retrying.
+    pub(crate) fn should_retry(&self, result: &Result<Response<Body>>) -> bool {
+        match result {
+            Ok(response) => self.statuses.contains(&response.status()),
+            Err(Error::Hyper(e)) => e.is_connect() || e.is_closed() || e.is_incomplete_message(),
+            Err(Error::RequestTimeout(_)) => true,
+            Err(_) => false,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+/// A builder struct for RetryPolicy.
+pub struct RetryPolicyBuilder {
+    policy: RetryPolicy,
+}
+
+impl RetryPolicyBuilder {
+    /// Maximum number of retries of a single request, defaults to 3.
+    pub fn max_retries(mut self, retries: u32) -> Self {
+        self.policy.max_retries = retries;
+        self
+    }
+
+    /// The wait before the first retry, doubled with each further retry up to `max`. Defaults to
+    /// 100ms and 5s.
+    pub fn backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.policy.initial_backoff = initial;
+        self.policy.max_backoff = max;
+        self
+    }
+
+    /// Whether to randomize the wait between retries so that clients failing at the same time
+    /// don't retry at the same time. If enabled the wait is between half and the full backoff.
+    /// Enabled by default.
+    pub fn jitter(mut self, jitter: bool) -> Self {
+        self.policy.jitter = jitter;
+        self
+    }
+
+    /// Limits the number of retries relative to the number of requests so that a failing docker
+    /// host isn't overloaded by retries. Every request adds `ratio` to a budget shared by all
+    /// clones of a [`Docker`](crate::Docker) instance and every retry takes one from it. The
+    /// budget starts with and holds at most `max` retries. Defaults to 0.2 and 10.
+    pub fn budget(mut self, ratio: f32, max: u32) -> Self {
+        self.policy.budget_ratio = ratio;
+        self.policy.budget_max = max as f32;
+        self
+    }
+
+    /// Status codes returned by the docker host that cause a retry.
+    pub fn statuses<S>(mut self, statuses: S) -> Self
+    where
+        S: IntoIterator<Item = StatusCode>,
+    {
+        self.policy.statuses = statuses.into_iter().collect();
+        self
+    }
+
+    /// Finish building RetryPolicy.
+    pub fn build(&self) -> RetryPolicy {
+        self.policy.clone()
+    }
+}
+
+#[derive(Clone, Debug)]
+/// A retry policy together with the budget shared by all clones of a client.
+pub(crate) struct Retry {
+    pub policy: RetryPolicy,
+    budget: Arc<Mutex<f32>>,
+}
+
+impl Retry {
+    pub fn new(policy: RetryPolicy) -> Self {
+        Retry {
+            budget: Arc::new(Mutex::new(policy.budget_max)),
+            policy,
+        }
+    }
+
+    /// Checks if requests with `method` are retried. `all` includes requests that are not
+    /// idempotent.
+    pub fn applies_to(&self, method: &Method, all: bool) -> bool {
+        all || method == Method::GET || method == Method::HEAD
+    }
+
+    /// Records a new request.
+    pub fn deposit(&self) {
+        let mut budget = self.budget.lock().expect("retry budget lock");
+        *budget = (*budget + self.policy.budget_ratio).min(self.policy.budget_max);
+    }
+
+    /// Takes a retry from the budget, returns false if there is none left.
+    pub fn withdraw(&self) -> bool {
+        let mut budget = self.budget.lock().expect("retry budget lock");
+        if *budget >= 1. {
+            *budget -= 1.;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backs_off_exponentially() {
+        let policy = RetryPolicy::builder()
+            .backoff(Duration::from_millis(100), Duration::from_millis(350))
+            .jitter(false)
+            .build();
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(350));
+        assert_eq!(policy.backoff(40), Duration::from_millis(350));
+
+        let policy = RetryPolicy::builder().jitter(true).build();
+        for _ in 0..10 {
+            let backoff = policy.backoff(2);
+            assert!(backoff >= Duration::from_millis(100));
+            assert!(backoff <= Duration::from_millis(200));
+        }
+    }
+
+    #[test]
+    fn limits_retries_by_budget() {
+        let retry = Retry::new(RetryPolicy::builder().budget(0.5, 2).build());
+        assert!(retry.withdraw());
+        assert!(retry.withdraw());
+        assert!(!retry.withdraw());
+
+        retry.deposit();
+        assert!(!retry.withdraw());
+        retry.deposit();
+        assert!(retry.withdraw());
+    }
+
+    /// Fails the first two attempts of every request.
+    struct Flaky(std::sync::atomic::AtomicUsize);
+
+    impl crate::conn::Middleware for Flaky {
+        fn handle<'a>(
+            &'a self,
+            _: hyper::Request<Body>,
+            _: crate::conn::Next<'a>,
+        ) -> futures_util::future::BoxFuture<'a, Result<Response<Body>>> {
+            let attempt = self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            let mut response = Response::new(Body::from("null"));
+            if attempt % 3 != 2 {
+                *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+            }
+            Box::pin(async move { Ok(response) })
+        }
+    }
+
+    #[tokio::test]
+    async fn retries_idempotent_requests() {
+        let flaky = Arc::new(Flaky(Default::default()));
+        let docker = crate::Docker::builder()
+            .host("tcp://127.0.0.1:1")
+            .middleware(flaky.clone())
+            .retry_policy(
+                RetryPolicy::builder()
+                    .backoff(Duration::from_millis(1), Duration::from_millis(1))
+                    .build(),
+            )
+            .build()
+            .unwrap();
+        let container = docker.containers().get("web");
+        let attempts = || flaky.0.swap(0, std::sync::atomic::Ordering::SeqCst);
+
+        assert!(container.changes().await.is_ok());
+        assert_eq!(attempts(), 3);
+
+        assert!(container.stop(None).await.is_err());
+        assert_eq!(attempts(), 1);
+
+        docker
+            .retrying()
+            .containers()
+            .get("web")
+            .stop(None)
+            .await
+            .unwrap();
+        assert_eq!(attempts(), 3);
+    }
+
+    #[tokio::test]
+    async fn sends_streamed_bodies_once() {
+        use futures_util::{stream, StreamExt};
+
+        let flaky = Arc::new(Flaky(Default::default()));
+        let docker = crate::Docker::builder()
+            .host("tcp://127.0.0.1:1")
+            .middleware(flaky.clone())
+            .retry_policy(
+                RetryPolicy::builder()
+                    .backoff(Duration::from_millis(1), Duration::from_millis(1))
+                    .build(),
+            )
+            .build()
+            .unwrap();
+        let tarball = stream::iter(vec![Ok::<_, std::io::Error>(bytes::Bytes::from_static(
+            b"tarball",
+        ))]);
+
+        let mut chunks = docker.retrying().images().import_stream(tarball);
+        assert!(chunks.next().await.unwrap().is_err());
+        assert_eq!(flaky.0.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+}
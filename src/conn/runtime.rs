@@ -0,0 +1,711 @@
+//! Async runtimes the client runs on.
+//!
+//! The client needs a runtime to open connections to the docker daemon, to run the background
+//! tasks driving them and for timers like request timeouts and retry backoff. [`Tokio`](Tokio)
+//! (`runtime-tokio` feature, enabled by default) and [`AsyncStd`](AsyncStd)
+//! (`runtime-async-std` feature) are provided. Other runtimes can be used by implementing
+//! [`Runtime`](Runtime) and passing it to
+//! [`DockerBuilder::runtime`](crate::DockerBuilder::runtime).
+//!
+//! If only `runtime-async-std` is enabled it's the default runtime, otherwise tokio is.
+
+use crate::conn::Connection;
+
+use futures_util::{
+    future::{self, BoxFuture, Either},
+    io::{AsyncRead, AsyncWrite},
+};
+use hyper::{
+    client::connect::{Connected, Connection as HyperConnection},
+    service::Service,
+    Uri,
+};
+use pin_project::pin_project;
+use tokio::io::ReadBuf;
+
+use std::{
+    fmt,
+    future::Future,
+    io,
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+/// An async runtime spawning tasks, providing timers and opening connections.
+///
+/// Connections are returned as tokio IO types, runtimes whose IO types implement the `futures`
+/// IO traits can wrap them with [`FuturesIo`](FuturesIo).
+pub trait Runtime: fmt::Debug + Send + Sync + 'static {
+    /// Runs `task` in the background.
+    fn spawn(&self, task: BoxFuture<'static, ()>);
+
+    /// Runs the blocking `task` where blocking doesn't stall other tasks, used to produce
+    /// streamed request bodies like the build context of an image.
+    ///
+    /// By default every task gets a thread of its own.
+    fn spawn_blocking(&self, task: Box<dyn FnOnce() + Send>) {
+        std::thread::spawn(task);
+    }
+
+    /// Returns a future completing after `duration`.
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()>;
+
+    /// Opens a TCP connection to `host` on `port`. If `keepalive` is set keep-alive probes are
+    /// sent after the connection has been idle for that long.
+    fn connect_tcp(
+        &self,
+        host: &str,
+        port: u16,
+        keepalive: Option<Duration>,
+    ) -> BoxFuture<'static, io::Result<Box<dyn Connection>>>;
+
+    #[cfg(unix)]
+    #[cfg_attr(docsrs, doc(cfg(unix)))]
+    /// Opens a connection to the Unix socket at `path`.
+    fn connect_unix(&self, path: &Path) -> BoxFuture<'static, io::Result<Box<dyn Connection>>>;
+
+    /// Spawns `program` with `args` and returns a connection to its stdin and stdout, used to
+    /// tunnel requests to `ssh://` hosts. The process is killed once the connection is dropped.
+    ///
+    /// By default this fails with [`io::ErrorKind::Unsupported`](std::io::ErrorKind::Unsupported)
+    /// for runtimes that can't spawn processes.
+    fn spawn_process(&self, program: &Path, args: &[String]) -> io::Result<Box<dyn Connection>> {
+        let _ = (program, args);
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("{:?} can't spawn processes", self),
+        ))
+    }
+}
+
+/// Returns the runtime used when none is configured.
+#[cfg(feature = "runtime-tokio")]
+pub(crate) fn default_runtime() -> Arc<dyn Runtime> {
+    Arc::new(Tokio)
+}
+
+/// Returns the runtime used when none is configured.
+#[cfg(all(feature = "runtime-async-std", not(feature = "runtime-tokio")))]
+pub(crate) fn default_runtime() -> Arc<dyn Runtime> {
+    Arc::new(AsyncStd)
+}
+
+// the crate doesn't build without a runtime, the error is raised where the default one is needed
+#[cfg(not(any(feature = "runtime-tokio", feature = "runtime-async-std")))]
+pub(crate) fn default_runtime() -> Arc<dyn Runtime> {
+    compile_error!("either the `runtime-tokio` or the `runtime-async-std` feature must be enabled")
+}
+
+/// Waits for `future` to complete for at most `duration`, returns `None` if it didn't.
+pub(crate) async fn timeout<F>(
+    runtime: &dyn Runtime,
+    duration: Duration,
+    future: F,
+) -> Option<F::Output>
+where
+    F: Future,
+{
+    futures_util::pin_mut!(future);
+    match future::select(future, runtime.sleep(duration)).await {
+        Either::Left((output, _)) => Some(output),
+        Either::Right(_) => None,
+    }
+}
+
+#[cfg(feature = "runtime-tokio")]
+#[cfg_attr(docsrs, doc(cfg(feature = "runtime-tokio")))]
+#[derive(Clone, Copy, Debug, Default)]
+/// The [tokio](https://tokio.rs) runtime. Requests have to be sent from within a tokio runtime.
+pub struct Tokio;
+
+#[cfg(feature = "runtime-tokio")]
+impl Runtime for Tokio {
+    fn spawn(&self, task: BoxFuture<'static, ()>) {
+        tokio::spawn(task);
+    }
+
+    fn spawn_blocking(&self, task: Box<dyn FnOnce() + Send>) {
+        tokio::task::spawn_blocking(task);
+    }
+
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        Box::pin(tokio::time::sleep(duration))
+    }
+
+    fn connect_tcp(
+        &self,
+        host: &str,
+        port: u16,
+        keepalive: Option<Duration>,
+    ) -> BoxFuture<'static, io::Result<Box<dyn Connection>>> {
+        let host = host.to_string();
+        Box::pin(async move {
+            let stream = tokio::net::TcpStream::connect((host.as_str(), port)).await?;
+            if let Some(interval) = keepalive {
+                set_keepalive(socket2::SockRef::from(&stream), interval)?;
+            }
+            Ok(Box::new(stream) as Box<dyn Connection>)
+        })
+    }
+
+    #[cfg(unix)]
+    fn connect_unix(&self, path: &Path) -> BoxFuture<'static, io::Result<Box<dyn Connection>>> {
+        let path = path.to_path_buf();
+        Box::pin(async move {
+            let stream = tokio::net::UnixStream::connect(path).await?;
+            Ok(Box::new(stream) as Box<dyn Connection>)
+        })
+    }
+
+    fn spawn_process(&self, program: &Path, args: &[String]) -> io::Result<Box<dyn Connection>> {
+        use std::process::Stdio;
+
+        let mut child = tokio::process::Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()?;
+        let stdin = child.stdin.take();
+        let stdout = child.stdout.take();
+        ProcessIo::new(child, stdin, stdout).map(|io| Box::new(io) as Box<dyn Connection>)
+    }
+}
+
+#[cfg(feature = "runtime-async-std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "runtime-async-std")))]
+#[derive(Clone, Copy, Debug, Default)]
+/// The [async-std](https://async.rs) runtime.
+pub struct AsyncStd;
+
+#[cfg(feature = "runtime-async-std")]
+impl Runtime for AsyncStd {
+    fn spawn(&self, task: BoxFuture<'static, ()>) {
+        async_std::task::spawn(task);
+    }
+
+    fn spawn_blocking(&self, task: Box<dyn FnOnce() + Send>) {
+        async_std::task::spawn_blocking(task);
+    }
+
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        Box::pin(async_std::task::sleep(duration))
+    }
+
+    fn connect_tcp(
+        &self,
+        host: &str,
+        port: u16,
+        keepalive: Option<Duration>,
+    ) -> BoxFuture<'static, io::Result<Box<dyn Connection>>> {
+        let host = host.to_string();
+        Box::pin(async move {
+            let stream = async_std::net::TcpStream::connect((host.as_str(), port)).await?;
+            if let Some(interval) = keepalive {
+                set_keepalive(socket2::SockRef::from(&stream), interval)?;
+            }
+            Ok(Box::new(FuturesIo::new(stream)) as Box<dyn Connection>)
+        })
+    }
+
+    #[cfg(unix)]
+    fn connect_unix(&self, path: &Path) -> BoxFuture<'static, io::Result<Box<dyn Connection>>> {
+        let path = path.to_path_buf();
+        Box::pin(async move {
+            let stream = async_std::os::unix::net::UnixStream::connect(path).await?;
+            Ok(Box::new(FuturesIo::new(stream)) as Box<dyn Connection>)
+        })
+    }
+
+    fn spawn_process(&self, program: &Path, args: &[String]) -> io::Result<Box<dyn Connection>> {
+        use std::process::Stdio;
+
+        let mut child = async_process::Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()?;
+        let stdin = child.stdin.take();
+        let stdout = child.stdout.take();
+        ProcessIo::new(child, stdin, stdout)
+            .map(|io| Box::new(FuturesIo::new(io)) as Box<dyn Connection>)
+    }
+}
+
+/// Connection over the stdin and stdout of a child process, the child is kept until the
+/// connection is dropped.
+#[cfg(any(feature = "runtime-tokio", feature = "runtime-async-std"))]
+#[pin_project]
+struct ProcessIo<C, W, R> {
+    _child: C,
+    #[pin]
+    stdin: W,
+    #[pin]
+    stdout: R,
+}
+
+#[cfg(any(feature = "runtime-tokio", feature = "runtime-async-std"))]
+impl<C, W, R> ProcessIo<C, W, R> {
+    fn new(child: C, stdin: Option<W>, stdout: Option<R>) -> io::Result<Self> {
+        match (stdin, stdout) {
+            (Some(stdin), Some(stdout)) => Ok(ProcessIo {
+                _child: child,
+                stdin,
+                stdout,
+            }),
+            _ => Err(io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                "failed to capture stdio of the process",
+            )),
+        }
+    }
+}
+
+#[cfg(feature = "runtime-tokio")]
+impl<C, W, R> tokio::io::AsyncRead for ProcessIo<C, W, R>
+where
+    R: tokio::io::AsyncRead,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        self.project().stdout.poll_read(cx, buf)
+    }
+}
+
+#[cfg(feature = "runtime-tokio")]
+impl<C, W, R> tokio::io::AsyncWrite for ProcessIo<C, W, R>
+where
+    W: tokio::io::AsyncWrite,
+{
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.project().stdin.poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.project().stdin.poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.project().stdin.poll_shutdown(cx)
+    }
+}
+
+#[cfg(feature = "runtime-async-std")]
+impl<C, W, R> AsyncRead for ProcessIo<C, W, R>
+where
+    R: AsyncRead,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        self.project().stdout.poll_read(cx, buf)
+    }
+}
+
+#[cfg(feature = "runtime-async-std")]
+impl<C, W, R> AsyncWrite for ProcessIo<C, W, R>
+where
+    W: AsyncWrite,
+{
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.project().stdin.poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.project().stdin.poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.project().stdin.poll_close(cx)
+    }
+}
+
+#[cfg(any(feature = "runtime-tokio", feature = "runtime-async-std"))]
+fn set_keepalive(socket: socket2::SockRef<'_>, interval: Duration) -> io::Result<()> {
+    socket.set_tcp_keepalive(&socket2::TcpKeepalive::new().with_time(interval))
+}
+
+/// Adapts an IO type implementing the `futures` [`AsyncRead`](futures_util::io::AsyncRead) and
+/// [`AsyncWrite`](futures_util::io::AsyncWrite) traits to the tokio ones used by
+/// [`Connection`](crate::conn::Connection).
+#[pin_project]
+#[derive(Debug)]
+pub struct FuturesIo<S> {
+    #[pin]
+    inner: S,
+}
+
+impl<S> FuturesIo<S> {
+    pub fn new(inner: S) -> Self {
+        FuturesIo { inner }
+    }
+
+    /// Returns the wrapped IO type.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S> tokio::io::AsyncRead for FuturesIo<S>
+where
+    S: AsyncRead,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let unfilled = buf.initialize_unfilled();
+        match self.project().inner.poll_read(cx, unfilled) {
+            Poll::Ready(Ok(n)) => {
+                buf.advance(n);
+                Poll::Ready(Ok(()))
+            }
+            Poll::Ready(Err(e)) => Poll::Ready(Err(e)),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl<S> tokio::io::AsyncWrite for FuturesIo<S>
+where
+    S: AsyncWrite,
+{
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.project().inner.poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.project().inner.poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.project().inner.poll_close(cx)
+    }
+}
+
+/// Runs the background tasks of the HTTP client on a [`Runtime`](Runtime).
+#[derive(Clone)]
+pub(crate) struct RuntimeExecutor(pub Arc<dyn Runtime>);
+
+impl<F> hyper::rt::Executor<F> for RuntimeExecutor
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    fn execute(&self, future: F) {
+        self.0.spawn(Box::pin(future))
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Target {
+    Tcp {
+        keepalive: Option<Duration>,
+    },
+    #[cfg(unix)]
+    Unix(PathBuf),
+}
+
+/// Connector opening connections to the docker daemon with a [`Runtime`](Runtime), used by
+/// [`Transport::Tcp`](crate::conn::Transport::Tcp) and
+/// [`Transport::Unix`](crate::conn::Transport::Unix).
+#[derive(Clone)]
+pub struct RuntimeConnector {
+    runtime: Arc<dyn Runtime>,
+    target: Target,
+    connect_timeout: Option<Duration>,
+}
+
+impl RuntimeConnector {
+    /// Returns a connector for TCP connections to the host of each request URI.
+    pub(crate) fn tcp(
+        runtime: Arc<dyn Runtime>,
+        connect_timeout: Option<Duration>,
+        keepalive: Option<Duration>,
+    ) -> Self {
+        RuntimeConnector {
+            runtime,
+            target: Target::Tcp { keepalive },
+            connect_timeout,
+        }
+    }
+
+    #[cfg(unix)]
+    /// Returns a connector for connections to the Unix socket at `path`.
+    pub(crate) fn unix(runtime: Arc<dyn Runtime>, path: PathBuf) -> Self {
+        RuntimeConnector {
+            runtime,
+            target: Target::Unix(path),
+            connect_timeout: None,
+        }
+    }
+}
+
+impl fmt::Debug for RuntimeConnector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RuntimeConnector")
+            .field("runtime", &self.runtime)
+            .field("target", &self.target)
+            .field("connect_timeout", &self.connect_timeout)
+            .finish()
+    }
+}
+
+impl Service<Uri> for RuntimeConnector {
+    type Response = RuntimeStream;
+    type Error = io::Error;
+    type Future = BoxFuture<'static, io::Result<Self::Response>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, uri: Uri) -> Self::Future {
+        let connect = match &self.target {
+            Target::Tcp { keepalive } => {
+                let host = match uri.host() {
+                    Some(host) => host.trim_start_matches('[').trim_end_matches(']'),
+                    None => {
+                        return Box::pin(future::ready(Err(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            format!("missing host in `{}`", uri),
+                        ))))
+                    }
+                };
+                let port = uri.port_u16().unwrap_or(match uri.scheme_str() {
+                    Some("https") => 443,
+                    _ => 80,
+                });
+                self.runtime.connect_tcp(host, port, *keepalive)
+            }
+            #[cfg(unix)]
+            Target::Unix(path) => self.runtime.connect_unix(path),
+        };
+        let runtime = self.runtime.clone();
+        let connect_timeout = self.connect_timeout;
+
+        Box::pin(async move {
+            let connection = match connect_timeout {
+                Some(duration) => timeout(runtime.as_ref(), duration, connect)
+                    .await
+                    .unwrap_or_else(|| {
+                        Err(io::Error::new(
+                            io::ErrorKind::TimedOut,
+                            format!("connection timed out after {:?}", duration),
+                        ))
+                    })?,
+                None => connect.await?,
+            };
+            Ok(RuntimeStream(connection))
+        })
+    }
+}
+
+/// A connection opened by [`RuntimeConnector`](RuntimeConnector).
+pub struct RuntimeStream(Box<dyn Connection>);
+
+impl fmt::Debug for RuntimeStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RuntimeStream").finish()
+    }
+}
+
+impl tokio::io::AsyncRead for RuntimeStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.0).poll_read(cx, buf)
+    }
+}
+
+impl tokio::io::AsyncWrite for RuntimeStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.0).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.0).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.0).poll_shutdown(cx)
+    }
+}
+
+impl HyperConnection for RuntimeStream {
+    fn connected(&self) -> Connected {
+        Connected::new()
+    }
+}
+
+/// Builds the URI of `endpoint` on the docker daemon listening on the Unix socket at `path`. The
+/// path is hex encoded into the host so connections to different sockets aren't pooled together.
+#[cfg(unix)]
+pub(crate) fn unix_uri(path: &Path, endpoint: &str) -> String {
+    use std::os::unix::ffi::OsStrExt;
+
+    let host: String = path
+        .as_os_str()
+        .as_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    format!("unix://{}:0{}", host, endpoint)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn encodes_socket_path_into_host() {
+        assert_eq!(
+            unix_uri(Path::new("/run/d.sock"), "/v1.41/_ping"),
+            "unix://2f72756e2f642e736f636b:0/v1.41/_ping"
+        );
+    }
+
+    #[cfg(feature = "runtime-tokio")]
+    #[tokio::test]
+    async fn times_out_connecting() {
+        #[derive(Debug)]
+        struct Unreachable;
+
+        impl Runtime for Unreachable {
+            fn spawn(&self, task: BoxFuture<'static, ()>) {
+                Tokio.spawn(task)
+            }
+
+            fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+                Tokio.sleep(duration)
+            }
+
+            fn connect_tcp(
+                &self,
+                _: &str,
+                _: u16,
+                _: Option<Duration>,
+            ) -> BoxFuture<'static, io::Result<Box<dyn Connection>>> {
+                Box::pin(future::pending())
+            }
+
+            #[cfg(unix)]
+            fn connect_unix(
+                &self,
+                _: &Path,
+            ) -> BoxFuture<'static, io::Result<Box<dyn Connection>>> {
+                Box::pin(future::pending())
+            }
+        }
+
+        let mut connector =
+            RuntimeConnector::tcp(Arc::new(Unreachable), Some(Duration::from_millis(10)), None);
+        let error = connector
+            .call("tcp://10.0.0.1:2375".parse().unwrap())
+            .await
+            .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::TimedOut);
+    }
+
+    #[cfg(feature = "runtime-tokio")]
+    async fn echo(mut connection: impl tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let mut buf = [0; 4];
+        connection.read_exact(&mut buf).await.unwrap();
+        connection.write_all(&buf).await.unwrap();
+    }
+
+    #[cfg(feature = "runtime-tokio")]
+    async fn assert_echoes(mut connection: RuntimeStream) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        connection.write_all(b"ping").await.unwrap();
+        let mut buf = [0; 4];
+        connection.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"ping");
+    }
+
+    #[cfg(feature = "runtime-tokio")]
+    #[tokio::test]
+    async fn connects_over_tcp_on_default_runtime() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(async move { echo(listener.accept().await.unwrap().0).await });
+
+        let mut connector =
+            RuntimeConnector::tcp(default_runtime(), None, Some(Duration::from_secs(60)));
+        let connection = connector
+            .call(format!("http://127.0.0.1:{}", port).parse().unwrap())
+            .await
+            .unwrap();
+        assert_echoes(connection).await;
+        server.await.unwrap();
+    }
+
+    #[cfg(all(unix, feature = "runtime-tokio"))]
+    #[tokio::test]
+    async fn connects_over_unix_socket_on_default_runtime() {
+        let path =
+            std::env::temp_dir().join(format!("docker-api-runtime-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let listener = tokio::net::UnixListener::bind(&path).unwrap();
+        let server = tokio::spawn(async move { echo(listener.accept().await.unwrap().0).await });
+
+        let mut connector = RuntimeConnector::unix(default_runtime(), path.clone());
+        let connection = connector
+            .call(unix_uri(&path, "/_ping").parse().unwrap())
+            .await
+            .unwrap();
+        assert_echoes(connection).await;
+        server.await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(all(feature = "runtime-async-std", feature = "testing"))]
+    #[test]
+    fn talks_to_daemon_on_async_std() {
+        let server = tokio::runtime::Runtime::new().unwrap();
+        let engine = server.block_on(crate::testing::FakeEngine::tcp()).unwrap();
+        engine.add_image("ubuntu");
+
+        let docker = crate::Docker::builder()
+            .host(engine.host())
+            .runtime(AsyncStd)
+            .build()
+            .unwrap();
+        async_std::task::block_on(async {
+            docker.ping().await.unwrap();
+            let images = docker.images().list(&Default::default()).await.unwrap();
+            assert_eq!(images.len(), 1);
+        });
+    }
+}
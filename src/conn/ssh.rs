@@ -0,0 +1,230 @@
+//! Connector tunneling HTTP over `ssh` to a remote `docker system dial-stdio`.
+
+use crate::{
+    conn::{runtime::default_runtime, Connection, Runtime},
+    Error, Result,
+};
+
+use futures_util::future::BoxFuture;
+use hyper::{
+    client::connect::{Connected, Connection as HyperConnection},
+    service::Service,
+    Uri,
+};
+use tokio::io::ReadBuf;
+use url::Url;
+
+use std::{
+    fmt, io,
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+/// Default program used to open SSH connections.
+pub const DEFAULT_SSH_PROGRAM: &str = "ssh";
+
+/// Connector spawning `ssh [-l user] [-p port] -- host docker system dial-stdio` for each new
+/// connection and using the stdin/stdout of the child process as the connection. The process is
+/// spawned with the [`Runtime`](crate::conn::Runtime) of the client.
+#[derive(Clone, Debug)]
+pub struct SshConnector {
+    program: PathBuf,
+    args: Vec<String>,
+    runtime: Arc<dyn Runtime>,
+}
+
+impl SshConnector {
+    /// Creates a new connector for the given `destination` which is a URL like
+    /// `ssh://user@host:port`. User and port are optional.
+    pub fn new(destination: &Url) -> Result<Self> {
+        Self::with_program(destination, DEFAULT_SSH_PROGRAM)
+    }
+
+    /// Same as [`SshConnector::new`](SshConnector::new) but uses `program` instead of `ssh`. The
+    /// program will be called with the same arguments as `ssh` would.
+    pub fn with_program<P>(destination: &Url, program: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        if destination.scheme() != "ssh" {
+            return Err(Error::UnsupportedScheme(destination.scheme().to_string()));
+        }
+        let host = destination
+            .host_str()
+            .filter(|host| !host.is_empty())
+            .ok_or(Error::MissingAuthority)?;
+
+        let mut args = vec!["-T".to_string()];
+        if !destination.username().is_empty() {
+            args.push("-l".into());
+            args.push(destination.username().into());
+        }
+        if let Some(port) = destination.port() {
+            args.push("-p".into());
+            args.push(port.to_string());
+        }
+        args.extend(
+            ["--", host, "docker", "system", "dial-stdio"]
+                .iter()
+                .map(|arg| arg.to_string()),
+        );
+
+        Ok(SshConnector {
+            program: program.as_ref().to_path_buf(),
+            args,
+            runtime: default_runtime(),
+        })
+    }
+
+    /// Spawns the `ssh` processes with `runtime` instead of the default runtime.
+    pub fn runtime(mut self, runtime: Arc<dyn Runtime>) -> Self {
+        self.runtime = runtime;
+        self
+    }
+
+    fn spawn(&self) -> io::Result<SshStream> {
+        log::trace!("spawning {} {:?}", self.program.display(), self.args);
+        self.runtime
+            .spawn_process(&self.program, &self.args)
+            .map(SshStream)
+    }
+}
+
+impl Service<Uri> for SshConnector {
+    type Response = SshStream;
+    type Error = io::Error;
+    type Future = BoxFuture<'static, io::Result<Self::Response>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, _: Uri) -> Self::Future {
+        let connection = self.spawn();
+        Box::pin(async move { connection })
+    }
+}
+
+/// Connection to the docker daemon over the stdio of a `ssh` process. The process is killed once
+/// the connection is dropped.
+pub struct SshStream(Box<dyn Connection>);
+
+impl fmt::Debug for SshStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SshStream").finish()
+    }
+}
+
+impl tokio::io::AsyncRead for SshStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.0).poll_read(cx, buf)
+    }
+}
+
+impl tokio::io::AsyncWrite for SshStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.0).poll_write(cx, buf)
+    }
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.0).poll_flush(cx)
+    }
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.0).poll_shutdown(cx)
+    }
+}
+
+impl HyperConnection for SshStream {
+    fn connected(&self) -> Connected {
+        Connected::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_ssh_arguments() {
+        let connector =
+            SshConnector::new(&Url::parse("ssh://builder@10.0.0.1:2222").unwrap()).unwrap();
+        assert_eq!(connector.program, PathBuf::from("ssh"));
+        assert_eq!(
+            connector.args,
+            vec![
+                "-T",
+                "-l",
+                "builder",
+                "-p",
+                "2222",
+                "--",
+                "10.0.0.1",
+                "docker",
+                "system",
+                "dial-stdio"
+            ]
+        );
+
+        let connector = SshConnector::new(&Url::parse("ssh://build-box").unwrap()).unwrap();
+        assert_eq!(
+            connector.args,
+            vec!["-T", "--", "build-box", "docker", "system", "dial-stdio"]
+        );
+
+        match SshConnector::new(&Url::parse("ssh://").unwrap()).unwrap_err() {
+            Error::MissingAuthority => {}
+            e => panic!("Expected Error::MissingAuthority, got {}", e),
+        }
+    }
+
+    #[test]
+    fn fails_without_process_support() {
+        use futures_util::future::BoxFuture;
+        use std::time::Duration;
+
+        #[derive(Debug)]
+        struct NoProcesses;
+
+        impl Runtime for NoProcesses {
+            fn spawn(&self, _: BoxFuture<'static, ()>) {}
+
+            fn sleep(&self, _: Duration) -> BoxFuture<'static, ()> {
+                Box::pin(futures_util::future::pending())
+            }
+
+            fn connect_tcp(
+                &self,
+                _: &str,
+                _: u16,
+                _: Option<Duration>,
+            ) -> BoxFuture<'static, io::Result<Box<dyn Connection>>> {
+                Box::pin(futures_util::future::pending())
+            }
+
+            #[cfg(unix)]
+            fn connect_unix(
+                &self,
+                _: &Path,
+            ) -> BoxFuture<'static, io::Result<Box<dyn Connection>>> {
+                Box::pin(futures_util::future::pending())
+            }
+        }
+
+        let connector = SshConnector::new(&Url::parse("ssh://build-box").unwrap())
+            .unwrap()
+            .runtime(Arc::new(NoProcesses));
+        assert_eq!(
+            connector.spawn().unwrap_err().kind(),
+            io::ErrorKind::Unsupported
+        );
+    }
+}
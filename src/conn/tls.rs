@@ -0,0 +1,395 @@
+//! TLS connectors backed either by OpenSSL (`tls` feature) or rustls (`rustls-tls` feature).
+//!
+//! If both features are enabled OpenSSL is used.
+
+use crate::{conn::RuntimeConnector, Error, Result};
+
+use std::{fs, io, path::Path};
+
+#[cfg(feature = "tls")]
+/// Connector used by [`Transport::EncryptedTcp`](crate::conn::Transport::EncryptedTcp).
+pub type HttpsConnector = hyper_openssl::HttpsConnector<RuntimeConnector>;
+
+#[cfg(all(feature = "rustls-tls", not(feature = "tls")))]
+/// Connector used by [`Transport::EncryptedTcp`](crate::conn::Transport::EncryptedTcp).
+pub type HttpsConnector = hyper_rustls::HttpsConnector<RuntimeConnector>;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+/// Determines how the certificate presented by the docker host is verified.
+pub enum TlsVerification {
+    /// Verify the certificate chain of the docker host and check that the certificate was issued
+    /// for the expected server name.
+    #[default]
+    Full,
+    /// Accept any certificate presented by the docker host. This makes the connection vulnerable
+    /// to man-in-the-middle attacks and should only be used for testing.
+    Insecure,
+}
+
+#[derive(Clone, Debug, Default)]
+/// TLS settings of a connection to the docker host. All certificates and keys are PEM encoded.
+pub struct TlsConfig {
+    identity: Option<(Vec<u8>, Vec<u8>)>,
+    ca: Option<Vec<u8>>,
+    server_name: Option<String>,
+    verification: TlsVerification,
+}
+
+impl TlsConfig {
+    /// Returns a builder for TLS settings.
+    pub fn builder() -> TlsConfigBuilder {
+        TlsConfigBuilder::default()
+    }
+
+    /// Reads the client certificate (`cert.pem`) and key (`key.pem`) and, if the certificate of
+    /// the host is verified, the CA (`ca.pem`) from the directory `cert_path`. This is the layout
+    /// used by the docker CLI in `DOCKER_CERT_PATH`. Like the docker CLI the client certificate and
+    /// key are optional, a directory with only `ca.pem` verifies the host without authenticating
+    /// the client.
+    pub fn from_dir<P>(cert_path: P, verification: TlsVerification) -> Result<TlsConfig>
+    where
+        P: AsRef<Path>,
+    {
+        let cert_path = cert_path.as_ref();
+        let mut builder = TlsConfig::builder().verification(verification);
+
+        match (
+            read_optional(&cert_path.join("cert.pem"))?,
+            read_optional(&cert_path.join("key.pem"))?,
+        ) {
+            (Some(cert), Some(key)) => builder = builder.client_cert(cert, key),
+            (None, None) => {}
+            (cert, _) => {
+                let missing = if cert.is_some() {
+                    "key.pem"
+                } else {
+                    "cert.pem"
+                };
+                return Err(Error::Tls(
+                    format!(
+                        "{} is missing in {}, cert.pem and key.pem are only used together",
+                        missing,
+                        cert_path.display()
+                    )
+                    .into(),
+                ));
+            }
+        }
+
+        if verification == TlsVerification::Full {
+            // fall back to the default trust store
+            if let Some(ca) = read_optional(&cert_path.join("ca.pem"))? {
+                builder = builder.ca(ca);
+            }
+        }
+
+        Ok(builder.build())
+    }
+}
+
+/// Reads the file at `path`, returns `None` if it doesn't exist.
+fn read_optional(path: &Path) -> Result<Option<Vec<u8>>> {
+    match fs::read(path) {
+        Ok(data) => Ok(Some(data)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(Error::IO(e)),
+    }
+}
+
+#[derive(Debug, Default)]
+/// A builder struct for TlsConfig.
+pub struct TlsConfigBuilder {
+    config: TlsConfig,
+}
+
+impl TlsConfigBuilder {
+    /// Certificate and private key used to authenticate the client. If the certificate is followed
+    /// by intermediate certificates they are sent as well.
+    pub fn client_cert<C, K>(mut self, cert: C, key: K) -> Self
+    where
+        C: Into<Vec<u8>>,
+        K: Into<Vec<u8>>,
+    {
+        self.config.identity = Some((cert.into(), key.into()));
+        self
+    }
+
+    /// Bundle of CA certificates the certificate of the docker host is verified against. When set
+    /// only these certificates are trusted, otherwise the trust store of the system is used.
+    pub fn ca<C>(mut self, ca: C) -> Self
+    where
+        C: Into<Vec<u8>>,
+    {
+        self.config.ca = Some(ca.into());
+        self
+    }
+
+    /// Name the certificate of the docker host is expected to be issued for. Defaults to the host
+    /// the client connects to. Can be either a DNS name or an IP address.
+    pub fn server_name<N>(mut self, name: N) -> Self
+    where
+        N: Into<String>,
+    {
+        self.config.server_name = Some(name.into());
+        self
+    }
+
+    /// How the certificate of the docker host is verified, by default
+    /// [`TlsVerification::Full`](TlsVerification::Full).
+    pub fn verification(mut self, verification: TlsVerification) -> Self {
+        self.config.verification = verification;
+        self
+    }
+
+    /// Finish building TlsConfig.
+    pub fn build(&self) -> TlsConfig {
+        self.config.clone()
+    }
+}
+
+fn no_pem_item(item: &str) -> Error {
+    Error::Tls(format!("no valid {} found in PEM data", item).into())
+}
+
+#[cfg(feature = "tls")]
+pub(crate) fn get_https_connector(
+    http: RuntimeConnector,
+    config: &TlsConfig,
+) -> Result<HttpsConnector> {
+    use openssl::{
+        pkey::PKey,
+        ssl::{SslConnector, SslMethod, SslVerifyMode},
+        x509::{store::X509StoreBuilder, X509},
+    };
+
+    let mut ssl = SslConnector::builder(SslMethod::tls())?;
+    ssl.set_cipher_list("DEFAULT")?;
+
+    if let Some((cert, key)) = &config.identity {
+        let mut chain = X509::stack_from_pem(cert)?.into_iter();
+        let cert = chain
+            .next()
+            .ok_or_else(|| no_pem_item("client certificate"))?;
+        ssl.set_certificate(&cert)?;
+        for cert in chain {
+            ssl.add_extra_chain_cert(cert)?;
+        }
+        let key = PKey::private_key_from_pem(key)?;
+        ssl.set_private_key(&key)?;
+    }
+
+    match config.verification {
+        TlsVerification::Full => {
+            if let Some(ca) = &config.ca {
+                let mut store = X509StoreBuilder::new()?;
+                for cert in X509::stack_from_pem(ca)? {
+                    store.add_cert(cert)?;
+                }
+                ssl.set_cert_store(store.build());
+            }
+        }
+        TlsVerification::Insecure => ssl.set_verify(SslVerifyMode::NONE),
+    }
+
+    let mut connector = HttpsConnector::with_connector(http, ssl)?;
+
+    let server_name = config.server_name.clone();
+    let verification = config.verification;
+    connector.set_callback(move |ssl, _| {
+        if verification == TlsVerification::Insecure {
+            ssl.set_verify_hostname(false);
+        } else if let Some(name) = &server_name {
+            // replace the hostname that would be taken from the URI
+            ssl.set_verify_hostname(false);
+            ssl.set_use_server_name_indication(false);
+            ssl.set_hostname(name)?;
+            match name.parse() {
+                Ok(ip) => ssl.param_mut().set_ip(ip)?,
+                Err(_) => ssl.param_mut().set_host(name)?,
+            }
+        }
+        Ok(())
+    });
+
+    Ok(connector)
+}
+
+#[cfg(feature = "tls")]
+impl From<openssl::error::ErrorStack> for Error {
+    fn from(error: openssl::error::ErrorStack) -> Self {
+        Error::Tls(Box::new(error))
+    }
+}
+
+#[cfg(all(feature = "rustls-tls", not(feature = "tls")))]
+pub(crate) fn get_https_connector(
+    http: RuntimeConnector,
+    config: &TlsConfig,
+) -> Result<HttpsConnector> {
+    use rustls::{ClientConfig, RootCertStore};
+    use std::sync::Arc;
+
+    let identity = match &config.identity {
+        Some((cert, key)) => Some((
+            rustls_pem::read_certs(cert, "client certificate")?,
+            rustls_pem::read_key(key)?,
+        )),
+        None => None,
+    };
+    macro_rules! with_identity {
+        ($builder:expr) => {
+            match identity {
+                Some((certs, key)) => $builder.with_client_auth_cert(certs, key)?,
+                None => $builder.with_no_client_auth(),
+            }
+        };
+    }
+
+    let builder = ClientConfig::builder().with_safe_defaults();
+    let tls_config = match config.verification {
+        TlsVerification::Full => {
+            let mut roots = RootCertStore::empty();
+            match &config.ca {
+                Some(ca) => {
+                    for cert in rustls_pem::read_certs(ca, "CA certificate")? {
+                        roots.add(&cert)?;
+                    }
+                }
+                // fall back to the trust store of the system like the OpenSSL backend
+                None => {
+                    let certs: Vec<_> = rustls_native_certs::load_native_certs()?
+                        .into_iter()
+                        .map(|cert| cert.0)
+                        .collect();
+                    roots.add_parsable_certificates(&certs);
+                }
+            }
+            with_identity!(builder.with_root_certificates(roots))
+        }
+        TlsVerification::Insecure => with_identity!(
+            builder.with_custom_certificate_verifier(Arc::new(rustls_pem::NoVerification))
+        ),
+    };
+
+    let builder = hyper_rustls::HttpsConnectorBuilder::new()
+        .with_tls_config(tls_config)
+        .https_or_http();
+    let builder = match &config.server_name {
+        Some(name) => builder.with_server_name(name.clone()),
+        None => builder,
+    };
+
+    Ok(builder.enable_http1().wrap_connector(http))
+}
+
+#[cfg(feature = "rustls-tls")]
+impl From<rustls::Error> for Error {
+    fn from(error: rustls::Error) -> Self {
+        Error::Tls(Box::new(error))
+    }
+}
+
+#[cfg(all(feature = "rustls-tls", not(feature = "tls")))]
+mod rustls_pem {
+    use crate::Result;
+
+    use rustls::{
+        client::{ServerCertVerified, ServerCertVerifier},
+        Certificate, PrivateKey, ServerName,
+    };
+    use rustls_pemfile::Item;
+
+    use std::time::SystemTime;
+
+    pub fn read_certs(pem: &[u8], item: &str) -> Result<Vec<Certificate>> {
+        let certs = rustls_pemfile::certs(&mut { pem })?;
+        if certs.is_empty() {
+            return Err(super::no_pem_item(item));
+        }
+        Ok(certs.into_iter().map(Certificate).collect())
+    }
+
+    pub fn read_key(pem: &[u8]) -> Result<PrivateKey> {
+        let mut reader = pem;
+        while let Some(item) = rustls_pemfile::read_one(&mut reader)? {
+            match item {
+                Item::RSAKey(key) | Item::PKCS8Key(key) | Item::ECKey(key) => {
+                    return Ok(PrivateKey(key))
+                }
+                _ => continue,
+            }
+        }
+        Err(super::no_pem_item("private key"))
+    }
+
+    /// Accepts any certificate presented by the server.
+    pub struct NoVerification;
+
+    impl ServerCertVerifier for NoVerification {
+        fn verify_server_cert(
+            &self,
+            _: &Certificate,
+            _: &[Certificate],
+            _: &ServerName,
+            _: &mut dyn Iterator<Item = &[u8]>,
+            _: &[u8],
+            _: SystemTime,
+        ) -> std::result::Result<ServerCertVerified, rustls::Error> {
+            Ok(ServerCertVerified::assertion())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_tls_config_from_dir() {
+        let dir = std::env::temp_dir().join(format!("docker-api-tls-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("cert.pem"), "cert").unwrap();
+        fs::write(dir.join("key.pem"), "key").unwrap();
+        fs::write(dir.join("ca.pem"), "ca").unwrap();
+
+        let config = TlsConfig::from_dir(&dir, TlsVerification::Full).unwrap();
+        assert_eq!(config.identity, Some((b"cert".to_vec(), b"key".to_vec())));
+        assert_eq!(config.ca, Some(b"ca".to_vec()));
+        assert_eq!(config.verification, TlsVerification::Full);
+
+        let config = TlsConfig::from_dir(&dir, TlsVerification::Insecure).unwrap();
+        assert_eq!(config.ca, None);
+        assert_eq!(config.verification, TlsVerification::Insecure);
+
+        fs::remove_file(dir.join("key.pem")).unwrap();
+        assert!(matches!(
+            TlsConfig::from_dir(&dir, TlsVerification::Full),
+            Err(Error::Tls(_))
+        ));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn reads_ca_only_tls_config_from_dir() {
+        let dir = std::env::temp_dir().join(format!("docker-api-tls-ca-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("ca.pem"), "ca").unwrap();
+
+        let config = TlsConfig::from_dir(&dir, TlsVerification::Full).unwrap();
+        assert_eq!(config.identity, None);
+        assert_eq!(config.ca, Some(b"ca".to_vec()));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(any(feature = "tls", feature = "rustls-tls"))]
+    #[test]
+    fn verifies_with_system_roots_without_ca() {
+        let config = TlsConfig::builder()
+            .verification(TlsVerification::Full)
+            .build();
+        let http = RuntimeConnector::tcp(crate::conn::runtime::default_runtime(), None, None);
+        assert!(get_https_connector(http, &config).is_ok());
+    }
+}
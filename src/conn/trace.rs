@@ -0,0 +1,333 @@
+//! [`tracing`](https://docs.rs/tracing) instrumentation of requests sent to the docker daemon.
+//!
+//! Every API call gets a `docker_api` span recording the method, the endpoint template like
+//! `/containers/{id}/start`, the id of the object, the API version, the number of attempts, the
+//! status code, the number of bytes received and the duration in milliseconds. Retries of a
+//! request are recorded in the same span. Requests made on behalf of an API call, like the ping
+//! negotiating the API version or the inspect request deciding how logs are decoded, don't get a
+//! span of their own. The span stays open until the response body is
+//! read to the end or dropped, so streaming endpoints like logs or image pulls are covered as a
+//! whole.
+
+use crate::{errors::Resource, util::url::strip_version, Result};
+
+use futures_util::Stream;
+use hyper::{body::Bytes, Body, Request, Response, StatusCode};
+use tracing::{field, Instrument, Span};
+
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Instant,
+};
+
+/// Opens the span of `req`. The span is entered by [`instrument`](instrument) around all attempts
+/// of sending the request.
+pub(crate) fn request_span(req: &Request<Body>) -> Span {
+    let path = req.uri().path();
+    let (api_version, endpoint) = split_version(path);
+    let id = Resource::from_endpoint(req.method(), path).and_then(|resource| resource.id);
+
+    let span = tracing::info_span!(
+        "docker_api",
+        method = %req.method(),
+        endpoint = %endpoint_template(endpoint, id.as_deref()),
+        id = field::Empty,
+        api_version = field::Empty,
+        attempts = 1u32,
+        status = field::Empty,
+        bytes = field::Empty,
+        duration_ms = field::Empty,
+        error = field::Empty,
+    );
+    if let Some(id) = &id {
+        span.record("id", id.as_str());
+    }
+    if let Some(version) = api_version {
+        span.record("api_version", version);
+    }
+    span
+}
+
+/// Records the number of times the request of the current span was sent.
+pub(crate) fn record_attempts(attempts: u32) {
+    Span::current().record("attempts", attempts);
+}
+
+/// Runs `send` in `span` and keeps the span open until the body of the response is read.
+pub(crate) async fn instrument<F>(span: Span, send: F) -> Result<Response<Body>>
+where
+    F: Future<Output = Result<Response<Body>>>,
+{
+    let start = Instant::now();
+    match send.instrument(span.clone()).await {
+        Ok(response) => {
+            span.record("status", response.status().as_u16());
+            if response.status() == StatusCode::SWITCHING_PROTOCOLS {
+                // the body of an upgraded connection is not read through hyper
+                record_duration(&span, start);
+                return Ok(response);
+            }
+
+            let (parts, body) = response.into_parts();
+            let body = TracedBody {
+                body,
+                span,
+                start,
+                bytes: 0,
+                done: false,
+            };
+            Ok(Response::from_parts(parts, Body::wrap_stream(body)))
+        }
+        Err(e) => {
+            span.record("error", field::display(&e));
+            record_duration(&span, start);
+            Err(e)
+        }
+    }
+}
+
+fn record_duration(span: &Span, start: Instant) {
+    span.record("duration_ms", start.elapsed().as_millis() as u64);
+}
+
+/// Response body keeping the span of the request alive until it's read to the end or dropped.
+struct TracedBody {
+    body: Body,
+    span: Span,
+    start: Instant,
+    bytes: u64,
+    done: bool,
+}
+
+impl TracedBody {
+    fn finish(&mut self) {
+        if !self.done {
+            self.done = true;
+            self.span.record("bytes", self.bytes);
+            record_duration(&self.span, self.start);
+        }
+    }
+}
+
+impl Stream for TracedBody {
+    type Item = std::result::Result<Bytes, hyper::Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let span = self.span.clone();
+        let _entered = span.enter();
+        let poll = Pin::new(&mut self.body).poll_next(cx);
+        match &poll {
+            Poll::Ready(Some(Ok(chunk))) => self.bytes += chunk.len() as u64,
+            Poll::Ready(Some(Err(e))) => {
+                self.span.record("error", field::display(e));
+                self.finish();
+            }
+            Poll::Ready(None) => self.finish(),
+            Poll::Pending => {}
+        }
+        poll
+    }
+}
+
+impl Drop for TracedBody {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+/// Splits a path like `/v1.41/containers/json` into the API version and the endpoint.
+fn split_version(path: &str) -> (Option<&str>, &str) {
+    let endpoint = strip_version(path);
+    let version = path[..path.len() - endpoint.len()].trim_start_matches("/v");
+    (
+        Some(version).filter(|version| !version.is_empty()),
+        endpoint,
+    )
+}
+
+/// Replaces the object id in `endpoint` with a `{id}` placeholder.
+fn endpoint_template(endpoint: &str, id: Option<&str>) -> String {
+    match id {
+        Some(id) => endpoint.replacen(&format!("/{}", id), "/{id}", 1),
+        None => endpoint.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn templates_endpoints() {
+        let template_with = |method, path| {
+            let (version, endpoint) = split_version(path);
+            let id = Resource::from_endpoint(&method, path).and_then(|r| r.id);
+            (version, endpoint_template(endpoint, id.as_deref()))
+        };
+        let template = |path| template_with(hyper::Method::GET, path);
+
+        assert_eq!(
+            template("/v1.41/containers/abc/start"),
+            (Some("1.41"), "/containers/{id}/start".to_string())
+        );
+        assert_eq!(
+            template("/v1.40/images/library/ubuntu/json"),
+            (Some("1.40"), "/images/{id}/json".to_string())
+        );
+        assert_eq!(
+            template("/v1.41/containers/json"),
+            (Some("1.41"), "/containers/json".to_string())
+        );
+        assert_eq!(
+            template_with(hyper::Method::DELETE, "/v1.41/images/library/ubuntu"),
+            (Some("1.41"), "/images/{id}".to_string())
+        );
+        assert_eq!(
+            template_with(
+                hyper::Method::POST,
+                "/v1.41/images/registry:5000/web/app/tag"
+            ),
+            (Some("1.41"), "/images/{id}/tag".to_string())
+        );
+        assert_eq!(template("/_ping"), (None, "/_ping".to_string()));
+    }
+
+    /// Subscriber with a single span keeping the last recorded number of attempts and counting
+    /// the opened spans.
+    #[derive(Default)]
+    struct Attempts {
+        attempts: std::sync::atomic::AtomicU64,
+        spans: std::sync::atomic::AtomicUsize,
+        span: std::sync::Mutex<Option<&'static tracing::Metadata<'static>>>,
+        entered: std::sync::atomic::AtomicBool,
+    }
+
+    impl field::Visit for &Attempts {
+        fn record_u64(&mut self, field: &field::Field, value: u64) {
+            if field.name() == "attempts" {
+                self.attempts
+                    .store(value, std::sync::atomic::Ordering::SeqCst);
+            }
+        }
+
+        fn record_debug(&mut self, _: &field::Field, _: &dyn std::fmt::Debug) {}
+    }
+
+    impl tracing::Subscriber for &'static Attempts {
+        fn enabled(&self, _: &tracing::Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &tracing::span::Attributes<'_>) -> tracing::span::Id {
+            *self.span.lock().unwrap() = Some(span.metadata());
+            self.spans.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            span.record(&mut &**self);
+            tracing::span::Id::from_u64(1)
+        }
+
+        fn record(&self, _: &tracing::span::Id, values: &tracing::span::Record<'_>) {
+            values.record(&mut &**self);
+        }
+
+        fn record_follows_from(&self, _: &tracing::span::Id, _: &tracing::span::Id) {}
+
+        fn event(&self, _: &tracing::Event<'_>) {}
+
+        fn enter(&self, _: &tracing::span::Id) {
+            self.entered
+                .store(true, std::sync::atomic::Ordering::SeqCst);
+        }
+
+        fn exit(&self, _: &tracing::span::Id) {
+            self.entered
+                .store(false, std::sync::atomic::Ordering::SeqCst);
+        }
+
+        fn current_span(&self) -> tracing_core::span::Current {
+            match *self.span.lock().unwrap() {
+                Some(metadata) if self.entered.load(std::sync::atomic::Ordering::SeqCst) => {
+                    tracing_core::span::Current::new(tracing::span::Id::from_u64(1), metadata)
+                }
+                _ => tracing_core::span::Current::none(),
+            }
+        }
+    }
+
+    /// Fails the first attempt of every request.
+    struct Flaky(std::sync::atomic::AtomicUsize);
+
+    impl crate::conn::Middleware for Flaky {
+        fn handle<'a>(
+            &'a self,
+            _: Request<Body>,
+            _: crate::conn::Next<'a>,
+        ) -> futures_util::future::BoxFuture<'a, Result<Response<Body>>> {
+            let attempt = self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            let mut response = Response::new(Body::from("null"));
+            if attempt == 0 {
+                *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+            }
+            Box::pin(async move { Ok(response) })
+        }
+    }
+
+    #[tokio::test]
+    async fn records_attempts_in_one_span() {
+        let attempts: &'static Attempts = Box::leak(Box::default());
+        let _guard = tracing::subscriber::set_default(attempts);
+
+        let docker = crate::Docker::builder()
+            .host("tcp://127.0.0.1:1")
+            .middleware(Flaky(Default::default()))
+            .retry_policy(
+                crate::conn::RetryPolicy::builder()
+                    .backoff(
+                        std::time::Duration::from_millis(1),
+                        std::time::Duration::from_millis(1),
+                    )
+                    .build(),
+            )
+            .build()
+            .unwrap();
+        docker.containers().get("web").changes().await.unwrap();
+
+        assert_eq!(
+            attempts.attempts.load(std::sync::atomic::Ordering::SeqCst),
+            2
+        );
+    }
+
+    #[cfg(feature = "testing")]
+    #[tokio::test]
+    async fn opens_one_span_per_api_call() {
+        use futures_util::TryStreamExt;
+
+        let attempts: &'static Attempts = Box::leak(Box::default());
+        let _guard = tracing::subscriber::set_default(attempts);
+
+        // before API 1.42 the TTY setting of the container is looked up to decode the logs
+        let mock = crate::conn::Mock::new();
+        mock.on(hyper::Method::GET, "/containers/web/logs")
+            .chunks(StatusCode::OK, vec!["hello\n"]);
+        mock.on(hyper::Method::GET, "/containers/web/json").json(
+            StatusCode::OK,
+            &crate::api::container::tests::details(true, true),
+        );
+        let docker = crate::Docker::builder()
+            .version(crate::ApiVersion::new(1, 41))
+            .build_with_mock(mock.clone());
+        let lines: Vec<_> = docker
+            .containers()
+            .get("web")
+            .log_lines(&crate::api::LogsOpts::builder().stdout(true).build())
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(lines.len(), 1);
+        assert_eq!(mock.requests().len(), 2);
+        assert_eq!(attempts.spans.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+}
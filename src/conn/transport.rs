@@ -1,35 +1,18 @@
 //! Transports for communicating with the docker daemon
 
-use crate::{Error, Result};
-
-use futures_util::{
-    io::{AsyncRead, AsyncWrite},
-    stream::{self, Stream},
-    StreamExt, TryFutureExt,
-};
-use hyper::{
-    body::Bytes,
-    client::{Client, HttpConnector},
-    header, Body, Method, Request, Response, StatusCode,
+use crate::{
+    conn::{RuntimeConnector, SshConnector},
+    Error, Result,
 };
-#[cfg(feature = "tls")]
-use hyper_openssl::HttpsConnector;
-#[cfg(unix)]
-use hyperlocal::UnixConnector;
-#[cfg(unix)]
-use hyperlocal::Uri as DomainUri;
-use pin_project::pin_project;
 
-use serde::{Deserialize, Serialize};
+#[cfg(any(feature = "tls", feature = "rustls-tls"))]
+use crate::conn::HttpsConnector;
+#[cfg(any(test, feature = "testing"))]
+use crate::conn::{Mock, Replay};
+use hyper::{client::Client, Body, Request, Response};
 use url::Url;
 
-use std::{
-    io,
-    iter::IntoIterator,
-    path::PathBuf,
-    pin::Pin,
-    task::{Context, Poll},
-};
+use std::{iter::IntoIterator, path::PathBuf};
 
 #[derive(Debug, Default, Clone)]
 /// Helper structure used as a container for HTTP headers passed to a request
@@ -122,366 +105,98 @@ impl<B: Into<Body>> Payload<B> {
 pub enum Transport {
     /// A network tcp interface
     Tcp {
-        client: Client<HttpConnector>,
+        client: Client<RuntimeConnector>,
         host: Url,
     },
     /// TCP/TLS
-    #[cfg(feature = "tls")]
-    #[cfg_attr(docsrs, doc(cfg(feature = "tls")))]
+    #[cfg(any(feature = "tls", feature = "rustls-tls"))]
+    #[cfg_attr(docsrs, doc(cfg(any(feature = "tls", feature = "rustls-tls"))))]
     EncryptedTcp {
-        client: Client<HttpsConnector<HttpConnector>>,
+        client: Client<HttpsConnector>,
         host: Url,
     },
     /// A Unix domain socket
     #[cfg(unix)]
     Unix {
-        client: Client<UnixConnector>,
+        client: Client<RuntimeConnector>,
         path: PathBuf,
     },
+    /// HTTP tunneled over the stdio of `ssh host docker system dial-stdio`
+    Ssh {
+        client: Client<SshConnector>,
+        host: Url,
+    },
+    /// In-memory transport answering with canned responses, see [`Mock`](crate::conn::Mock)
+    #[cfg(any(test, feature = "testing"))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "testing")))]
+    Mock { mock: Mock },
+    /// In-memory transport answering with recorded responses, see
+    /// [`Replay`](crate::conn::Replay)
+    #[cfg(any(test, feature = "testing"))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "testing")))]
+    Replay { replay: Replay },
 }
 
 impl Transport {
     pub fn remote_addr(&self) -> &str {
         match &self {
             Self::Tcp { ref host, .. } => host.as_ref(),
-            #[cfg(feature = "tls")]
+            #[cfg(any(feature = "tls", feature = "rustls-tls"))]
             Self::EncryptedTcp { ref host, .. } => host.as_ref(),
             Self::Unix { ref path, .. } => path.to_str().unwrap_or_default(),
+            Self::Ssh { ref host, .. } => host.as_ref(),
+            #[cfg(any(test, feature = "testing"))]
+            Self::Mock { .. } => "mock",
+            #[cfg(any(test, feature = "testing"))]
+            Self::Replay { .. } => "replay",
         }
     }
 
-    pub(crate) async fn request<B>(
-        &self,
-        method: Method,
-        endpoint: impl AsRef<str>,
-        body: Payload<B>,
-        headers: Option<Headers>,
-    ) -> Result<Response<Body>>
-    where
-        B: Into<Body>,
-    {
-        let req = self.build_request(method, endpoint, body, headers, Request::builder())?;
-
-        self.send_request(req).await
-    }
-
-    pub(crate) async fn request_string<B>(
-        &self,
-        method: Method,
-        endpoint: impl AsRef<str>,
-        body: Payload<B>,
-        headers: Option<Headers>,
-    ) -> Result<String>
-    where
-        B: Into<Body>,
-    {
-        let body = self.get_body(method, endpoint, body, headers).await?;
-        let bytes = hyper::body::to_bytes(body).await?;
-        String::from_utf8(bytes.to_vec()).map_err(Error::from)
-    }
-
-    pub(crate) fn stream_chunks<'transport, B>(
-        &'transport self,
-        method: Method,
-        endpoint: impl AsRef<str> + 'transport,
-        body: Payload<B>,
-        headers: Option<Headers>,
-    ) -> impl Stream<Item = Result<Bytes>> + 'transport
-    where
-        B: Into<Body> + 'transport,
-    {
-        self.get_chunk_stream(method, endpoint, body, headers)
-            .try_flatten_stream()
-    }
-
-    pub(crate) fn stream_json_chunks<'transport, B>(
-        &'transport self,
-        method: Method,
-        endpoint: impl AsRef<str> + 'transport,
-        body: Payload<B>,
-        headers: Option<Headers>,
-    ) -> impl Stream<Item = Result<Bytes>> + 'transport
-    where
-        B: Into<Body> + 'transport,
-    {
-        self.get_json_chunk_stream(method, endpoint, body, headers)
-            .try_flatten_stream()
-    }
-
-    pub(crate) async fn stream_upgrade<B>(
-        &self,
-        method: Method,
-        endpoint: impl AsRef<str>,
-        body: Payload<B>,
-    ) -> Result<impl AsyncRead + AsyncWrite>
-    where
-        B: Into<Body>,
-    {
-        self.stream_upgrade_tokio(method, endpoint, body)
-            .await
-            .map(Compat::new)
-            .map_err(Error::from)
-    }
-
-    async fn get_body<B>(
-        &self,
-        method: Method,
-        endpoint: impl AsRef<str>,
-        body: Payload<B>,
-        headers: Option<Headers>,
-    ) -> Result<Body>
-    where
-        B: Into<Body>,
-    {
-        let response = self.request(method, endpoint, body, headers).await?;
-        log::trace!(
-            "got response {} {:?}",
-            response.status(),
-            response.headers()
-        );
-
-        let status = response.status();
-
-        match status {
-            // Success case: pass on the response
-            StatusCode::OK
-            | StatusCode::CREATED
-            | StatusCode::SWITCHING_PROTOCOLS
-            | StatusCode::NO_CONTENT => Ok(response.into_body()),
-            _ => {
-                let bytes = hyper::body::to_bytes(response.into_body()).await?;
-                let message_body = String::from_utf8(bytes.to_vec())?;
-
-                Err(Error::Fault {
-                    code: status,
-                    message: Self::get_error_message(&message_body).unwrap_or_else(|| {
-                        status
-                            .canonical_reason()
-                            .unwrap_or("unknown error code")
-                            .to_owned()
-                    }),
-                })
-            }
-        }
-    }
-
-    async fn get_chunk_stream<B>(
-        &self,
-        method: Method,
-        endpoint: impl AsRef<str>,
-        body: Payload<B>,
-        headers: Option<Headers>,
-    ) -> Result<impl Stream<Item = Result<Bytes>>>
-    where
-        B: Into<Body>,
-    {
-        self.get_body(method, endpoint, body, headers)
-            .await
-            .map(stream_body)
-    }
-
-    async fn get_json_chunk_stream<B>(
-        &self,
-        method: Method,
-        endpoint: impl AsRef<str>,
-        body: Payload<B>,
-        headers: Option<Headers>,
-    ) -> Result<impl Stream<Item = Result<Bytes>>>
-    where
-        B: Into<Body>,
-    {
-        self.get_body(method, endpoint, body, headers)
-            .await
-            .map(stream_json_body)
-    }
-
-    /// Builds an HTTP request.
-    fn build_request<B>(
-        &self,
-        method: Method,
-        endpoint: impl AsRef<str>,
-        body: Payload<B>,
-        headers: Option<Headers>,
-        builder: hyper::http::request::Builder,
-    ) -> Result<Request<Body>>
-    where
-        B: Into<Body>,
-    {
-        let ep = endpoint.as_ref();
-        let uri: hyper::Uri = match self {
-            Transport::Tcp { host, .. } => format!("{}{}", host, ep)
+    /// Builds the URI of `endpoint` on the docker host.
+    pub(crate) fn make_uri(&self, endpoint: &str) -> Result<hyper::Uri> {
+        match self {
+            Transport::Tcp { host, .. } => format!("{}{}", host, endpoint)
                 .parse()
-                .map_err(Error::InvalidUri)?,
-            #[cfg(feature = "tls")]
-            Transport::EncryptedTcp { host, .. } => format!("{}{}", host, ep)
+                .map_err(Error::InvalidUri),
+            #[cfg(any(feature = "tls", feature = "rustls-tls"))]
+            Transport::EncryptedTcp { host, .. } => format!("{}{}", host, endpoint)
                 .parse()
-                .map_err(Error::InvalidUri)?,
+                .map_err(Error::InvalidUri),
             #[cfg(unix)]
-            Transport::Unix { path, .. } => DomainUri::new(&path, ep).into(),
-        };
-        let req = builder.method(method).uri(&uri);
-        let mut req = req.header(header::HOST, "");
-
-        if let Some(h) = headers {
-            for (k, v) in h.into_iter() {
-                req = req.header(k, v);
+            Transport::Unix { path, .. } => crate::conn::runtime::unix_uri(path, endpoint)
+                .parse()
+                .map_err(Error::InvalidUri),
+            Transport::Ssh { host, .. } => {
+                format!("http://{}{}", host.host_str().unwrap_or_default(), endpoint)
+                    .parse()
+                    .map_err(Error::InvalidUri)
             }
+            #[cfg(any(test, feature = "testing"))]
+            Transport::Mock { .. } => format!("http://mock{}", endpoint)
+                .parse()
+                .map_err(Error::InvalidUri),
+            #[cfg(any(test, feature = "testing"))]
+            Transport::Replay { .. } => format!("http://replay{}", endpoint)
+                .parse()
+                .map_err(Error::InvalidUri),
         }
-
-        // early return
-        if body.is_none() {
-            return Ok(req.body(Body::empty())?);
-        }
-
-        let mime = body.mime_type();
-        if let Some(c) = mime {
-            req = req.header(header::CONTENT_TYPE, &c.to_string());
-        }
-
-        // it's ok to unwrap, we check that the body is not none
-        req.body(body.into_inner().unwrap().into())
-            .map_err(Error::from)
     }
 
     /// Send the given request to the docker daemon and return a Future of the response.
-    async fn send_request(&self, req: Request<Body>) -> Result<Response<Body>> {
-        log::trace!("sending request {} {}", req.method(), req.uri());
+    pub(crate) async fn send_request(&self, req: Request<Body>) -> Result<Response<Body>> {
         match self {
+            #[cfg(any(test, feature = "testing"))]
+            Transport::Mock { mock } => return mock.send_request(req).await,
+            #[cfg(any(test, feature = "testing"))]
+            Transport::Replay { replay } => return replay.send_request(req).await,
             Transport::Tcp { ref client, .. } => client.request(req),
-            #[cfg(feature = "tls")]
+            #[cfg(any(feature = "tls", feature = "rustls-tls"))]
             Transport::EncryptedTcp { ref client, .. } => client.request(req),
             #[cfg(unix)]
             Transport::Unix { ref client, .. } => client.request(req),
+            Transport::Ssh { ref client, .. } => client.request(req),
         }
         .await
         .map_err(Error::from)
     }
-
-    /// Makes an HTTP request, upgrading the connection to a TCP
-    /// stream on success.
-    ///
-    /// This method can be used for operations such as viewing
-    /// docker container logs interactively.
-    async fn stream_upgrade_tokio<B>(
-        &self,
-        method: Method,
-        endpoint: impl AsRef<str>,
-        body: Payload<B>,
-    ) -> Result<hyper::upgrade::Upgraded>
-    where
-        B: Into<Body>,
-    {
-        let req = self.build_request(
-            method,
-            endpoint,
-            body,
-            Headers::none(),
-            Request::builder()
-                .header(header::CONNECTION, "Upgrade")
-                .header(header::UPGRADE, "tcp"),
-        )?;
-
-        let response = self.send_request(req).await?;
-        match response.status() {
-            StatusCode::SWITCHING_PROTOCOLS => Ok(hyper::upgrade::on(response).await?),
-            _ => Err(Error::ConnectionNotUpgraded),
-        }
-    }
-
-    /// Extract the error message content from an HTTP response that
-    /// contains a Docker JSON error structure.
-    fn get_error_message(body: &str) -> Option<String> {
-        serde_json::from_str::<ErrorResponse>(body)
-            .map(|e| e.message)
-            .ok()
-    }
-}
-
-#[pin_project]
-struct Compat<S> {
-    #[pin]
-    tokio_multiplexer: S,
-}
-
-impl<S> Compat<S> {
-    fn new(tokio_multiplexer: S) -> Self {
-        Self { tokio_multiplexer }
-    }
-}
-
-impl<S> AsyncRead for Compat<S>
-where
-    S: tokio::io::AsyncRead,
-{
-    fn poll_read(
-        self: Pin<&mut Self>,
-        cx: &mut Context<'_>,
-        buf: &mut [u8],
-    ) -> Poll<io::Result<usize>> {
-        let mut readbuf = tokio::io::ReadBuf::new(buf);
-        match self.project().tokio_multiplexer.poll_read(cx, &mut readbuf) {
-            Poll::Pending => Poll::Pending,
-            Poll::Ready(Ok(())) => Poll::Ready(Ok(readbuf.filled().len())),
-            Poll::Ready(Err(e)) => Poll::Ready(Err(e)),
-        }
-    }
-}
-
-impl<S> AsyncWrite for Compat<S>
-where
-    S: tokio::io::AsyncWrite,
-{
-    fn poll_write(
-        self: Pin<&mut Self>,
-        cx: &mut Context<'_>,
-        buf: &[u8],
-    ) -> Poll<io::Result<usize>> {
-        self.project().tokio_multiplexer.poll_write(cx, buf)
-    }
-    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
-        self.project().tokio_multiplexer.poll_flush(cx)
-    }
-    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
-        self.project().tokio_multiplexer.poll_shutdown(cx)
-    }
-}
-
-#[derive(Serialize, Deserialize)]
-struct ErrorResponse {
-    message: String,
-}
-
-fn stream_body(body: Body) -> impl Stream<Item = Result<Bytes>> {
-    async fn unfold(mut body: Body) -> Option<(Result<Bytes>, Body)> {
-        body.next()
-            .await
-            .map(|chunk| (chunk.map_err(Error::from), body))
-    }
-
-    stream::unfold(body, unfold)
-}
-
-static JSON_WHITESPACE: &[u8] = b"\r\n";
-
-fn stream_json_body(body: Body) -> impl Stream<Item = Result<Bytes>> {
-    async fn unfold(mut body: Body) -> Option<(Result<Bytes>, Body)> {
-        let mut chunk = Vec::new();
-        while let Some(chnk) = body.next().await {
-            match chnk {
-                Ok(chnk) => {
-                    chunk.extend(chnk.to_vec());
-                    if chnk.ends_with(JSON_WHITESPACE) {
-                        break;
-                    }
-                }
-                Err(e) => {
-                    return Some((Err(Error::from(e)), body));
-                }
-            }
-        }
-
-        Some((Ok(Bytes::from(chunk)), body))
-    }
-
-    stream::unfold(body, unfold)
 }
@@ -1,12 +1,27 @@
 //! Types for working with docker TTY streams
 
-use crate::{Error, Result};
+use crate::{
+    api::{Attributes, LogLine, LogStream},
+    Error, Result,
+};
+#[cfg(feature = "chrono")]
+use chrono::{DateTime, Utc};
 use futures_util::{
+    future::{self, BoxFuture, TryFutureExt},
     io::{AsyncRead, AsyncReadExt, AsyncWrite},
-    stream::{Stream, TryStreamExt},
+    stream::{self, Stream, StreamExt, TryStreamExt},
 };
 use pin_project::pin_project;
-use std::{convert::TryInto, io};
+use std::{
+    collections::VecDeque,
+    convert::TryInto,
+    future::Future,
+    io,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
 
 /// An enum representing a chunk of TTY text streamed from a Docker container.
 ///
@@ -18,6 +33,23 @@ pub enum TtyChunk {
     StdErr(Vec<u8>),
 }
 
+impl TtyChunk {
+    /// Encodes this chunk as a frame of a multiplexed stream: a header holding the stream type and
+    /// the length of the chunk followed by the chunk itself.
+    pub fn encode(&self) -> Vec<u8> {
+        let stream_type = match self {
+            TtyChunk::StdIn(_) => 0,
+            TtyChunk::StdOut(_) => 1,
+            TtyChunk::StdErr(_) => 2,
+        };
+        let mut frame = Vec::with_capacity(8 + self.len());
+        frame.extend_from_slice(&[stream_type, 0, 0, 0]);
+        frame.extend_from_slice(&(self.len() as u32).to_be_bytes());
+        frame.extend_from_slice(self);
+        frame
+    }
+}
+
 impl From<TtyChunk> for Vec<u8> {
     fn from(tty_chunk: TtyChunk) -> Self {
         match tty_chunk {
@@ -74,7 +106,15 @@ where
         0 => TtyChunk::StdIn(data),
         1 => TtyChunk::StdOut(data),
         2 => TtyChunk::StdErr(data),
-        n => panic!("invalid stream number from docker daemon: '{}'", n),
+        n => {
+            return Some((
+                Err(Error::InvalidResponse(format!(
+                    "invalid stream number from docker daemon: '{}'",
+                    n
+                ))),
+                stream,
+            ))
+        }
     };
 
     Some((Ok(chunk), stream))
@@ -91,34 +131,411 @@ where
     futures_util::stream::unfold(stream, decode_chunk)
 }
 
-type TtyReader<'a> = Pin<Box<dyn Stream<Item = Result<TtyChunk>> + Send + 'a>>;
-type TtyWriter<'a> = Pin<Box<dyn AsyncWrite + Send + 'a>>;
+/// Media type of the logs of objects without a TTY, multiplexed into stdout and stderr.
+pub(crate) const MULTIPLEXED_STREAM: &str = "application/vnd.docker.multiplexed-stream";
+/// Media type of the logs of objects with a TTY, the raw output.
+pub(crate) const RAW_STREAM: &str = "application/vnd.docker.raw-stream";
+
+/// Splits the raw output of an object with a TTY at newlines into chunks of stdout.
+///
+/// The rest of a chunk of the response after its last newline is returned as a chunk of its own
+/// rather than joined to the next one. Every message of the daemon starts with the requested
+/// timestamp and details, the [`LogDecoder`](LogDecoder) strips them from each chunk before it
+/// appends the chunk to the line that didn't end yet.
+pub(crate) fn split_lines<S>(stream: S) -> impl Stream<Item = Result<TtyChunk>>
+where
+    S: Stream<Item = Result<hyper::body::Bytes>> + Unpin,
+{
+    stream::unfold(
+        Some((stream, hyper::body::Bytes::new())),
+        |state| async move {
+            let (mut stream, mut buffer) = state?;
+            while buffer.is_empty() {
+                match stream.next().await {
+                    Some(Ok(chunk)) => buffer = chunk,
+                    Some(Err(e)) => return Some((Err(e), None)),
+                    None => return None,
+                }
+            }
+            let end = buffer
+                .iter()
+                .position(|b| *b == b'\n')
+                .map_or(buffer.len(), |end| end + 1);
+            let line = buffer.split_to(end).to_vec();
+            Some((Ok(TtyChunk::StdOut(line)), Some((stream, buffer))))
+        },
+    )
+}
+
+/// Assembles the chunks of the logs of a container, service or task into
+/// [`LogLine`](LogLine)s.
+///
+/// Every chunk holds one message written by the logging driver. Messages longer than the buffer of
+/// the driver are split across chunks, the following parts of the same stream are appended to the
+/// line until it ends with a newline. The output of a TTY ends lines with `\r\n`, the carriage
+/// return is dropped along with the newline.
+pub(crate) struct LogDecoder {
+    timestamps: bool,
+    details: bool,
+    tty: bool,
+    /// Lines of stdout and stderr that didn't end yet.
+    partial: [Option<LogLine>; 2],
+    lines: VecDeque<LogLine>,
+}
+
+impl LogDecoder {
+    pub fn new(timestamps: bool, details: bool, tty: bool) -> Self {
+        LogDecoder {
+            timestamps,
+            details,
+            tty,
+            partial: [None, None],
+            lines: VecDeque::new(),
+        }
+    }
+
+    /// Feeds the next chunk of the logs.
+    pub fn feed(&mut self, chunk: TtyChunk) -> Result<()> {
+        let stream = match chunk {
+            TtyChunk::StdOut(_) => LogStream::StdOut,
+            TtyChunk::StdErr(_) => LogStream::StdErr,
+            TtyChunk::StdIn(_) => {
+                return Err(Error::InvalidResponse(
+                    "logs contain a chunk of stdin".into(),
+                ))
+            }
+        };
+        if chunk.is_empty() {
+            return Ok(());
+        }
+        self.message(stream, &chunk)
+    }
+
+    /// Ends the logs, the lines without a trailing newline are returned as they are.
+    pub fn finish(&mut self) {
+        for partial in &mut self.partial {
+            self.lines.extend(partial.take());
+        }
+    }
+
+    /// Returns the next complete line.
+    pub fn next_line(&mut self) -> Option<LogLine> {
+        self.lines.pop_front()
+    }
+
+    /// Adds a message that starts with the requested timestamp and details to the lines of
+    /// `stream`.
+    fn message(&mut self, stream: LogStream, mut data: &[u8]) -> Result<()> {
+        let timestamp = if self.timestamps {
+            let (timestamp, rest) = split_prefix(data, "timestamp")?;
+            data = rest;
+            Some(parse_timestamp(timestamp)?)
+        } else {
+            None
+        };
+        let details = if self.details {
+            let (details, rest) = split_prefix(data, "details")?;
+            data = rest;
+            Some(parse_details(details))
+        } else {
+            None
+        };
+
+        let start = LogLine {
+            stream,
+            timestamp,
+            details,
+            message: Vec::new(),
+        };
+        let partial = &mut self.partial[stream as usize];
+        loop {
+            let line = partial.get_or_insert_with(|| start.clone());
+            match data.iter().position(|b| *b == b'\n') {
+                Some(end) => {
+                    line.message.extend_from_slice(&data[..end]);
+                    if self.tty && line.message.last() == Some(&b'\r') {
+                        line.message.pop();
+                    }
+                    self.lines.extend(partial.take());
+                    data = &data[end + 1..];
+                    if data.is_empty() {
+                        return Ok(());
+                    }
+                }
+                None => {
+                    line.message.extend_from_slice(data);
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+/// Splits the space terminated `field` off the start of a log message.
+fn split_prefix<'a>(data: &'a [u8], field: &str) -> Result<(&'a [u8], &'a [u8])> {
+    match data.iter().position(|b| *b == b' ') {
+        Some(end) => Ok((&data[..end], &data[end + 1..])),
+        None => Err(Error::InvalidResponse(format!(
+            "log message without {}: '{}'",
+            field,
+            String::from_utf8_lossy(data)
+        ))),
+    }
+}
+
+#[cfg(feature = "chrono")]
+fn parse_timestamp(timestamp: &[u8]) -> Result<DateTime<Utc>> {
+    std::str::from_utf8(timestamp)
+        .ok()
+        .and_then(|timestamp| DateTime::parse_from_rfc3339(timestamp).ok())
+        .map(|timestamp| timestamp.with_timezone(&Utc))
+        .ok_or_else(|| {
+            Error::InvalidResponse(format!(
+                "invalid log timestamp: '{}'",
+                String::from_utf8_lossy(timestamp)
+            ))
+        })
+}
+
+#[cfg(not(feature = "chrono"))]
+fn parse_timestamp(timestamp: &[u8]) -> Result<String> {
+    Ok(String::from_utf8(timestamp.to_vec())?)
+}
+
+/// Parses details formatted as comma separated `key=value` pairs with URL encoded keys and values.
+fn parse_details(details: &[u8]) -> Attributes {
+    details
+        .split(|b| *b == b',')
+        .filter_map(|pair| url::form_urlencoded::parse(pair).next())
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect()
+}
+
+/// Decodes the chunks of the logs of a container, service or task into lines, see
+/// [`LogDecoder`](LogDecoder). `logs` resolves to whether the logs are the output of a TTY and
+/// their chunks.
+pub(crate) fn decode_logs<F, S>(
+    logs: F,
+    timestamps: bool,
+    details: bool,
+) -> impl Stream<Item = Result<LogLine>>
+where
+    F: Future<Output = Result<(bool, S)>>,
+    S: Stream<Item = Result<TtyChunk>>,
+{
+    logs.map_ok(move |(tty, stream)| {
+        decode_log_lines(stream, LogDecoder::new(timestamps, details, tty))
+    })
+    .try_flatten_stream()
+}
+
+fn decode_log_lines<S>(stream: S, decoder: LogDecoder) -> impl Stream<Item = Result<LogLine>>
+where
+    S: Stream<Item = Result<TtyChunk>>,
+{
+    stream::unfold(
+        Some((Box::pin(stream), decoder, false)),
+        |state| async move {
+            let (mut stream, mut decoder, mut done) = state?;
+            loop {
+                if let Some(line) = decoder.next_line() {
+                    return Some((Ok(line), Some((stream, decoder, done))));
+                }
+                if done {
+                    return None;
+                }
+                let result = match stream.next().await {
+                    Some(Ok(chunk)) => decoder.feed(chunk),
+                    Some(Err(e)) => Err(e),
+                    None => {
+                        done = true;
+                        decoder.finish();
+                        Ok(())
+                    }
+                };
+                if let Err(e) = result {
+                    return Some((Err(e), None));
+                }
+            }
+        },
+    )
+}
+
+/// The key sequence that detaches from a container, watched for in the data written to stdin.
+#[derive(Clone, Debug)]
+pub(crate) struct DetachKeys {
+    keys: Vec<u8>,
+    /// Length of the longest proper prefix of `keys[..=i]` that is also its suffix.
+    fallback: Vec<usize>,
+    matched: usize,
+}
+
+impl DetachKeys {
+    /// The sequence used by the docker daemon if none is configured.
+    pub const DEFAULT: &'static str = "ctrl-p,ctrl-q";
+
+    /// Parses a sequence in the format of the `detachKeys` parameter, comma separated keys that are
+    /// either a single character or `ctrl-<value>` where `<value>` is one of a-z, @, ^, [, \\, ]
+    /// or _.
+    pub fn parse(keys: &str) -> Result<Self> {
+        let keys = keys
+            .split(',')
+            .map(|key| match key.as_bytes() {
+                [key] => Ok(*key),
+                [b'c', b't', b'r', b'l', b'-', code] => match code {
+                    b'a'..=b'z' => Ok(code - b'a' + 1),
+                    b'@' => Ok(0),
+                    b'[' => Ok(27),
+                    b'\\' => Ok(28),
+                    b']' => Ok(29),
+                    b'^' => Ok(30),
+                    b'_' => Ok(31),
+                    _ => Err(Error::InvalidDetachKeys(format!("unknown key `{}`", key))),
+                },
+                _ => Err(Error::InvalidDetachKeys(format!("unknown key `{}`", key))),
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut fallback = vec![0; keys.len()];
+        let mut matched = 0;
+        for i in 1..keys.len() {
+            while matched > 0 && keys[i] != keys[matched] {
+                matched = fallback[matched - 1];
+            }
+            if keys[i] == keys[matched] {
+                matched += 1;
+            }
+            fallback[i] = matched;
+        }
+
+        Ok(DetachKeys {
+            keys,
+            fallback,
+            matched: 0,
+        })
+    }
+
+    /// Feeds data written to stdin. Once the sequence is complete returns how much of `data`
+    /// precedes it.
+    pub fn feed(&mut self, data: &[u8]) -> Option<usize> {
+        for (i, byte) in data.iter().enumerate() {
+            // on a mismatch keep the longest part of the sequence that still matches
+            while self.matched > 0 && *byte != self.keys[self.matched] {
+                self.matched = self.fallback[self.matched - 1];
+            }
+            if *byte == self.keys[self.matched] {
+                self.matched += 1;
+            }
+            if self.matched == self.keys.len() {
+                self.matched = 0;
+                return Some((i + 1).saturating_sub(self.keys.len()));
+            }
+        }
+        None
+    }
+}
+
+impl Default for DetachKeys {
+    fn default() -> Self {
+        Self::parse(Self::DEFAULT).expect("valid default detach keys")
+    }
+}
+
+/// Writer to stdin noting when the detach keys were written.
+#[pin_project]
+struct DetachWatcher<W> {
+    #[pin]
+    writer: W,
+    keys: DetachKeys,
+    keys_written: Arc<AtomicBool>,
+}
+
+impl<W: AsyncWrite> AsyncWrite for DetachWatcher<W> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.project();
+        let written = futures_util::ready!(this.writer.poll_write(cx, buf))?;
+        if this.keys.feed(&buf[..written]).is_some() {
+            this.keys_written.store(true, Ordering::SeqCst);
+        }
+        Poll::Ready(Ok(written))
+    }
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.project().writer.poll_flush(cx)
+    }
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.project().writer.poll_close(cx)
+    }
+}
+
+type TtyReader = Pin<Box<dyn Stream<Item = Result<TtyChunk>> + Send>>;
+type TtyWriter = Pin<Box<dyn AsyncWrite + Send>>;
+
+/// Asks the daemon whether the attached container is still running.
+pub(crate) type RunningCheck = Box<dyn FnOnce() -> BoxFuture<'static, bool> + Send>;
 
 /// TTY multiplexer returned by the [`attach`](crate::Container::attach) method.
 ///
 /// This object can emit a stream of `TtyChunk`s and also implements `AsyncWrite` for streaming bytes to Stdin.
+///
+/// Writing the detach keys to stdin of a container with a TTY makes the daemon close the stream
+/// while the container keeps running. Once the stream ended [`detached`](Multiplexer::detached)
+/// tells such a detach apart from the container exiting.
 #[pin_project]
-pub struct Multiplexer<'a> {
+pub struct Multiplexer {
     #[pin]
-    reader: TtyReader<'a>,
+    reader: TtyReader,
     #[pin]
-    writer: TtyWriter<'a>,
+    writer: TtyWriter,
+    detached: Arc<AtomicBool>,
 }
 
-impl<'a> Multiplexer<'a> {
-    pub(crate) fn new<T>(tcp_connection: T) -> Self
+impl Multiplexer {
+    pub(crate) fn new<T>(
+        tcp_connection: T,
+        detach_keys: DetachKeys,
+        is_running: RunningCheck,
+    ) -> Self
     where
-        T: AsyncRead + AsyncWrite + Send + 'a,
+        T: AsyncRead + AsyncWrite + Send + 'static,
     {
         let (reader, writer) = tcp_connection.split();
+        let keys_written = Arc::new(AtomicBool::new(false));
+        let detached = Arc::new(AtomicBool::new(false));
+
+        // the daemon ignores the detach keys of containers without a TTY, so after the keys were
+        // written the stream only ended because of them if the container is still running
+        let check_detached = {
+            let keys_written = keys_written.clone();
+            let detached = detached.clone();
+            stream::once(async move {
+                if keys_written.load(Ordering::SeqCst) && is_running().await {
+                    detached.store(true, Ordering::SeqCst);
+                }
+            })
+            .filter_map(|()| future::ready(None))
+        };
 
         Self {
-            reader: Box::pin(futures_util::stream::unfold(reader, |reader| {
-                decode_chunk(reader)
-            })),
-            writer: Box::pin(writer),
+            reader: Box::pin(stream::unfold(reader, decode_chunk).chain(check_detached)),
+            writer: Box::pin(DetachWatcher {
+                writer,
+                keys: detach_keys,
+                keys_written,
+            }),
+            detached,
         }
     }
+
+    /// Returns `true` if the session was detached with the detach keys and the container kept
+    /// running. This is known once the stream ended, `false` means the container exited or the
+    /// daemon closed the stream.
+    pub fn detached(&self) -> bool {
+        self.detached.load(Ordering::SeqCst)
+    }
 }
 
 use std::{
@@ -126,14 +543,14 @@ use std::{
     task::{Context, Poll},
 };
 
-impl<'a> Stream for Multiplexer<'a> {
+impl Stream for Multiplexer {
     type Item = Result<TtyChunk>;
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
         self.project().reader.poll_next(cx)
     }
 }
 
-impl<'a> AsyncWrite for Multiplexer<'a> {
+impl AsyncWrite for Multiplexer {
     fn poll_write(
         self: Pin<&mut Self>,
         cx: &mut Context<'_>,
@@ -149,14 +566,143 @@ impl<'a> AsyncWrite for Multiplexer<'a> {
     }
 }
 
-impl<'a> Multiplexer<'a> {
+impl Multiplexer {
     /// Split the `Multiplexer` into the component `Stream` and `AsyncWrite` parts
     pub fn split(
         self,
     ) -> (
-        impl Stream<Item = Result<TtyChunk>> + 'a,
-        impl AsyncWrite + Send + 'a,
+        impl Stream<Item = Result<TtyChunk>> + Send,
+        impl AsyncWrite + Send,
     ) {
         (self.reader, self.writer)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_detects_detach_keys() {
+        let mut keys = DetachKeys::default();
+        assert_eq!(keys.keys, [16, 17]);
+        assert_eq!(keys.feed(b"ls\x10"), None);
+        assert_eq!(keys.feed(b"\x11rest"), Some(0));
+        assert_eq!(keys.feed(b"\x10\x10\x11"), Some(1));
+
+        assert_eq!(
+            DetachKeys::parse("a,ctrl-@,ctrl-\\").unwrap().keys,
+            [b'a', 0, 28]
+        );
+        assert!(DetachKeys::parse("ctrl-1").is_err());
+        assert!(DetachKeys::parse("ab").is_err());
+
+        let mut keys = DetachKeys::parse("a,a,b").unwrap();
+        assert_eq!(keys.feed(b"aaab"), Some(1));
+        assert_eq!(keys.feed(b"aa"), None);
+        assert_eq!(keys.feed(b"ab"), Some(0));
+        let mut keys = DetachKeys::parse("a,b,a,c").unwrap();
+        assert_eq!(keys.feed(b"xababac"), Some(3));
+    }
+
+    #[test]
+    fn decodes_log_lines() {
+        fn take_lines(decoder: &mut LogDecoder) -> Vec<LogLine> {
+            std::iter::from_fn(|| decoder.next_line()).collect()
+        }
+        const TIME: &str = "2021-06-01T12:30:00.123456789Z";
+
+        let mut decoder = LogDecoder::new(true, true, false);
+        for chunk in [
+            TtyChunk::StdOut(format!("{} env=a%2Cb,tag=web hel", TIME).into()),
+            TtyChunk::StdErr(format!("{}  oops\n", TIME).into()),
+            TtyChunk::StdOut(format!("{} env=a%2Cb,tag=web lo\n", TIME).into()),
+        ] {
+            decoder.feed(chunk).unwrap();
+        }
+        let lines = take_lines(&mut decoder);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].stream, LogStream::StdErr);
+        assert_eq!(lines[0].message, b"oops");
+        assert_eq!(lines[0].details, Some(Attributes::new()));
+        assert_eq!(lines[1].stream, LogStream::StdOut);
+        assert_eq!(lines[1].message, b"hello");
+        let details = lines[1].details.as_ref().unwrap();
+        assert_eq!(details["env"], "a,b");
+        assert_eq!(details["tag"], "web");
+        #[cfg(feature = "chrono")]
+        let timestamp = lines[1]
+            .timestamp
+            .unwrap()
+            .to_rfc3339_opts(chrono::SecondsFormat::Nanos, true);
+        #[cfg(not(feature = "chrono"))]
+        let timestamp = lines[1].timestamp.clone().unwrap();
+        assert_eq!(timestamp, TIME);
+
+        let mut decoder = LogDecoder::new(false, false, false);
+        decoder.feed(TtyChunk::StdOut(b"$ ".to_vec())).unwrap();
+        decoder.finish();
+        assert_eq!(take_lines(&mut decoder)[0].message, b"$ ");
+
+        let mut decoder = LogDecoder::new(true, false, false);
+        assert!(decoder.feed(TtyChunk::StdOut(b"hello\n".to_vec())).is_err());
+
+        let mut decoder = LogDecoder::new(false, false, true);
+        for chunk in ["$ ls\r", "\nfoo\r\n", "a\rb\n"] {
+            decoder.feed(TtyChunk::StdOut(chunk.into())).unwrap();
+        }
+        let messages: Vec<_> = take_lines(&mut decoder)
+            .into_iter()
+            .map(|line| line.message)
+            .collect();
+        assert_eq!(messages, [&b"$ ls"[..], b"foo", b"a\rb"]);
+    }
+
+    #[tokio::test]
+    async fn decodes_tty_lines_split_across_timestamped_messages() {
+        const TIME: &str = "2021-06-01T12:30:00.123456789Z";
+        const LATER: &str = "2021-06-01T12:30:01.123456789Z";
+
+        let output = stream::iter(
+            [
+                format!("{} $ ls\r\n{} foo  ", TIME, TIME),
+                format!("{} bar\r\n", LATER),
+            ]
+            .map(|chunk| Ok(hyper::body::Bytes::from(chunk))),
+        );
+        let logs = future::ready(Ok((true, split_lines(output))));
+        let lines: Vec<_> = decode_logs(logs, true, false).try_collect().await.unwrap();
+        let messages: Vec<_> = lines.iter().map(|line| &line.message[..]).collect();
+        assert_eq!(messages, [&b"$ ls"[..], b"foo  bar"]);
+        #[cfg(feature = "chrono")]
+        let timestamp = lines[1]
+            .timestamp
+            .unwrap()
+            .to_rfc3339_opts(chrono::SecondsFormat::Nanos, true);
+        #[cfg(not(feature = "chrono"))]
+        let timestamp = lines[1].timestamp.clone().unwrap();
+        assert_eq!(timestamp, TIME);
+    }
+
+    #[tokio::test]
+    async fn splits_raw_output_into_lines() {
+        let output = stream::iter(
+            ["$ ls\r\nfoo  ", "bar\r\n$ "]
+                .map(|chunk| Ok(hyper::body::Bytes::from_static(chunk.as_bytes()))),
+        );
+        let lines: Vec<_> = split_lines(output)
+            .map_ok(Vec::from)
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(
+            lines,
+            [
+                b"$ ls\r\n".to_vec(),
+                b"foo  ".to_vec(),
+                b"bar\r\n".to_vec(),
+                b"$ ".to_vec()
+            ]
+        );
+    }
+}
@@ -0,0 +1,171 @@
+//! Connections upgraded to the WebSocket protocol.
+
+use crate::{conn::Connection, Error, Result};
+
+use futures_util::{
+    sink::Sink,
+    stream::{Stream, StreamExt},
+};
+use hyper::header::{HeaderMap, HeaderValue, SEC_WEBSOCKET_ACCEPT, SEC_WEBSOCKET_KEY};
+use tokio_tungstenite::{
+    tungstenite::{self, handshake::derive_accept_key, protocol::Role, Message},
+    WebSocketStream,
+};
+
+use std::{
+    fmt,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// A WebSocket connection returned by the
+/// [`attach_websocket`](crate::Container::attach_websocket) method.
+///
+/// Output of the container is received as a [`Stream`](Stream) of the data of text and binary
+/// messages, data sent through the [`Sink`](Sink) is written to stdin as binary messages. Unlike
+/// [`TtyMultiplexer`](crate::conn::Multiplexer) the output isn't multiplexed, stdout and stderr
+/// are interleaved. The stream ends when the daemon closes the connection.
+pub struct WebSocket {
+    inner: WebSocketStream<Box<dyn Connection>>,
+}
+
+impl WebSocket {
+    pub(crate) async fn client(connection: Box<dyn Connection>) -> Self {
+        WebSocket {
+            inner: WebSocketStream::from_raw_socket(connection, Role::Client, None).await,
+        }
+    }
+
+    /// Split the `WebSocket` into the component `Stream` and `Sink` parts.
+    pub fn split(
+        self,
+    ) -> (
+        impl Sink<Vec<u8>, Error = Error> + Send,
+        impl Stream<Item = Result<Vec<u8>>> + Send,
+    ) {
+        StreamExt::split(self)
+    }
+}
+
+impl Stream for WebSocket {
+    type Item = Result<Vec<u8>>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            return match futures_util::ready!(self.inner.poll_next_unpin(cx)) {
+                Some(Ok(Message::Binary(data))) => Poll::Ready(Some(Ok(data))),
+                Some(Ok(Message::Text(text))) => Poll::Ready(Some(Ok(text.into_bytes()))),
+                // pings are answered by the protocol implementation
+                Some(Ok(Message::Ping(_) | Message::Pong(_) | Message::Frame(_))) => continue,
+                Some(Ok(Message::Close(_))) | None => Poll::Ready(None),
+                Some(Err(
+                    tungstenite::Error::ConnectionClosed
+                    | tungstenite::Error::Protocol(
+                        tungstenite::error::ProtocolError::ResetWithoutClosingHandshake,
+                    ),
+                )) => Poll::Ready(None),
+                Some(Err(e)) => Poll::Ready(Some(Err(Error::WebSocket(e.into())))),
+            };
+        }
+    }
+}
+
+impl Sink<Vec<u8>> for WebSocket {
+    type Error = Error;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        Pin::new(&mut self.inner)
+            .poll_ready(cx)
+            .map_err(|e| Error::WebSocket(e.into()))
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, item: Vec<u8>) -> Result<()> {
+        Pin::new(&mut self.inner)
+            .start_send(Message::Binary(item))
+            .map_err(|e| Error::WebSocket(e.into()))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        Pin::new(&mut self.inner)
+            .poll_flush(cx)
+            .map_err(|e| Error::WebSocket(e.into()))
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        Pin::new(&mut self.inner)
+            .poll_close(cx)
+            .map_err(|e| Error::WebSocket(e.into()))
+    }
+}
+
+impl fmt::Debug for WebSocket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WebSocket").finish()
+    }
+}
+
+/// Returns the `Sec-WebSocket-Accept` value answering a request with `headers`, used by the
+/// in-memory transports to complete the handshake like a daemon would.
+pub(crate) fn accept_key(headers: &HeaderMap) -> Option<HeaderValue> {
+    let key = headers.get(SEC_WEBSOCKET_KEY)?;
+    HeaderValue::from_str(&derive_accept_key(key.as_bytes())).ok()
+}
+
+/// Checks that the daemon accepted the handshake started with `key`.
+pub(crate) fn verify_accept_key(key: &str, headers: &HeaderMap) -> Result<()> {
+    let expected = derive_accept_key(key.as_bytes());
+    match headers.get(SEC_WEBSOCKET_ACCEPT) {
+        Some(accept) if accept.as_bytes() == expected.as_bytes() => Ok(()),
+        _ => Err(Error::WebSocket(
+            "the daemon answered with an invalid Sec-WebSocket-Accept header".into(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{conn::Mock, container::AttachOpts, Docker};
+
+    use futures_util::SinkExt;
+    use hyper::Method;
+
+    #[tokio::test]
+    async fn attaches_over_websocket() {
+        let mock = Mock::new();
+        let daemon = mock.on(Method::GET, "/containers/web/attach/ws").upgrade();
+        let daemon = tokio::spawn(async move {
+            let mut daemon = WebSocketStream::from_raw_socket(daemon, Role::Server, None).await;
+            daemon.send(Message::Text("$ ".into())).await.unwrap();
+            let input = daemon.next().await.unwrap().unwrap();
+            daemon.send(input).await.unwrap();
+        });
+
+        let websocket = Docker::mock(mock.clone())
+            .containers()
+            .get("web")
+            .attach_websocket(
+                &AttachOpts::builder()
+                    .stream(true)
+                    .stdin(true)
+                    .stdout(true)
+                    .build(),
+            )
+            .await
+            .unwrap();
+        let (mut sink, mut stream) = websocket.split();
+        assert_eq!(stream.next().await.unwrap().unwrap(), b"$ ");
+        sink.send(b"ls\n".to_vec()).await.unwrap();
+        assert_eq!(stream.next().await.unwrap().unwrap(), b"ls\n");
+        daemon.await.unwrap();
+        // the connection closed without a close frame ends the stream too
+        assert!(stream.next().await.is_none());
+
+        let request = &mock.requests()[0];
+        assert_eq!(request.headers[hyper::header::UPGRADE], "websocket");
+        assert!(request.headers.contains_key(SEC_WEBSOCKET_KEY));
+        let mut query: Vec<_> = request.query.as_deref().unwrap().split('&').collect();
+        query.sort_unstable();
+        assert_eq!(query, ["stdin=true", "stdout=true", "stream=true"]);
+    }
+}
@@ -0,0 +1,409 @@
+//! Docker CLI context store support.
+//!
+//! Contexts are created and managed by the docker CLI with `docker context create` and
+//! `docker context use`. Their metadata is stored under `~/.docker/contexts/meta` and TLS material
+//! under `~/.docker/contexts/tls`, the currently selected context is saved in
+//! `~/.docker/config.json`.
+
+use crate::{
+    docker::{docker_config_dir, DEFAULT_DOCKER_HOST},
+    Error, Result,
+};
+
+use log::warn;
+use serde::Deserialize;
+
+use std::{
+    collections::HashMap,
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+/// Name of the context that is always available and configured from the environment.
+pub const DEFAULT_CONTEXT: &str = "default";
+
+#[derive(Clone, Debug, PartialEq)]
+/// Docker CLI context pointing to a docker host.
+pub struct Context {
+    pub name: String,
+    pub description: Option<String>,
+    /// URI of the docker host, for example `unix:///var/run/docker.sock` or `tcp://10.0.0.1:2376`.
+    pub host: String,
+    pub skip_tls_verify: bool,
+    /// Directory containing `ca.pem`, `cert.pem` and `key.pem` of this context if it has any TLS
+    /// material stored.
+    pub tls_path: Option<PathBuf>,
+}
+
+impl Context {
+    /// Lists all contexts available in the context store including the `default` context.
+    pub fn list() -> Result<Vec<Context>> {
+        ContextStore::from_env()?.list()
+    }
+
+    /// Returns the context with the given `name`.
+    pub fn get<N>(name: N) -> Result<Context>
+    where
+        N: AsRef<str>,
+    {
+        ContextStore::from_env()?.get(name.as_ref())
+    }
+
+    /// Returns the currently selected context, see [`current_name`](Context::current_name).
+    pub fn current() -> Result<Context> {
+        let store = ContextStore::from_env()?;
+        store.get(&store.current_name()?)
+    }
+
+    /// Returns the name of the currently selected context.
+    ///
+    /// Same as the docker CLI, the `default` context is used if `DOCKER_HOST` is set, otherwise
+    /// `DOCKER_CONTEXT` is used followed by the `currentContext` entry of `config.json`.
+    pub fn current_name() -> Result<String> {
+        ContextStore::from_env()?.current_name()
+    }
+
+    /// Whether this is the `default` context configured from the environment.
+    pub fn is_default(&self) -> bool {
+        self.name == DEFAULT_CONTEXT
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ContextMeta {
+    name: String,
+    #[serde(default)]
+    metadata: Option<ContextMetadata>,
+    #[serde(default)]
+    endpoints: HashMap<String, EndpointMeta>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ContextMetadata {
+    description: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct EndpointMeta {
+    host: Option<String>,
+    #[serde(rename = "SkipTLSVerify", default)]
+    skip_tls_verify: bool,
+}
+
+#[derive(Deserialize)]
+struct ConfigFile {
+    #[serde(rename = "currentContext")]
+    current_context: Option<String>,
+}
+
+/// Name of the endpoint used for the docker daemon in context metadata.
+const DOCKER_ENDPOINT: &str = "docker";
+
+struct ContextStore {
+    config_dir: PathBuf,
+    docker_host: Option<String>,
+    docker_context: Option<String>,
+}
+
+impl ContextStore {
+    fn from_env() -> Result<Self> {
+        Self::from_env_with(|key| std::env::var(key).ok())
+    }
+
+    fn from_env_with<F>(var: F) -> Result<Self>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let var = |key: &str| var(key).filter(|val| !val.is_empty());
+        Ok(ContextStore {
+            config_dir: docker_config_dir(var).ok_or(Error::MissingConfigDir)?,
+            docker_host: var("DOCKER_HOST"),
+            docker_context: var("DOCKER_CONTEXT"),
+        })
+    }
+
+    fn current_name(&self) -> Result<String> {
+        if self.docker_host.is_some() {
+            return Ok(DEFAULT_CONTEXT.to_string());
+        }
+        if let Some(name) = &self.docker_context {
+            return Ok(name.clone());
+        }
+
+        let config = match fs::read(self.config_dir.join("config.json")) {
+            Ok(data) => serde_json::from_slice::<ConfigFile>(&data)?,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(DEFAULT_CONTEXT.to_string()),
+            Err(e) => return Err(Error::IO(e)),
+        };
+
+        Ok(config
+            .current_context
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| DEFAULT_CONTEXT.to_string()))
+    }
+
+    fn default_context(&self) -> Context {
+        Context {
+            name: DEFAULT_CONTEXT.to_string(),
+            description: Some("Current DOCKER_HOST based configuration".to_string()),
+            host: self
+                .docker_host
+                .clone()
+                .unwrap_or_else(|| DEFAULT_DOCKER_HOST.to_string()),
+            skip_tls_verify: false,
+            tls_path: None,
+        }
+    }
+
+    /// Lists the default context followed by the contexts of the store sorted by name. Contexts
+    /// with invalid metadata are skipped so a single broken context doesn't hide the others.
+    fn list(&self) -> Result<Vec<Context>> {
+        let mut contexts = vec![self.default_context()];
+        for dir in self.context_dirs()? {
+            let context = self
+                .read_meta(&dir)
+                .and_then(|meta| meta.map(|meta| self.context(&dir, meta)).transpose());
+            match context {
+                Ok(context) => contexts.extend(context),
+                Err(e) => warn!("skipping context at {}: {}", dir.display(), e),
+            }
+        }
+        contexts[1..].sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(contexts)
+    }
+
+    /// Returns the context named `name`. Only the metadata of that context has to be valid.
+    fn get(&self, name: &str) -> Result<Context> {
+        if name == DEFAULT_CONTEXT {
+            return Ok(self.default_context());
+        }
+        for dir in self.context_dirs()? {
+            // the names of unreadable contexts are unknown, they can't be the requested one
+            if let Ok(Some(meta)) = self.read_meta(&dir) {
+                if meta.name == name {
+                    return self.context(&dir, meta);
+                }
+            }
+        }
+        Err(Error::ContextNotFound(name.to_string()))
+    }
+
+    /// Returns the metadata directories of all contexts in the store.
+    fn context_dirs(&self) -> Result<Vec<PathBuf>> {
+        let meta_dir = self.config_dir.join("contexts").join("meta");
+        let entries = match fs::read_dir(&meta_dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(Error::IO(e)),
+        };
+
+        let mut dirs = vec![];
+        for entry in entries {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                dirs.push(entry.path());
+            }
+        }
+        Ok(dirs)
+    }
+
+    /// Reads the metadata of a context from its metadata directory. Returns `None` if the
+    /// directory doesn't contain any metadata.
+    fn read_meta(&self, dir: &Path) -> Result<Option<ContextMeta>> {
+        match fs::read(dir.join("meta.json")) {
+            Ok(data) => Ok(Some(serde_json::from_slice(&data)?)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(Error::IO(e)),
+        }
+    }
+
+    /// Builds the context stored in `dir` from its metadata.
+    fn context(&self, dir: &Path, mut meta: ContextMeta) -> Result<Context> {
+        let endpoint = meta.endpoints.remove(DOCKER_ENDPOINT).ok_or_else(|| {
+            Error::InvalidContext(format!(
+                "context `{}` has no `{}` endpoint",
+                meta.name, DOCKER_ENDPOINT
+            ))
+        })?;
+        let host = endpoint.host.ok_or_else(|| {
+            Error::InvalidContext(format!("context `{}` has no docker host", meta.name))
+        })?;
+
+        // TLS material is stored in a directory named the same as the metadata directory
+        let tls_path = dir.file_name().map(|id| {
+            self.config_dir
+                .join("contexts")
+                .join("tls")
+                .join(id)
+                .join(DOCKER_ENDPOINT)
+        });
+
+        Ok(Context {
+            name: meta.name,
+            description: meta.metadata.and_then(|m| m.description),
+            host,
+            skip_tls_verify: endpoint.skip_tls_verify,
+            tls_path: tls_path.filter(|path| path.is_dir()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_context(config_dir: &Path, id: &str, meta: &str, tls: bool) {
+        let dir = config_dir.join("contexts").join("meta").join(id);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("meta.json"), meta).unwrap();
+        if tls {
+            fs::create_dir_all(
+                config_dir
+                    .join("contexts")
+                    .join("tls")
+                    .join(id)
+                    .join("docker"),
+            )
+            .unwrap();
+        }
+    }
+
+    #[test]
+    fn reads_context_store() {
+        let config_dir =
+            std::env::temp_dir().join(format!("docker-api-context-store-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&config_dir);
+
+        write_context(
+            &config_dir,
+            "a3c5",
+            r#"{"Name":"remote","Metadata":{"Description":"build box"},"Endpoints":{"docker":{"Host":"tcp://10.0.0.1:2376","SkipTLSVerify":false}}}"#,
+            true,
+        );
+        write_context(
+            &config_dir,
+            "0f1e",
+            r#"{"Name":"local","Metadata":{},"Endpoints":{"docker":{"Host":"unix:///run/user/1000/docker.sock","SkipTLSVerify":false}}}"#,
+            false,
+        );
+        fs::write(
+            config_dir.join("config.json"),
+            r#"{"auths":{},"currentContext":"remote"}"#,
+        )
+        .unwrap();
+
+        let config_path = config_dir.to_string_lossy().to_string();
+        let store = |vars: &[(&str, &str)]| {
+            let mut vars: HashMap<_, _> = vars.iter().cloned().collect();
+            vars.insert("DOCKER_CONFIG", &config_path);
+            ContextStore::from_env_with(|key| vars.get(key).map(|v| v.to_string())).unwrap()
+        };
+
+        let contexts = store(&[]).list().unwrap();
+        let names: Vec<_> = contexts.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["default", "local", "remote"]);
+        assert_eq!(contexts[0].host, DEFAULT_DOCKER_HOST);
+        assert_eq!(contexts[1].tls_path, None);
+
+        let remote = store(&[]).get("remote").unwrap();
+        assert_eq!(remote.host, "tcp://10.0.0.1:2376");
+        assert_eq!(remote.description.as_deref(), Some("build box"));
+        assert_eq!(
+            remote.tls_path,
+            Some(
+                config_dir
+                    .join("contexts")
+                    .join("tls")
+                    .join("a3c5")
+                    .join("docker")
+            )
+        );
+
+        assert_eq!(store(&[]).current_name().unwrap(), "remote");
+        assert_eq!(
+            store(&[("DOCKER_CONTEXT", "local")])
+                .current_name()
+                .unwrap(),
+            "local"
+        );
+        assert_eq!(
+            store(&[
+                ("DOCKER_HOST", "tcp://127.0.0.1:2375"),
+                ("DOCKER_CONTEXT", "local")
+            ])
+            .current_name()
+            .unwrap(),
+            DEFAULT_CONTEXT
+        );
+
+        match store(&[]).get("missing").unwrap_err() {
+            Error::ContextNotFound(name) if name == "missing" => {}
+            e => panic!(r#"Expected Error::ContextNotFound("missing"), got {}"#, e),
+        }
+
+        fs::remove_dir_all(&config_dir).unwrap();
+    }
+
+    #[test]
+    fn skips_broken_contexts() {
+        let config_dir = std::env::temp_dir().join(format!(
+            "docker-api-broken-context-store-{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&config_dir);
+
+        write_context(
+            &config_dir,
+            "a3c5",
+            r#"{"Name":"remote","Metadata":{},"Endpoints":{"docker":{"Host":"tcp://10.0.0.1:2376"}}}"#,
+            false,
+        );
+        write_context(
+            &config_dir,
+            "77b0",
+            r#"{"Name":"k8s","Metadata":{},"Endpoints":{"kubernetes":{"Host":"https://10.0.0.2:6443"}}}"#,
+            false,
+        );
+        write_context(&config_dir, "9d41", r#"{"Name":"#, false);
+        fs::write(
+            config_dir.join("config.json"),
+            r#"{"currentContext":"remote"}"#,
+        )
+        .unwrap();
+
+        let config_path = config_dir.to_string_lossy().to_string();
+        let store = ContextStore::from_env_with(|key| match key {
+            "DOCKER_CONFIG" => Some(config_path.clone()),
+            _ => None,
+        })
+        .unwrap();
+
+        let names: Vec<_> = store
+            .list()
+            .unwrap()
+            .into_iter()
+            .map(|context| context.name)
+            .collect();
+        assert_eq!(names, ["default", "remote"]);
+        assert_eq!(store.get("remote").unwrap().host, "tcp://10.0.0.1:2376");
+        assert_eq!(
+            store.get(DEFAULT_CONTEXT).unwrap().host,
+            DEFAULT_DOCKER_HOST
+        );
+        assert_eq!(
+            store.get(&store.current_name().unwrap()).unwrap().name,
+            "remote"
+        );
+        assert!(matches!(
+            store.get("k8s").unwrap_err(),
+            Error::InvalidContext(_)
+        ));
+
+        fs::remove_dir_all(&config_dir).unwrap();
+    }
+}
@@ -2,35 +2,64 @@
 //!
 //! API Reference: <https://docs.docker.com/engine/api/v1.41/>
 use crate::{
-    conn::{get_http_connector, Headers, Payload, Transport},
+    api::system::PingInfo,
+    conn::{
+        runtime::{default_runtime, RuntimeExecutor},
+        tty, Headers, Middleware, Middlewares, Payload, RequestClient, Retry, RetryPolicy, Runtime,
+        RuntimeConnector, SshConnector, Transport, TtyChunk, DEFAULT_SSH_PROGRAM,
+    },
+    context::Context,
     errors::{Error, Result},
+    version::Endpoint,
     ApiVersion, Containers, Images, Networks, Volumes, LATEST_API_VERSION,
 };
 
+#[cfg(any(test, feature = "testing"))]
+use crate::conn::{Mock, Recorder, Replay};
+
 #[cfg(feature = "swarm")]
 use crate::{Configs, Nodes, Plugins, Secrets, Services, Swarm, Tasks};
 
-#[cfg(feature = "tls")]
-use crate::conn::get_https_connector;
-#[cfg(unix)]
-use crate::conn::get_unix_connector;
+#[cfg(any(feature = "tls", feature = "rustls-tls"))]
+use crate::conn::{get_https_connector, TlsConfig, TlsVerification};
 
 use futures_util::{
     io::{AsyncRead, AsyncWrite},
-    stream::Stream,
-    TryStreamExt,
+    stream::{BoxStream, Stream, StreamExt},
+    Future, TryFutureExt, TryStreamExt,
+};
+use hyper::{
+    body::Bytes,
+    client::Builder as ClientBuilder,
+    header::{self, HeaderMap, HeaderName, HeaderValue},
+    Body, Client, Method, Response,
 };
-use hyper::{body::Bytes, Body, Client, Method, Response};
 use log::trace;
 use serde::de::DeserializeOwned;
 
-use std::path::Path;
+use tokio::sync::OnceCell;
+
+use std::{
+    convert::TryFrom,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+/// Docker host used by [`Docker::from_env`](Docker::from_env) when `DOCKER_HOST` is not set.
+pub(crate) const DEFAULT_DOCKER_HOST: &str = "unix:///var/run/docker.sock";
 
 /// Entrypoint interface for communicating with docker daemon
+///
+/// Cloning is cheap, all clones share the same connection pool. The interfaces returned by this
+/// type, like [`Container`](crate::Container), own a clone so that they and the streams they return
+/// can be moved into spawned tasks.
 #[derive(Debug, Clone)]
 pub struct Docker {
     version: ApiVersion,
-    transport: Transport,
+    /// API version of the docker host, only set if version negotiation is enabled.
+    server_version: Option<Arc<OnceCell<ApiVersion>>>,
+    client: Arc<RequestClient>,
 }
 
 impl Docker {
@@ -41,9 +70,10 @@ impl Docker {
     ///  - `unix://` only works when build target is `unix`, otherwise returns an Error
     ///  - `tcp://`
     ///  - `http://`
+    ///  - `ssh://`
     ///
     ///  To create a Docker instance utilizing TLS use explicit [Docker::tls](Docker::tls)
-    ///  constructor (this requires `tls` feature enabled).
+    ///  constructor (this requires `tls` or `rustls-tls` feature enabled).
     ///  
     ///  Uses [`LATEST_API_VERSION`](crate::LATEST_API_VERSION), to use a specific version see
     ///  [`Docker::new_versioned`](Docker::new_versioned).
@@ -59,32 +89,13 @@ impl Docker {
     where
         U: AsRef<str>,
     {
-        let version = version.into();
-        let uri = uri.as_ref();
-        let mut it = uri.split("://");
+        Docker::builder().host(uri).version(version).build()
+    }
 
-        match it.next() {
-            #[cfg(unix)]
-            Some("unix") => {
-                if let Some(path) = it.next() {
-                    Ok(Docker::unix_versioned(path, version))
-                } else {
-                    Err(Error::MissingAuthority)
-                }
-            }
-            #[cfg(not(unix))]
-            Some("unix") => Err(Error::UnsupportedScheme("unix".to_string())),
-            Some("tcp") | Some("http") => {
-                if let Some(host) = it.next() {
-                    Docker::tcp_versioned(host, version)
-                } else {
-                    Err(Error::MissingAuthority)
-                }
-            }
-            Some(scheme) => Err(Error::UnsupportedScheme(scheme.to_string())),
-            None => unreachable!(), // This is never possible because calling split on an empty string
-                                    // always returns at least one element
-        }
+    /// Returns a builder that allows configuring the HTTP client used to talk to the docker host,
+    /// for example its timeouts and connection pool.
+    pub fn builder() -> DockerBuilder {
+        DockerBuilder::default()
     }
 
     #[cfg(unix)]
@@ -108,26 +119,21 @@ impl Docker {
     where
         P: AsRef<Path>,
     {
-        Docker {
-            version: version.into(),
-            transport: Transport::Unix {
-                client: Client::builder()
-                    .pool_max_idle_per_host(0)
-                    .build(get_unix_connector()),
-                path: socket_path.as_ref().to_path_buf(),
-            },
-        }
+        let builder = Docker::builder().version(version);
+        builder.finish(builder.unix_transport(socket_path.as_ref()))
     }
 
-    #[cfg(feature = "tls")]
-    #[cfg_attr(docsrs, doc(cfg(feature = "tls")))]
+    #[cfg(any(feature = "tls", feature = "rustls-tls"))]
+    #[cfg_attr(docsrs, doc(cfg(any(feature = "tls", feature = "rustls-tls"))))]
     /// Creates a new docker instance for a docker host listening on a given TCP socket `host`.
     /// `host` is the part of URI that comes after `tcp://` or `http://` or `https://` schemes,
     /// also known as authority part.
     ///
-    /// `cert_path` specifies the base path in the filesystem containing a certificate (`cert.pem`)
-    /// and a key (`key.pem`) that will be used by the client. If verify is `true` a CA file will be
-    /// added (`ca.pem`) to the connector.
+    /// `cert_path` specifies the base path in the filesystem containing an optional certificate
+    /// (`cert.pem`) and key (`key.pem`) that will be used by the client. If verify is `true` the
+    /// certificate of the host is verified against a CA file (`ca.pem`), otherwise the certificate
+    /// of the host is not verified at all, same as `--tlsverify=false` of the docker CLI. To load the
+    /// certificates from memory use [`Docker::tls_with_config`](Docker::tls_with_config).
     ///
     /// Returns an error if the provided host will fail to parse as URL or reading the certificate
     /// files will fail.
@@ -142,8 +148,8 @@ impl Docker {
         Self::tls_versioned(host, LATEST_API_VERSION, cert_path, verify)
     }
 
-    #[cfg(feature = "tls")]
-    #[cfg_attr(docsrs, doc(cfg(feature = "tls")))]
+    #[cfg(any(feature = "tls", feature = "rustls-tls"))]
+    #[cfg_attr(docsrs, doc(cfg(any(feature = "tls", feature = "rustls-tls"))))]
     /// Same as [`Docker::tls`](Docker::tls) but the API version can be explicitly specified.
     pub fn tls_versioned<H, P>(
         host: H,
@@ -155,21 +161,43 @@ impl Docker {
         H: AsRef<str>,
         P: AsRef<Path>,
     {
-        Ok(Docker {
-            version: version.into(),
-            transport: Transport::EncryptedTcp {
-                client: Client::builder().build(get_https_connector(cert_path.as_ref(), verify)?),
-                host: url::Url::parse(&format!("https://{}", host.as_ref()))
-                    .map_err(Error::InvalidUrl)?,
-            },
-        })
+        let verification = if verify {
+            TlsVerification::Full
+        } else {
+            TlsVerification::Insecure
+        };
+        Self::tls_with_config(
+            host,
+            version,
+            &TlsConfig::from_dir(cert_path, verification)?,
+        )
+    }
+
+    #[cfg(any(feature = "tls", feature = "rustls-tls"))]
+    #[cfg_attr(docsrs, doc(cfg(any(feature = "tls", feature = "rustls-tls"))))]
+    /// Creates a new docker instance for a docker host listening on a given TCP socket `host`
+    /// using the TLS settings from `config`, see [`TlsConfig`](crate::conn::TlsConfig).
+    ///
+    /// Returns an error if the provided host will fail to parse as URL or the certificates from
+    /// `config` are invalid.
+    pub fn tls_with_config<H>(
+        host: H,
+        version: impl Into<ApiVersion>,
+        config: &TlsConfig,
+    ) -> Result<Docker>
+    where
+        H: AsRef<str>,
+    {
+        let builder = Docker::builder().version(version);
+        Ok(builder.finish(builder.tls_transport(host.as_ref(), config)?))
     }
 
     /// Creates a new docker instance for a docker host listening on a given TCP socket `host`.
     /// `host` is the part of URI that comes after `tcp://` or `http://` schemes, also known as
     /// authority part.
     ///
-    /// TLS is supported with feature `tls` enabled through [Docker::tls](Docker::tls) constructor.
+    /// TLS is supported with feature `tls` or `rustls-tls` enabled through [Docker::tls](Docker::tls)
+    /// constructor.
     ///
     /// Returns an error if the provided host will fail to parse as URL.
     ///  
@@ -187,38 +215,278 @@ impl Docker {
     where
         H: AsRef<str>,
     {
-        Ok(Docker {
-            version: version.into(),
-            transport: Transport::Tcp {
-                client: Client::builder().build(get_http_connector()),
-                host: url::Url::parse(&format!("tcp://{}", host.as_ref()))
-                    .map_err(Error::InvalidUrl)?,
-            },
-        })
+        let builder = Docker::builder().version(version);
+        Ok(builder.finish(builder.tcp_transport(host.as_ref())?))
+    }
+
+    /// Creates a new docker instance for a docker host reachable over SSH. `destination` is a URL
+    /// in the form of `ssh://[user@]host[:port]`.
+    ///
+    /// Every connection spawns `ssh` running `docker system dial-stdio` on the remote host, so the
+    /// docker CLI has to be installed there. Authentication is left to `ssh` itself, for example
+    /// through `ssh-agent` or `~/.ssh/config`.
+    ///
+    /// Returns an error if the provided destination will fail to parse as URL.
+    ///  
+    ///  Uses [`LATEST_API_VERSION`](crate::LATEST_API_VERSION), to use a specific version see
+    ///  [`Docker::ssh_versioned`](Docker::ssh_versioned).
+    pub fn ssh<D>(destination: D) -> Result<Docker>
+    where
+        D: AsRef<str>,
+    {
+        Self::ssh_versioned(destination, LATEST_API_VERSION)
+    }
+
+    /// Same as [`Docker::ssh`](Docker::ssh) but the API version can be explicitly specified.
+    pub fn ssh_versioned<D>(destination: D, version: impl Into<ApiVersion>) -> Result<Docker>
+    where
+        D: AsRef<str>,
+    {
+        Self::ssh_with_program(destination, DEFAULT_SSH_PROGRAM, version)
+    }
+
+    /// Same as [`Docker::ssh_versioned`](Docker::ssh_versioned) but spawns `program` instead of
+    /// `ssh`. The program is called with the same arguments `ssh` would be called with and has to
+    /// tunnel its stdio to the docker daemon.
+    pub fn ssh_with_program<D, P>(
+        destination: D,
+        program: P,
+        version: impl Into<ApiVersion>,
+    ) -> Result<Docker>
+    where
+        D: AsRef<str>,
+        P: AsRef<Path>,
+    {
+        let builder = Docker::builder().version(version).ssh_program(program);
+        Ok(builder.finish(builder.ssh_transport(destination.as_ref())?))
+    }
+
+    /// Creates a new Docker instance that sends requests to `mock` instead of a docker host, see
+    /// [`Mock`](crate::conn::Mock). Uses [`LATEST_API_VERSION`](crate::LATEST_API_VERSION).
+    #[cfg(any(test, feature = "testing"))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "testing")))]
+    pub fn mock(mock: Mock) -> Docker {
+        Docker::builder().build_with_mock(mock)
+    }
+
+    /// Creates a new Docker instance that answers requests with the responses recorded in a
+    /// fixture instead of sending them to a docker host, see [`Replay`](crate::conn::Replay).
+    /// Uses [`LATEST_API_VERSION`](crate::LATEST_API_VERSION).
+    #[cfg(any(test, feature = "testing"))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "testing")))]
+    pub fn replay(replay: Replay) -> Docker {
+        Docker::builder().build_with_replay(replay)
+    }
+
+    /// Creates a new Docker instance configured from the environment the same way the docker CLI
+    /// does it.
+    ///
+    /// Following variables are used:
+    ///  - `DOCKER_HOST` - URI of the docker host, defaults to `unix:///var/run/docker.sock`.
+    ///  - `DOCKER_TLS_VERIFY` - if set to a non-empty value the connection is encrypted and the
+    ///    certificate of the host is verified using `ca.pem`.
+    ///  - `DOCKER_CERT_PATH` - directory containing `cert.pem`, `key.pem` and `ca.pem`, defaults
+    ///    to `~/.docker`. Only used when `DOCKER_TLS_VERIFY` is set.
+    ///  - `DOCKER_API_VERSION` - API version used by the client, defaults to
+    ///    [`LATEST_API_VERSION`](crate::LATEST_API_VERSION).
+    ///
+    /// TLS settings only apply to `tcp://` hosts. Returns an error if TLS is requested but the
+    /// crate was built without the `tls` or `rustls-tls` feature.
+    pub fn from_env() -> Result<Docker> {
+        Self::from_env_with(|key| std::env::var(key).ok())
+    }
+
+    fn from_env_with<F>(var: F) -> Result<Docker>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let var = |key: &str| var(key).filter(|val| !val.is_empty());
+
+        let version = api_version_from_env(var)?;
+        let host = var("DOCKER_HOST").unwrap_or_else(|| DEFAULT_DOCKER_HOST.to_string());
+
+        // same as the docker CLI, `DOCKER_CERT_PATH` alone doesn't enable TLS
+        let verify = var("DOCKER_TLS_VERIFY").is_some();
+
+        match host.split_once("://") {
+            Some(("tcp", authority)) if verify => {
+                let cert_path = var("DOCKER_CERT_PATH")
+                    .map(PathBuf::from)
+                    .or_else(|| docker_config_dir(var))
+                    .ok_or(Error::MissingCertPath)?;
+                Self::tls_or_error(authority, version, Some(cert_path), verify)
+            }
+            _ => Docker::new_versioned(host, version),
+        }
+    }
+
+    /// Creates a new Docker instance for the docker host described by the context `name` from the
+    /// docker CLI context store (see `docker context ls`).
+    ///
+    /// The `default` context is resolved with [`Docker::from_env`](Docker::from_env). Same as the
+    /// docker CLI this honors `DOCKER_API_VERSION`.
+    pub fn from_context<N>(name: N) -> Result<Docker>
+    where
+        N: AsRef<str>,
+    {
+        Self::from_context_info(&Context::get(name)?)
+    }
+
+    /// Creates a new Docker instance for the currently selected docker CLI context, see
+    /// [`Context::current_name`](crate::context::Context::current_name) for how it's resolved.
+    pub fn from_current_context() -> Result<Docker> {
+        Self::from_context_info(&Context::current()?)
+    }
+
+    fn from_context_info(context: &Context) -> Result<Docker> {
+        if context.is_default() {
+            return Self::from_env();
+        }
+
+        let version = api_version_from_env(|key| std::env::var(key).ok())?;
+
+        match (context.host.split_once("://"), context.tls_path.as_ref()) {
+            (Some(("tcp", authority)), tls_path)
+                if tls_path.is_some() || context.skip_tls_verify =>
+            {
+                // like the docker CLI, skipping verification implies TLS even without any TLS
+                // material stored for the context
+                Self::tls_or_error(
+                    authority,
+                    version,
+                    tls_path.cloned(),
+                    !context.skip_tls_verify,
+                )
+            }
+            _ => Docker::new_versioned(&context.host, version),
+        }
+    }
+
+    #[cfg(any(feature = "tls", feature = "rustls-tls"))]
+    fn tls_or_error(
+        host: &str,
+        version: ApiVersion,
+        cert_path: Option<PathBuf>,
+        verify: bool,
+    ) -> Result<Docker> {
+        match cert_path {
+            Some(cert_path) => Docker::tls_versioned(host, version, cert_path, verify),
+            None => Docker::tls_with_config(
+                host,
+                version,
+                &TlsConfig::builder()
+                    .verification(if verify {
+                        TlsVerification::Full
+                    } else {
+                        TlsVerification::Insecure
+                    })
+                    .build(),
+            ),
+        }
+    }
+
+    #[cfg(not(any(feature = "tls", feature = "rustls-tls")))]
+    fn tls_or_error(_: &str, _: ApiVersion, _: Option<PathBuf>, _: bool) -> Result<Docker> {
+        Err(Error::TlsNotEnabled)
     }
 
     /// Exports an interface for interacting with Docker images
-    pub fn images(&'_ self) -> Images<'_> {
-        Images::new(self)
+    pub fn images(&self) -> Images {
+        Images::new(self.clone())
     }
 
     /// Exports an interface for interacting with Docker containers
-    pub fn containers(&'_ self) -> Containers<'_> {
-        Containers::new(self)
+    pub fn containers(&self) -> Containers {
+        Containers::new(self.clone())
     }
 
     /// Exports an interface for interacting with Docker networks
-    pub fn networks(&'_ self) -> Networks<'_> {
-        Networks::new(self)
+    pub fn networks(&self) -> Networks {
+        Networks::new(self.clone())
     }
 
     /// Exports an interface for interacting with Docker volumes
-    pub fn volumes(&'_ self) -> Volumes<'_> {
-        Volumes::new(self)
+    pub fn volumes(&self) -> Volumes {
+        Volumes::new(self.clone())
+    }
+
+    /// Returns a clone of this instance that retries all failed requests according to the
+    /// configured [`RetryPolicy`](crate::conn::RetryPolicy), including ones that are not
+    /// idempotent like stopping a container. Request bodies of a known size are buffered in memory
+    /// to be sent again, streamed bodies like build contexts or tarballs passed to
+    /// [`Images::import_stream`](crate::Images::import_stream) are sent once and never retried.
+    /// The retry budget is shared with this instance.
+    ///
+    /// ```no_run
+    /// # async {
+    /// use docker_api::{conn::RetryPolicy, Docker};
+    ///
+    /// let docker = Docker::builder()
+    ///     .retry_policy(RetryPolicy::default())
+    ///     .build()?;
+    /// docker.retrying().containers().get("web").stop(None).await?;
+    /// # Ok::<_, docker_api::Error>(())
+    /// # };
+    /// ```
+    pub fn retrying(&self) -> Docker {
+        Docker {
+            client: Arc::new(self.client.retrying()),
+            ..self.clone()
+        }
+    }
+
+    /// Returns a clone of this instance whose requests don't open a tracing span of their own.
+    /// Used for requests made on behalf of another API call, like looking up the TTY setting of
+    /// a container to decode its logs.
+    pub(crate) fn untraced(&self) -> Docker {
+        Docker {
+            client: Arc::new(self.client.untraced()),
+            ..self.clone()
+        }
+    }
+
+    /// Returns the runtime requests are sent on.
+    pub(crate) fn runtime(&self) -> Arc<dyn Runtime> {
+        self.client.runtime().clone()
+    }
+
+    /// Returns the API version used in requests to the docker host.
+    ///
+    /// If version negotiation is enabled with
+    /// [`DockerBuilder::negotiate_version`](DockerBuilder::negotiate_version) the first call pings
+    /// the docker host to learn its API version and the lower of the client and server version is
+    /// used from then on. The result is shared by all clones of this instance.
+    pub async fn api_version(&self) -> Result<ApiVersion> {
+        let server_version = match &self.server_version {
+            Some(server_version) => server_version,
+            None => return Ok(self.version),
+        };
+
+        let server_version = server_version
+            .get_or_try_init(|| async {
+                // `/_ping` is available without a version prefix on every API version
+                // the ping is part of the first API call, it doesn't get a span of its own
+                let response = self
+                    .client
+                    .untraced()
+                    .request(Method::GET, "/_ping", Payload::empty(), Headers::none())
+                    .await?;
+                let version: ApiVersion = PingInfo::try_from(response.headers())?
+                    .api_version
+                    .parse()?;
+                trace!("negotiated API version {}", version.min(self.version));
+                Ok::<_, Error>(version)
+            })
+            .await?;
+
+        Ok(self.version.min(*server_version))
     }
 
     /// Verifies the API version returned by the server and adjusts the version used by this client
     /// in future requests.
+    ///
+    /// To negotiate the version automatically before the first request see
+    /// [`DockerBuilder::negotiate_version`](DockerBuilder::negotiate_version).
     pub async fn adjust_api_version(&mut self) -> Result<()> {
         let server_version: ApiVersion =
             self.version().await.and_then(|v| v.api_version.parse())?;
@@ -236,23 +504,30 @@ impl Docker {
     //
     //####################################################################################################
 
-    pub(crate) async fn get(&self, endpoint: &str) -> Result<Response<Body>> {
-        self.transport
+    async fn make_endpoint(&self, endpoint: Endpoint) -> Result<String> {
+        endpoint.versioned(self.api_version().await?)
+    }
+
+    pub(crate) async fn get(&self, endpoint: impl Into<Endpoint>) -> Result<Response<Body>> {
+        self.client
             .request(
                 Method::GET,
-                self.version.make_endpoint(endpoint),
+                self.make_endpoint(endpoint.into()).await?,
                 Payload::empty(),
                 Headers::none(),
             )
             .await
     }
 
-    pub(crate) async fn get_json<T: DeserializeOwned>(&self, endpoint: &str) -> Result<T> {
+    pub(crate) async fn get_json<T: DeserializeOwned>(
+        &self,
+        endpoint: impl Into<Endpoint>,
+    ) -> Result<T> {
         let raw_string = self
-            .transport
+            .client
             .request_string(
                 Method::GET,
-                self.version.make_endpoint(endpoint),
+                self.make_endpoint(endpoint.into()).await?,
                 Payload::empty(),
                 Headers::none(),
             )
@@ -262,14 +537,18 @@ impl Docker {
         Ok(serde_json::from_str::<T>(&raw_string)?)
     }
 
-    pub(crate) async fn post<B>(&self, endpoint: &str, body: Payload<B>) -> Result<String>
+    pub(crate) async fn post<B>(
+        &self,
+        endpoint: impl Into<Endpoint>,
+        body: Payload<B>,
+    ) -> Result<String>
     where
         B: Into<Body>,
     {
-        self.transport
+        self.client
             .request_string(
                 Method::POST,
-                self.version.make_endpoint(endpoint),
+                self.make_endpoint(endpoint.into()).await?,
                 body,
                 Headers::none(),
             )
@@ -278,31 +557,35 @@ impl Docker {
 
     pub(crate) async fn post_headers<B>(
         &self,
-        endpoint: &str,
+        endpoint: impl Into<Endpoint>,
         body: Payload<B>,
         headers: Headers,
     ) -> Result<String>
     where
         B: Into<Body>,
     {
-        self.transport
+        self.client
             .request_string(
                 Method::POST,
-                self.version.make_endpoint(endpoint),
+                self.make_endpoint(endpoint.into()).await?,
                 body,
                 Some(headers),
             )
             .await
     }
 
-    pub(crate) async fn put<B>(&self, endpoint: &str, body: Payload<B>) -> Result<String>
+    pub(crate) async fn put<B>(
+        &self,
+        endpoint: impl Into<Endpoint>,
+        body: Payload<B>,
+    ) -> Result<String>
     where
         B: Into<Body>,
     {
-        self.transport
+        self.client
             .request_string(
                 Method::PUT,
-                self.version.make_endpoint(endpoint),
+                self.make_endpoint(endpoint.into()).await?,
                 body,
                 Headers::none(),
             )
@@ -311,7 +594,7 @@ impl Docker {
 
     pub(crate) async fn post_json<B, T>(
         &self,
-        endpoint: impl AsRef<str>,
+        endpoint: impl Into<Endpoint>,
         body: Payload<B>,
     ) -> Result<T>
     where
@@ -319,10 +602,10 @@ impl Docker {
         B: Into<Body>,
     {
         let raw_string = self
-            .transport
+            .client
             .request_string(
                 Method::POST,
-                self.version.make_endpoint(endpoint),
+                self.make_endpoint(endpoint.into()).await?,
                 body,
                 Headers::none(),
             )
@@ -335,7 +618,7 @@ impl Docker {
     #[allow(dead_code)]
     pub(crate) async fn post_json_headers<'a, B, T>(
         &self,
-        endpoint: impl AsRef<str>,
+        endpoint: impl Into<Endpoint>,
         body: Payload<B>,
         headers: Option<Headers>,
     ) -> Result<T>
@@ -344,10 +627,10 @@ impl Docker {
         B: Into<Body>,
     {
         let raw_string = self
-            .transport
+            .client
             .request_string(
                 Method::POST,
-                self.version.make_endpoint(endpoint),
+                self.make_endpoint(endpoint.into()).await?,
                 body,
                 headers,
             )
@@ -357,23 +640,26 @@ impl Docker {
         Ok(serde_json::from_str::<T>(&raw_string)?)
     }
 
-    pub(crate) async fn delete(&self, endpoint: &str) -> Result<String> {
-        self.transport
+    pub(crate) async fn delete(&self, endpoint: impl Into<Endpoint>) -> Result<String> {
+        self.client
             .request_string(
                 Method::DELETE,
-                self.version.make_endpoint(endpoint),
+                self.make_endpoint(endpoint.into()).await?,
                 Payload::empty(),
                 Headers::none(),
             )
             .await
     }
 
-    pub(crate) async fn delete_json<T: DeserializeOwned>(&self, endpoint: &str) -> Result<T> {
+    pub(crate) async fn delete_json<T: DeserializeOwned>(
+        &self,
+        endpoint: impl Into<Endpoint>,
+    ) -> Result<T> {
         let raw_string = self
-            .transport
+            .client
             .request_string(
                 Method::DELETE,
-                self.version.make_endpoint(endpoint),
+                self.make_endpoint(endpoint.into()).await?,
                 Payload::empty(),
                 Headers::none(),
             )
@@ -383,11 +669,14 @@ impl Docker {
         Ok(serde_json::from_str::<T>(&raw_string)?)
     }
 
-    pub(crate) async fn head_response(&self, endpoint: &str) -> Result<Response<Body>> {
-        self.transport
+    pub(crate) async fn head_response(
+        &self,
+        endpoint: impl Into<Endpoint>,
+    ) -> Result<Response<Body>> {
+        self.client
             .request(
                 Method::HEAD,
-                self.version.make_endpoint(endpoint),
+                self.make_endpoint(endpoint.into()).await?,
                 Payload::empty(),
                 Headers::none(),
             )
@@ -397,52 +686,60 @@ impl Docker {
     /// Send a streaming post request.
     ///
     /// Use stream_post_into_values if the endpoint returns JSON values
-    pub(crate) fn stream_post<'a, B>(
-        &'a self,
-        endpoint: impl AsRef<str> + 'a,
+    pub(crate) fn stream_post<B>(
+        &self,
+        endpoint: impl Into<Endpoint>,
         body: Payload<B>,
         headers: Option<Headers>,
-    ) -> impl Stream<Item = Result<Bytes>> + 'a
+    ) -> impl Stream<Item = Result<Bytes>> + 'static
     where
-        B: Into<Body> + 'a,
+        B: Into<Body> + 'static,
     {
-        self.transport.stream_chunks(
-            Method::POST,
-            self.version.make_endpoint(endpoint),
-            body,
-            headers,
-        )
+        let docker = self.clone();
+        let endpoint = endpoint.into();
+        async move {
+            let ep = docker.make_endpoint(endpoint).await?;
+            docker
+                .client
+                .get_chunk_stream(Method::POST, ep, body, headers)
+                .await
+        }
+        .try_flatten_stream()
     }
 
     /// Send a streaming post request.
-    fn stream_json_post<'a, B>(
-        &'a self,
-        endpoint: impl AsRef<str> + 'a,
+    fn stream_json_post<B>(
+        &self,
+        endpoint: impl Into<Endpoint>,
         body: Payload<B>,
         headers: Option<Headers>,
-    ) -> impl Stream<Item = Result<Bytes>> + 'a
+    ) -> impl Stream<Item = Result<Bytes>> + 'static
     where
-        B: Into<Body> + 'a,
+        B: Into<Body> + 'static,
     {
-        self.transport.stream_json_chunks(
-            Method::POST,
-            self.version.make_endpoint(endpoint),
-            body,
-            headers,
-        )
+        let docker = self.clone();
+        let endpoint = endpoint.into();
+        async move {
+            let ep = docker.make_endpoint(endpoint).await?;
+            docker
+                .client
+                .get_json_chunk_stream(Method::POST, ep, body, headers)
+                .await
+        }
+        .try_flatten_stream()
     }
 
     /// Send a streaming post request that returns a stream of JSON values
     ///
     /// When a received chunk does not contain a full JSON reads more chunks from the stream
-    pub(crate) fn stream_post_into<'a, B, T>(
-        &'a self,
-        endpoint: impl AsRef<str> + 'a,
+    pub(crate) fn stream_post_into<B, T>(
+        &self,
+        endpoint: impl Into<Endpoint>,
         body: Payload<B>,
         headers: Option<Headers>,
-    ) -> impl Stream<Item = Result<T>> + 'a
+    ) -> impl Stream<Item = Result<T>> + 'static
     where
-        B: Into<Body> + 'a,
+        B: Into<Body> + 'static,
         T: DeserializeOwned,
     {
         self.stream_json_post(endpoint, body, headers)
@@ -460,28 +757,90 @@ impl Docker {
             .try_flatten()
     }
 
-    pub(crate) fn stream_get<'a>(
-        &'a self,
-        endpoint: impl AsRef<str> + Unpin + 'a,
-    ) -> impl Stream<Item = Result<Bytes>> + 'a {
-        self.transport.stream_chunks(
-            Method::GET,
-            self.version.make_endpoint(endpoint),
-            Payload::empty(),
-            Headers::none(),
-        )
+    /// Requests the logs at `endpoint` and resolves to whether they are the raw output of a TTY
+    /// and a stream of their chunks of stdout and stderr.
+    ///
+    /// Only the logs of objects without a TTY are multiplexed. Since API 1.42 the daemon tells
+    /// them apart by the `Content-Type` of the response, for older versions `tty` decides.
+    pub(crate) fn stream_logs<F>(
+        &self,
+        endpoint: impl Into<Endpoint>,
+        tty: F,
+    ) -> impl Future<Output = Result<(bool, BoxStream<'static, Result<TtyChunk>>)>> + 'static
+    where
+        F: Future<Output = Result<bool>> + Send + 'static,
+    {
+        let docker = self.clone();
+        let endpoint = endpoint.into();
+        async move {
+            let version = docker.api_version().await?;
+            let (headers, body) = docker
+                .client
+                .get_chunk_stream_with_headers(
+                    Method::GET,
+                    endpoint.versioned(version)?,
+                    Payload::empty(),
+                    Headers::none(),
+                )
+                .await?;
+            let content_type = headers
+                .get(header::CONTENT_TYPE)
+                .and_then(|content_type| content_type.to_str().ok());
+            let multiplexed = match content_type {
+                Some(tty::MULTIPLEXED_STREAM) => true,
+                Some(tty::RAW_STREAM) if version >= ApiVersion::new(1, 42) => false,
+                _ => !tty.await?,
+            };
+
+            let body = Box::pin(body);
+            Ok(if multiplexed {
+                (false, tty::decode(body).boxed())
+            } else {
+                (true, tty::split_lines(body).boxed())
+            })
+        }
     }
 
-    pub(crate) async fn stream_post_upgrade<'a, B>(
-        &'a self,
-        endpoint: impl AsRef<str> + 'a,
+    pub(crate) fn stream_get(
+        &self,
+        endpoint: impl Into<Endpoint>,
+    ) -> impl Stream<Item = Result<Bytes>> + 'static {
+        let docker = self.clone();
+        let endpoint = endpoint.into();
+        async move {
+            let ep = docker.make_endpoint(endpoint).await?;
+            docker
+                .client
+                .get_chunk_stream(Method::GET, ep, Payload::empty(), Headers::none())
+                .await
+        }
+        .try_flatten_stream()
+    }
+
+    #[cfg(feature = "websocket")]
+    pub(crate) async fn websocket(
+        &self,
+        endpoint: impl Into<Endpoint>,
+    ) -> Result<crate::conn::WebSocket> {
+        self.client
+            .websocket_upgrade(self.make_endpoint(endpoint.into()).await?)
+            .await
+    }
+
+    pub(crate) async fn stream_post_upgrade<B>(
+        &self,
+        endpoint: impl Into<Endpoint>,
         body: Payload<B>,
-    ) -> Result<impl AsyncRead + AsyncWrite + 'a>
+    ) -> Result<impl AsyncRead + AsyncWrite + Send + 'static>
     where
-        B: Into<Body> + 'a,
+        B: Into<Body>,
     {
-        self.transport
-            .stream_upgrade(Method::POST, self.version.make_endpoint(endpoint), body)
+        self.client
+            .stream_upgrade(
+                Method::POST,
+                self.make_endpoint(endpoint.into()).await?,
+                body,
+            )
             .await
     }
 }
@@ -489,50 +848,437 @@ impl Docker {
 #[cfg(feature = "swarm")]
 impl Docker {
     /// Exports an interface for interacting with Docker services.
-    pub fn services(&'_ self) -> Services<'_> {
-        Services::new(self)
+    pub fn services(&self) -> Services {
+        Services::new(self.clone())
     }
 
     /// Exports an interface for interacting with Docker configs.
-    pub fn configs(&'_ self) -> Configs<'_> {
-        Configs::new(self)
+    pub fn configs(&self) -> Configs {
+        Configs::new(self.clone())
     }
 
     /// Exports an interface for interacting with Docker tasks.
-    pub fn tasks(&'_ self) -> Tasks<'_> {
-        Tasks::new(self)
+    pub fn tasks(&self) -> Tasks {
+        Tasks::new(self.clone())
     }
 
     /// Exports an interface for interacting with Docker secrets.
-    pub fn secrets(&'_ self) -> Secrets<'_> {
-        Secrets::new(self)
+    pub fn secrets(&self) -> Secrets {
+        Secrets::new(self.clone())
     }
 
     /// Exports an interface for interacting with Docker swarm.
-    pub fn swarm(&'_ self) -> Swarm<'_> {
-        Swarm::new(self)
+    pub fn swarm(&self) -> Swarm {
+        Swarm::new(self.clone())
     }
 
     /// Exports an interface for interacting with Docker nodes.
-    pub fn nodes(&'_ self) -> Nodes<'_> {
-        Nodes::new(self)
+    pub fn nodes(&self) -> Nodes {
+        Nodes::new(self.clone())
     }
 
     /// Exports an interface for interacting with Docker plugins.
-    pub fn plugins(&'_ self) -> Plugins<'_> {
-        Plugins::new(self)
+    pub fn plugins(&self) -> Plugins {
+        Plugins::new(self.clone())
+    }
+}
+
+#[derive(Clone, Debug)]
+/// A builder struct for Docker.
+///
+/// ```no_run
+/// use docker_api::Docker;
+/// use std::time::Duration;
+///
+/// # fn main() -> docker_api::Result<()> {
+/// let docker = Docker::builder()
+///     .host("tcp://10.0.0.1:2375")
+///     .connect_timeout(Duration::from_secs(5))
+///     .request_timeout(Duration::from_secs(30))
+///     .pool_max_idle_per_host(16)
+///     .header(
+///         hyper::header::USER_AGENT,
+///         hyper::header::HeaderValue::from_static("orchestrator/1.0"),
+///     )
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct DockerBuilder {
+    host: String,
+    version: ApiVersion,
+    connect_timeout: Option<Duration>,
+    request_timeout: Option<Duration>,
+    pool_max_idle_per_host: Option<usize>,
+    pool_idle_timeout: Option<Duration>,
+    tcp_keepalive: Option<Duration>,
+    runtime: Arc<dyn Runtime>,
+    headers: HeaderMap,
+    #[cfg(any(feature = "tls", feature = "rustls-tls"))]
+    tls: Option<TlsConfig>,
+    ssh_program: PathBuf,
+    negotiate_version: bool,
+    middlewares: Middlewares,
+    retry_policy: Option<RetryPolicy>,
+    #[cfg(any(test, feature = "testing"))]
+    recorder: Option<Recorder>,
+}
+
+impl Default for DockerBuilder {
+    fn default() -> Self {
+        DockerBuilder {
+            host: DEFAULT_DOCKER_HOST.to_string(),
+            version: LATEST_API_VERSION,
+            connect_timeout: None,
+            request_timeout: None,
+            pool_max_idle_per_host: None,
+            pool_idle_timeout: None,
+            tcp_keepalive: None,
+            runtime: default_runtime(),
+            headers: HeaderMap::new(),
+            #[cfg(any(feature = "tls", feature = "rustls-tls"))]
+            tls: None,
+            ssh_program: PathBuf::from(DEFAULT_SSH_PROGRAM),
+            negotiate_version: false,
+            middlewares: Middlewares::default(),
+            retry_policy: None,
+            #[cfg(any(test, feature = "testing"))]
+            recorder: None,
+        }
     }
 }
 
+impl DockerBuilder {
+    /// URI of the docker host, see [`Docker::new`](Docker::new) for supported schemes. Defaults
+    /// to `unix:///var/run/docker.sock`.
+    pub fn host<U>(mut self, uri: U) -> Self
+    where
+        U: AsRef<str>,
+    {
+        self.host = uri.as_ref().to_string();
+        self
+    }
+
+    /// API version used by the client, defaults to
+    /// [`LATEST_API_VERSION`](crate::LATEST_API_VERSION).
+    pub fn version(mut self, version: impl Into<ApiVersion>) -> Self {
+        self.version = version.into();
+        self
+    }
+
+    /// Whether to negotiate the API version with the docker host. If enabled the docker host is
+    /// pinged before the first request and the lower of [`version`](DockerBuilder::version) and
+    /// the API version of the docker host is used for all requests. Useful when talking to older
+    /// docker hosts that reject requests with a too recent API version.
+    pub fn negotiate_version(mut self, negotiate: bool) -> Self {
+        self.negotiate_version = negotiate;
+        self
+    }
+
+    /// Maximum time to wait for a TCP connection to the docker host to be established. Only
+    /// applies to `tcp://` hosts.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Maximum time to wait for the docker host to respond to a request. For streaming endpoints
+    /// like logs or events this only limits the time until the response headers arrive, not the
+    /// time the stream is read. Requests that exceed it fail with
+    /// [`Error::RequestTimeout`](crate::Error::RequestTimeout).
+    pub fn request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = Some(timeout);
+        self
+    }
+
+    /// Maximum number of idle connections kept open per host. Setting it to `0` opens a new
+    /// connection for every request. Defaults to `0` for unix sockets and to no limit for other
+    /// transports.
+    pub fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.pool_max_idle_per_host = Some(max);
+        self
+    }
+
+    /// Time after which an idle connection kept in the pool is closed. Defaults to 90 seconds.
+    pub fn pool_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.pool_idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Enables TCP keep-alive probes on connections to the docker host sent after the connection
+    /// has been idle for `interval`. Only applies to `tcp://` hosts.
+    pub fn tcp_keepalive(mut self, interval: Duration) -> Self {
+        self.tcp_keepalive = Some(interval);
+        self
+    }
+
+    /// Async runtime used to open connections, run background tasks and for timers, see
+    /// [`Runtime`](crate::conn::Runtime). Defaults to [`Tokio`](crate::conn::Tokio) if the
+    /// `runtime-tokio` feature is enabled, to [`AsyncStd`](crate::conn::AsyncStd) otherwise.
+    pub fn runtime<R>(mut self, runtime: R) -> Self
+    where
+        R: Runtime,
+    {
+        self.runtime = Arc::new(runtime);
+        self
+    }
+
+    /// Adds a header sent with every request, for example `User-Agent`. Headers set by a specific
+    /// endpoint take precedence.
+    pub fn header(mut self, key: HeaderName, value: HeaderValue) -> Self {
+        self.headers.insert(key, value);
+        self
+    }
+
+    /// Replaces all headers sent with every request.
+    pub fn default_headers(mut self, headers: HeaderMap) -> Self {
+        self.headers = headers;
+        self
+    }
+
+    #[cfg(any(feature = "tls", feature = "rustls-tls"))]
+    #[cfg_attr(docsrs, doc(cfg(any(feature = "tls", feature = "rustls-tls"))))]
+    /// Encrypts the connection to a `tcp://` host using the provided TLS settings.
+    pub fn tls(mut self, config: TlsConfig) -> Self {
+        self.tls = Some(config);
+        self
+    }
+
+    /// Program spawned to connect to `ssh://` hosts, see
+    /// [`Docker::ssh_with_program`](Docker::ssh_with_program). Defaults to `ssh`.
+    pub fn ssh_program<P>(mut self, program: P) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        self.ssh_program = program.as_ref().to_path_buf();
+        self
+    }
+
+    /// Wraps every request sent to the docker host, including streaming and upgrade requests,
+    /// with `middleware`. Middleware runs in the order it's added.
+    pub fn middleware<M>(mut self, middleware: M) -> Self
+    where
+        M: Middleware,
+    {
+        self.middlewares.push(middleware);
+        self
+    }
+
+    /// Retries requests that failed because of transient errors according to `policy`. By default
+    /// requests are not retried.
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(policy);
+        self
+    }
+
+    /// Records every request and the response to it with `recorder`, see
+    /// [`Recorder`](crate::conn::Recorder). The recorder runs after all middleware so it captures
+    /// requests as they are sent to the docker host.
+    #[cfg(any(test, feature = "testing"))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "testing")))]
+    pub fn record(mut self, recorder: Recorder) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    /// Finish building Docker.
+    pub fn build(&self) -> Result<Docker> {
+        let mut it = self.host.split("://");
+
+        let transport = match it.next() {
+            #[cfg(unix)]
+            Some("unix") => {
+                if let Some(path) = it.next() {
+                    self.unix_transport(Path::new(path))
+                } else {
+                    return Err(Error::MissingAuthority);
+                }
+            }
+            #[cfg(not(unix))]
+            Some("unix") => return Err(Error::UnsupportedScheme("unix".to_string())),
+            Some("tcp") | Some("http") => {
+                if let Some(host) = it.next() {
+                    self.tcp_or_tls_transport(host)?
+                } else {
+                    return Err(Error::MissingAuthority);
+                }
+            }
+            Some("ssh") => self.ssh_transport(&self.host)?,
+            Some(scheme) => return Err(Error::UnsupportedScheme(scheme.to_string())),
+            None => unreachable!(), // This is never possible because calling split on an empty string
+                                    // always returns at least one element
+        };
+
+        Ok(self.finish(transport))
+    }
+
+    /// Finish building Docker sending requests to `mock` instead of the configured host.
+    #[cfg(any(test, feature = "testing"))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "testing")))]
+    pub fn build_with_mock(&self, mock: Mock) -> Docker {
+        self.finish(Transport::Mock { mock })
+    }
+
+    /// Finish building Docker answering requests with the responses recorded in `replay` instead
+    /// of sending them to the configured host.
+    #[cfg(any(test, feature = "testing"))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "testing")))]
+    pub fn build_with_replay(&self, replay: Replay) -> Docker {
+        self.finish(Transport::Replay { replay })
+    }
+
+    fn finish(&self, transport: Transport) -> Docker {
+        let middlewares = self.middlewares.clone();
+        #[cfg(any(test, feature = "testing"))]
+        let middlewares = {
+            let mut middlewares = middlewares;
+            if let Some(recorder) = &self.recorder {
+                middlewares.push(recorder.clone());
+            }
+            middlewares
+        };
+
+        Docker {
+            version: self.version,
+            server_version: self.negotiate_version.then(|| Arc::new(OnceCell::new())),
+            client: Arc::new(RequestClient::new(
+                transport,
+                self.runtime.clone(),
+                self.headers.clone(),
+                self.request_timeout,
+                middlewares,
+                self.retry_policy.clone().map(Retry::new),
+            )),
+        }
+    }
+
+    fn client_builder(&self, default_max_idle: Option<usize>) -> ClientBuilder {
+        let mut builder = Client::builder();
+        builder.executor(RuntimeExecutor(self.runtime.clone()));
+        if let Some(max) = self.pool_max_idle_per_host.or(default_max_idle) {
+            builder.pool_max_idle_per_host(max);
+        }
+        if let Some(timeout) = self.pool_idle_timeout {
+            builder.pool_idle_timeout(timeout);
+        }
+        builder
+    }
+
+    fn tcp_connector(&self) -> RuntimeConnector {
+        RuntimeConnector::tcp(
+            self.runtime.clone(),
+            self.connect_timeout,
+            self.tcp_keepalive,
+        )
+    }
+
+    #[cfg(unix)]
+    fn unix_transport(&self, path: &Path) -> Transport {
+        Transport::Unix {
+            client: self.client_builder(Some(0)).build(RuntimeConnector::unix(
+                self.runtime.clone(),
+                path.to_path_buf(),
+            )),
+            path: path.to_path_buf(),
+        }
+    }
+
+    fn tcp_transport(&self, host: &str) -> Result<Transport> {
+        Ok(Transport::Tcp {
+            client: self.client_builder(None).build(self.tcp_connector()),
+            host: url::Url::parse(&format!("tcp://{}", host)).map_err(Error::InvalidUrl)?,
+        })
+    }
+
+    #[cfg(any(feature = "tls", feature = "rustls-tls"))]
+    fn tls_transport(&self, host: &str, config: &TlsConfig) -> Result<Transport> {
+        Ok(Transport::EncryptedTcp {
+            client: self
+                .client_builder(None)
+                .build(get_https_connector(self.tcp_connector(), config)?),
+            host: url::Url::parse(&format!("https://{}", host)).map_err(Error::InvalidUrl)?,
+        })
+    }
+
+    #[cfg(any(feature = "tls", feature = "rustls-tls"))]
+    fn tcp_or_tls_transport(&self, host: &str) -> Result<Transport> {
+        match &self.tls {
+            Some(config) => self.tls_transport(host, config),
+            None => self.tcp_transport(host),
+        }
+    }
+
+    #[cfg(not(any(feature = "tls", feature = "rustls-tls")))]
+    fn tcp_or_tls_transport(&self, host: &str) -> Result<Transport> {
+        self.tcp_transport(host)
+    }
+
+    fn ssh_transport(&self, destination: &str) -> Result<Transport> {
+        let host = url::Url::parse(destination).map_err(Error::InvalidUrl)?;
+        Ok(Transport::Ssh {
+            client: self.client_builder(None).build(
+                SshConnector::with_program(&host, &self.ssh_program)?.runtime(self.runtime.clone()),
+            ),
+            host,
+        })
+    }
+}
+
+/// Reads the API version from `DOCKER_API_VERSION` falling back to
+/// [`LATEST_API_VERSION`](crate::LATEST_API_VERSION) if it's not set.
+fn api_version_from_env<F>(var: F) -> Result<ApiVersion>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    match var("DOCKER_API_VERSION").filter(|version| !version.is_empty()) {
+        Some(version) => version.parse(),
+        None => Ok(LATEST_API_VERSION),
+    }
+}
+
+/// Returns the directory holding docker client configuration. Same as the docker CLI this is
+/// `DOCKER_CONFIG` if set, `~/.docker` otherwise.
+pub(crate) fn docker_config_dir<F>(var: F) -> Option<PathBuf>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    var("DOCKER_CONFIG").map(PathBuf::from).or_else(|| {
+        var("HOME")
+            .or_else(|| var("USERPROFILE"))
+            .map(|home| PathBuf::from(home).join(".docker"))
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::{Docker, Error};
+
+    #[test]
+    fn handles_and_streams_outlive_docker() {
+        fn assert_spawnable<T: Send + 'static>(_: T) {}
+
+        let docker = Docker::new("tcp://127.0.0.1:80").unwrap();
+        let container = docker.containers().get("test");
+        let logs = container.logs(&Default::default());
+        let stats = container.stats();
+        let pull = docker.images().pull(&Default::default());
+        let events = docker.events(&Default::default());
+        drop(docker);
+
+        assert_spawnable(logs);
+        assert_spawnable(stats);
+        assert_spawnable(pull);
+        assert_spawnable(events);
+        assert_spawnable(container);
+    }
     #[test]
     fn creates_correct_docker() {
         let d = Docker::new("tcp://127.0.0.1:80");
         d.unwrap();
         let d = Docker::new("http://127.0.0.1:80");
         d.unwrap();
+        let d = Docker::new("ssh://user@127.0.0.1:22");
+        d.unwrap();
 
         #[cfg(unix)]
         let d = Docker::new("unix://127.0.0.1:80");
@@ -568,4 +1314,254 @@ mod tests {
             e => panic!(r#"Expected Error::UnsupportedScheme(""), got {}"#, e),
         }
     }
+
+    #[test]
+    fn creates_docker_from_env() {
+        use std::collections::HashMap;
+
+        let from_env = |vars: &[(&str, &str)]| {
+            let vars: HashMap<_, _> = vars.iter().cloned().collect();
+            Docker::from_env_with(|key| vars.get(key).map(|v| v.to_string()))
+        };
+
+        let d = from_env(&[]).unwrap();
+        assert_eq!(d.version, crate::LATEST_API_VERSION);
+        #[cfg(unix)]
+        assert_eq!(d.client.transport().remote_addr(), "/var/run/docker.sock");
+
+        let d = from_env(&[
+            ("DOCKER_HOST", "tcp://127.0.0.1:2375"),
+            ("DOCKER_API_VERSION", "1.40"),
+        ])
+        .unwrap();
+        assert_eq!(d.version, crate::ApiVersion::new(1, 40));
+        assert_eq!(d.client.transport().remote_addr(), "tcp://127.0.0.1:2375");
+
+        // empty values are treated as unset
+        let d = from_env(&[
+            ("DOCKER_HOST", "tcp://127.0.0.1:2375"),
+            ("DOCKER_TLS_VERIFY", ""),
+        ]);
+        d.unwrap();
+
+        // the cert path alone doesn't enable TLS
+        let d = from_env(&[
+            ("DOCKER_HOST", "tcp://127.0.0.1:2375"),
+            ("DOCKER_CERT_PATH", "/certs"),
+        ])
+        .unwrap();
+        assert!(matches!(
+            d.client.transport(),
+            crate::conn::Transport::Tcp { .. }
+        ));
+
+        match from_env(&[("DOCKER_API_VERSION", "latest")]).unwrap_err() {
+            Error::MalformedVersion(_) => {}
+            e => panic!("Expected Error::MalformedVersion, got {}", e),
+        }
+
+        #[cfg(not(any(feature = "tls", feature = "rustls-tls")))]
+        match from_env(&[
+            ("DOCKER_HOST", "tcp://127.0.0.1:2376"),
+            ("DOCKER_TLS_VERIFY", "1"),
+            ("DOCKER_CERT_PATH", "/certs"),
+        ])
+        .unwrap_err()
+        {
+            Error::TlsNotEnabled => {}
+            e => panic!("Expected Error::TlsNotEnabled, got {}", e),
+        }
+    }
+
+    #[test]
+    fn uses_tls_for_context_skipping_verification() {
+        let context = crate::context::Context {
+            name: "remote".into(),
+            description: None,
+            host: "tcp://10.0.0.1:2376".into(),
+            skip_tls_verify: true,
+            tls_path: None,
+        };
+        let d = Docker::from_context_info(&context);
+
+        #[cfg(any(feature = "tls", feature = "rustls-tls"))]
+        assert!(matches!(
+            d.unwrap().client.transport(),
+            crate::conn::Transport::EncryptedTcp { .. }
+        ));
+        #[cfg(not(any(feature = "tls", feature = "rustls-tls")))]
+        assert!(matches!(d.unwrap_err(), Error::TlsNotEnabled));
+    }
+
+    #[tokio::test]
+    async fn applies_builder_options() {
+        use hyper::header::{HeaderValue, USER_AGENT};
+        use std::{
+            io::{Read, Write},
+            net::TcpListener,
+            time::Duration,
+        };
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = std::thread::spawn(move || {
+            // first connection gets a response, the second one is left hanging
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buf = [0; 1024];
+            let n = stream.read(&mut buf).unwrap();
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nOK")
+                .unwrap();
+            let (_stream, _) = listener.accept().unwrap();
+            std::thread::sleep(Duration::from_millis(500));
+            String::from_utf8_lossy(&buf[..n]).to_lowercase()
+        });
+
+        let docker = Docker::builder()
+            .host(format!("tcp://{}", addr))
+            .request_timeout(Duration::from_millis(100))
+            .pool_max_idle_per_host(0)
+            .header(USER_AGENT, HeaderValue::from_static("docker-api-test"))
+            .build()
+            .unwrap();
+
+        docker.get("/_ping").await.unwrap();
+        match docker.get("/_ping").await.unwrap_err() {
+            Error::RequestTimeout(timeout) => assert_eq!(timeout, Duration::from_millis(100)),
+            e => panic!("Expected Error::RequestTimeout, got {}", e),
+        }
+        assert!(server
+            .join()
+            .unwrap()
+            .contains("user-agent: docker-api-test\r\n"));
+    }
+
+    #[tokio::test]
+    async fn negotiates_api_version_once() {
+        use std::{
+            io::{Read, Write},
+            net::TcpListener,
+        };
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = std::thread::spawn(move || {
+            let mut requests = vec![];
+            for response in [
+                "HTTP/1.1 200 OK\r\nApi-Version: 1.40\r\nDocker-Experimental: false\r\nCache-Control: no-cache\r\nPragma: no-cache\r\nOstype: linux\r\nServer: Docker/19.03.15 (linux)\r\nDate: Fri, 16 Oct 2026 10:00:00 GMT\r\nContent-Length: 2\r\n\r\nOK",
+                "HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\n[]",
+                "HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\n[]",
+            ] {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut buf = [0; 1024];
+                let n = stream.read(&mut buf).unwrap();
+                let request = String::from_utf8_lossy(&buf[..n]).to_string();
+                requests.push(request.lines().next().unwrap_or_default().to_string());
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+            requests
+        });
+
+        let docker = Docker::builder()
+            .host(format!("tcp://{}", addr))
+            .pool_max_idle_per_host(0)
+            .negotiate_version(true)
+            .build()
+            .unwrap();
+
+        docker.get_json::<Vec<()>>("/images/json").await.unwrap();
+        // clones share the negotiated version
+        docker
+            .clone()
+            .get_json::<Vec<()>>("/images/json")
+            .await
+            .unwrap();
+        assert_eq!(
+            docker.api_version().await.unwrap(),
+            crate::ApiVersion::new(1, 40)
+        );
+
+        assert_eq!(
+            server.join().unwrap(),
+            vec![
+                "GET /_ping HTTP/1.1",
+                "GET /v1.40/images/json HTTP/1.1",
+                "GET /v1.40/images/json HTTP/1.1",
+            ]
+        );
+    }
+
+    /// Writes a fake `ssh` program answering one request with `OK` to a new directory, the
+    /// arguments it was called with are written to `args` next to it.
+    #[cfg(unix)]
+    fn fake_ssh_program(name: &str) -> std::path::PathBuf {
+        use std::{fs, os::unix::fs::PermissionsExt};
+
+        let dir = std::env::temp_dir().join(format!("docker-api-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let program = dir.join("ssh");
+        fs::write(
+            &program,
+            r#"#!/bin/sh
+echo "$@" > "$(dirname "$0")/args"
+while read -r line; do
+    [ "$line" = "$(printf '\r')" ] && break
+done
+printf 'HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: 2\r\n\r\nOK'
+"#,
+        )
+        .unwrap();
+        fs::set_permissions(&program, fs::Permissions::from_mode(0o755)).unwrap();
+        program
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn tunnels_requests_through_ssh_program() {
+        use std::fs;
+
+        let program = fake_ssh_program("ssh");
+        let dir = program.parent().unwrap();
+
+        let docker = Docker::ssh_with_program(
+            "ssh://builder@build-box:2222",
+            &program,
+            crate::LATEST_API_VERSION,
+        )
+        .unwrap();
+        assert_eq!(
+            docker.client.transport().remote_addr(),
+            "ssh://builder@build-box:2222"
+        );
+
+        let response = docker.get("/_ping").await.unwrap();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(&body[..], b"OK");
+        assert_eq!(
+            fs::read_to_string(dir.join("args")).unwrap().trim(),
+            "-T -l builder -p 2222 -- build-box docker system dial-stdio"
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(all(unix, feature = "runtime-async-std"))]
+    #[test]
+    fn tunnels_requests_through_ssh_program_on_async_std() {
+        let program = fake_ssh_program("ssh-async-std");
+        let docker = Docker::builder()
+            .host("ssh://build-box")
+            .ssh_program(&program)
+            .runtime(crate::conn::AsyncStd)
+            .build()
+            .unwrap();
+
+        // no tokio runtime is running here, the process is spawned with async-std
+        async_std::task::block_on(async {
+            let response = docker.get("/_ping").await.unwrap();
+            let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+            assert_eq!(&body[..], b"OK");
+        });
+        std::fs::remove_dir_all(program.parent().unwrap()).unwrap();
+    }
 }
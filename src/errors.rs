@@ -1,8 +1,10 @@
 //! Representations of various client errors
 
-use hyper::{self, StatusCode};
+use crate::{util::url::strip_version, ApiVersion};
+
+use hyper::{self, Method, StatusCode};
 use serde_json::Error as SerdeError;
-use std::string::FromUtf8Error;
+use std::{fmt, string::FromUtf8Error, time::Duration};
 use thiserror::Error as ThisError;
 
 use futures_util::io::Error as IoError;
@@ -10,9 +12,6 @@ use futures_util::io::Error as IoError;
 /// Represents the result of all docker operations
 pub type Result<T> = std::result::Result<T, Error>;
 
-#[cfg(feature = "tls")]
-use openssl::error::ErrorStack;
-
 #[derive(Debug, ThisError)]
 pub enum Error {
     #[error(transparent)]
@@ -30,11 +29,39 @@ pub enum Error {
     InvalidResponse(String),
     #[error("error {code} - {message}")]
     Fault { code: StatusCode, message: String },
+    #[error("{} was not modified - {message}", resource_or_request(.resource))]
+    NotModified {
+        resource: Option<Resource>,
+        message: String,
+    },
+    #[error("bad parameter for {} - {message}", resource_or_request(.resource))]
+    BadParameter {
+        resource: Option<Resource>,
+        message: String,
+    },
+    #[error("{} not found - {message}", resource_or_request(.resource))]
+    NotFound {
+        resource: Option<Resource>,
+        message: String,
+    },
+    #[error("conflict on {} - {message}", resource_or_request(.resource))]
+    Conflict {
+        resource: Option<Resource>,
+        message: String,
+    },
+    #[error("service unavailable for {} - {message}", resource_or_request(.resource))]
+    ServiceUnavailable {
+        resource: Option<Resource>,
+        message: String,
+    },
     #[error("The HTTP connection was not upgraded by the docker host")]
     ConnectionNotUpgraded,
-    #[cfg(feature = "tls")]
-    #[error(transparent)]
-    ErrorStack(#[from] ErrorStack),
+    #[cfg(any(feature = "tls", feature = "rustls-tls"))]
+    #[error("TLS error - {0}")]
+    Tls(Box<dyn std::error::Error + Send + Sync>),
+    #[cfg(feature = "websocket")]
+    #[error("WebSocket error - {0}")]
+    WebSocket(Box<dyn std::error::Error + Send + Sync>),
     #[error("Provided scheme `{0}` is not supported")]
     UnsupportedScheme(String),
     #[error("Provided URI is missing authority part after scheme")]
@@ -47,6 +74,392 @@ pub enum Error {
     InvalidPort(String),
     #[error("Invalid protocol - {0}")]
     InvalidProtocol(String),
+    #[error("Invalid detach keys - {0}")]
+    InvalidDetachKeys(String),
     #[error("Invalid version - {0}")]
     MalformedVersion(String),
+    #[error("TLS connection was requested but neither `tls` nor `rustls-tls` feature is enabled")]
+    TlsNotEnabled,
+    #[error("Failed to determine the path of client certificates")]
+    MissingCertPath,
+    #[error("Failed to determine the docker configuration directory")]
+    MissingConfigDir,
+    #[error("Context `{0}` does not exist")]
+    ContextNotFound(String),
+    #[error("Invalid context - {0}")]
+    InvalidContext(String),
+    #[error("The docker host did not respond within {0:?}")]
+    RequestTimeout(Duration),
+    #[error("API version {required} is required but {actual} is used")]
+    UnsupportedApiVersion {
+        required: ApiVersion,
+        actual: ApiVersion,
+    },
+}
+
+impl Error {
+    /// Creates the error for a failed request with `method` to `endpoint` that returned `code`.
+    /// The resource is determined from the request.
+    pub(crate) fn from_status(
+        code: StatusCode,
+        message: String,
+        method: &Method,
+        endpoint: &str,
+    ) -> Self {
+        let resource = Resource::from_endpoint(method, endpoint);
+        match code {
+            StatusCode::NOT_MODIFIED => Error::NotModified { resource, message },
+            StatusCode::BAD_REQUEST => Error::BadParameter { resource, message },
+            StatusCode::NOT_FOUND => Error::NotFound { resource, message },
+            StatusCode::CONFLICT => Error::Conflict { resource, message },
+            StatusCode::SERVICE_UNAVAILABLE => Error::ServiceUnavailable { resource, message },
+            code => Error::Fault { code, message },
+        }
+    }
+
+    /// Returns the HTTP status code returned by the docker daemon if this error was caused by an
+    /// unsuccessful response.
+    pub fn status_code(&self) -> Option<StatusCode> {
+        match self {
+            Error::Fault { code, .. } => Some(*code),
+            Error::NotModified { .. } => Some(StatusCode::NOT_MODIFIED),
+            Error::BadParameter { .. } => Some(StatusCode::BAD_REQUEST),
+            Error::NotFound { .. } => Some(StatusCode::NOT_FOUND),
+            Error::Conflict { .. } => Some(StatusCode::CONFLICT),
+            Error::ServiceUnavailable { .. } => Some(StatusCode::SERVICE_UNAVAILABLE),
+            _ => None,
+        }
+    }
+
+    /// Returns the error message returned by the docker daemon if this error was caused by an
+    /// unsuccessful response.
+    pub fn message(&self) -> Option<&str> {
+        match self {
+            Error::Fault { message, .. }
+            | Error::NotModified { message, .. }
+            | Error::BadParameter { message, .. }
+            | Error::NotFound { message, .. }
+            | Error::Conflict { message, .. }
+            | Error::ServiceUnavailable { message, .. } => Some(message),
+            _ => None,
+        }
+    }
+
+    /// Returns the resource the failed request was about if it's known.
+    pub fn resource(&self) -> Option<&Resource> {
+        match self {
+            Error::NotModified { resource, .. }
+            | Error::BadParameter { resource, .. }
+            | Error::NotFound { resource, .. }
+            | Error::Conflict { resource, .. }
+            | Error::ServiceUnavailable { resource, .. } => resource.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Checks if the requested resource doesn't exist.
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, Error::NotFound { .. })
+    }
+
+    /// Checks if the request conflicts with the current state of the resource, for example when
+    /// creating a container with a name that is already in use.
+    pub fn is_conflict(&self) -> bool {
+        matches!(self, Error::Conflict { .. })
+    }
+
+    /// Checks if the request had no effect, for example when starting an already started
+    /// container.
+    pub fn is_not_modified(&self) -> bool {
+        matches!(self, Error::NotModified { .. })
+    }
+}
+
+fn resource_or_request(resource: &Option<Resource>) -> String {
+    match resource {
+        Some(resource) => resource.to_string(),
+        None => "request".into(),
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+/// Kinds of objects managed by the docker daemon.
+pub enum ResourceKind {
+    Container,
+    Exec,
+    Image,
+    Distribution,
+    Network,
+    Volume,
+    Swarm,
+    Node,
+    Service,
+    Task,
+    Secret,
+    Config,
+    Plugin,
+}
+
+impl ResourceKind {
+    fn from_path_segment(segment: &str) -> Option<Self> {
+        use ResourceKind::*;
+        Some(match segment {
+            "containers" => Container,
+            "exec" => Exec,
+            "images" => Image,
+            "distribution" => Distribution,
+            "networks" => Network,
+            "volumes" => Volume,
+            "swarm" => Swarm,
+            "nodes" => Node,
+            "services" => Service,
+            "tasks" => Task,
+            "secrets" => Secret,
+            "configs" => Config,
+            "plugins" => Plugin,
+            _ => return None,
+        })
+    }
+
+    /// Returns the lowercase name of this kind.
+    pub fn as_str(&self) -> &'static str {
+        use ResourceKind::*;
+        match self {
+            Container => "container",
+            Exec => "exec",
+            Image => "image",
+            Distribution => "distribution",
+            Network => "network",
+            Volume => "volume",
+            Swarm => "swarm",
+            Node => "node",
+            Service => "service",
+            Task => "task",
+            Secret => "secret",
+            Config => "config",
+            Plugin => "plugin",
+        }
+    }
+}
+
+impl fmt::Display for ResourceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// The object a failed request was about.
+pub struct Resource {
+    /// Kind of the object.
+    pub kind: ResourceKind,
+    /// Id or name of the object. Not set for requests against the whole collection like listing
+    /// or creating objects.
+    pub id: Option<String>,
+}
+
+/// Methods and single path segments of endpoints that operate on the whole collection instead of
+/// a single object, like `GET /containers/json`.
+const COLLECTION_ENDPOINTS: &[(&str, &str)] = &[
+    ("GET", "json"),
+    ("POST", "create"),
+    ("POST", "prune"),
+    ("GET", "search"),
+    ("POST", "load"),
+    ("GET", "get"),
+    ("POST", "build"),
+    ("POST", "pull"),
+    ("GET", "privileges"),
+];
+
+/// Methods and last path segments of per-object actions on images, plugins and distribution
+/// entries, whose names may contain slashes.
+const OBJECT_ACTIONS: &[(&str, &str)] = &[
+    ("GET", "json"),
+    ("GET", "history"),
+    ("GET", "get"),
+    ("POST", "tag"),
+    ("POST", "push"),
+    ("POST", "create"),
+    ("POST", "enable"),
+    ("POST", "disable"),
+    ("POST", "upgrade"),
+    ("POST", "set"),
+];
+
+impl Resource {
+    /// Determines the resource from a request with `method` to an endpoint like
+    /// `/v1.41/containers/{id}/start?t=10`.
+    pub(crate) fn from_endpoint(method: &Method, endpoint: &str) -> Option<Self> {
+        let path = endpoint.split('?').next().unwrap_or_default();
+        let mut segments = strip_version(path).split('/').filter(|s| !s.is_empty());
+
+        let kind = ResourceKind::from_path_segment(segments.next()?)?;
+        let rest: Vec<_> = segments.collect();
+        let is_one_of = |endpoints: &[(&str, &str)], segment: &str| {
+            endpoints.contains(&(method.as_str(), segment))
+        };
+        let id = match (kind, rest.as_slice()) {
+            (ResourceKind::Swarm, _) | (_, []) => None,
+            (_, [segment]) if is_one_of(COLLECTION_ENDPOINTS, segment) => None,
+            // image and plugin names may contain slashes, the whole remainder is the name unless
+            // it ends with a known action
+            (
+                ResourceKind::Image | ResourceKind::Plugin | ResourceKind::Distribution,
+                [.., last],
+            ) if rest.len() > 1 && is_one_of(OBJECT_ACTIONS, last) => {
+                Some(rest[..rest.len() - 1].join("/"))
+            }
+            (ResourceKind::Image | ResourceKind::Plugin | ResourceKind::Distribution, _) => {
+                Some(rest.join("/"))
+            }
+            (_, [id, ..]) => Some(id.to_string()),
+        };
+
+        Some(Resource { kind, id })
+    }
+}
+
+impl fmt::Display for Resource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.id {
+            Some(id) => write!(f, "{} `{}`", self.kind, id),
+            None => write!(f, "{}", self.kind),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn determines_resource_from_endpoint() {
+        let resource = |ep| Resource::from_endpoint(&Method::GET, ep);
+        let with_id = |kind, id: &str| {
+            Some(Resource {
+                kind,
+                id: Some(id.to_string()),
+            })
+        };
+
+        assert_eq!(
+            resource("/v1.41/containers/abc/start?t=10"),
+            with_id(ResourceKind::Container, "abc")
+        );
+        assert_eq!(
+            resource("/containers/abc"),
+            with_id(ResourceKind::Container, "abc")
+        );
+        assert_eq!(
+            Resource::from_endpoint(&Method::POST, "/v1.41/containers/create?name=abc"),
+            Some(Resource {
+                kind: ResourceKind::Container,
+                id: None
+            })
+        );
+        assert_eq!(
+            resource("/images/library/ubuntu:latest/json"),
+            with_id(ResourceKind::Image, "library/ubuntu:latest")
+        );
+        assert_eq!(
+            resource("/images/ubuntu"),
+            with_id(ResourceKind::Image, "ubuntu")
+        );
+        assert_eq!(
+            resource("/v1.41/swarm/unlockkey"),
+            Some(Resource {
+                kind: ResourceKind::Swarm,
+                id: None
+            })
+        );
+        assert_eq!(resource("/_ping"), None);
+    }
+
+    #[test]
+    fn keeps_names_that_look_like_actions() {
+        let resource = |method, ep| Resource::from_endpoint(&method, ep);
+        let with_id = |kind, id: &str| {
+            Some(Resource {
+                kind,
+                id: Some(id.to_string()),
+            })
+        };
+
+        assert_eq!(
+            resource(Method::DELETE, "/v1.41/images/library/ubuntu"),
+            with_id(ResourceKind::Image, "library/ubuntu")
+        );
+        assert_eq!(
+            resource(Method::POST, "/images/library/ubuntu/tag?repo=web"),
+            with_id(ResourceKind::Image, "library/ubuntu")
+        );
+        assert_eq!(
+            resource(Method::GET, "/distribution/docker.io/library/ubuntu/json"),
+            with_id(ResourceKind::Distribution, "docker.io/library/ubuntu")
+        );
+        assert_eq!(
+            resource(Method::DELETE, "/containers/update"),
+            with_id(ResourceKind::Container, "update")
+        );
+        assert_eq!(
+            resource(Method::POST, "/containers/update/update"),
+            with_id(ResourceKind::Container, "update")
+        );
+        assert_eq!(
+            resource(Method::DELETE, "/containers/json"),
+            with_id(ResourceKind::Container, "json")
+        );
+        assert_eq!(
+            resource(Method::GET, "/networks/create"),
+            with_id(ResourceKind::Network, "create")
+        );
+        assert_eq!(
+            resource(Method::POST, "/networks/create"),
+            Some(Resource {
+                kind: ResourceKind::Network,
+                id: None
+            })
+        );
+    }
+
+    #[test]
+    fn maps_status_codes_to_errors() {
+        let err = Error::from_status(
+            StatusCode::NOT_MODIFIED,
+            "container already started".into(),
+            &Method::POST,
+            "/v1.41/containers/abc/start",
+        );
+        assert!(err.is_not_modified());
+        assert_eq!(err.resource(), with_container("abc").as_ref(),);
+        assert_eq!(err.status_code(), Some(StatusCode::NOT_MODIFIED));
+
+        let err = Error::from_status(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "oops".into(),
+            &Method::POST,
+            "/containers/abc/start",
+        );
+        assert!(matches!(err, Error::Fault { .. }));
+        assert_eq!(err.message(), Some("oops"));
+
+        let err = Error::from_status(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "This node is not a swarm manager.".into(),
+            &Method::GET,
+            "/v1.41/services/web",
+        );
+        assert_eq!(
+            err.to_string(),
+            "service unavailable for service `web` - This node is not a swarm manager."
+        );
+
+        fn with_container(id: &str) -> Option<Resource> {
+            Some(Resource {
+                kind: ResourceKind::Container,
+                id: Some(id.into()),
+            })
+        }
+    }
 }
@@ -34,9 +34,16 @@ mod util;
 mod version;
 
 pub mod api;
+#[cfg(feature = "blocking")]
+#[cfg_attr(docsrs, doc(cfg(feature = "blocking")))]
+pub mod blocking;
 pub mod conn;
+pub mod context;
 pub mod docker;
 pub mod errors;
+#[cfg(feature = "testing")]
+#[cfg_attr(docsrs, doc(cfg(feature = "testing")))]
+pub mod testing;
 
 pub use crate::{
     api::{
@@ -46,8 +53,9 @@ pub use crate::{
         network::{self, Network, Networks},
         volume::{self, Volume, Volumes},
     },
-    docker::Docker,
-    errors::{Error, Result},
+    context::Context,
+    docker::{Docker, DockerBuilder},
+    errors::{Error, Resource, ResourceKind, Result},
     version::ApiVersion,
 };
 
@@ -0,0 +1,477 @@
+//! A fake docker engine serving the Docker API from memory, for integration tests of code built
+//! on this crate that need a daemon but shouldn't depend on a real one.
+//!
+//! [`FakeEngine`](FakeEngine) listens on a TCP port or a Unix socket and keeps containers,
+//! images, networks, volumes, exec instances and events in memory. Containers don't run real
+//! programs, a few common commands like `echo`, `cat`, `sleep` and `sh -c` are emulated so that
+//! starting, waiting for, attaching to and reading the logs of containers behaves like it would
+//! against a docker daemon. Images are never downloaded, pulling one just records it.
+//!
+//! # Example
+//!
+//! ```
+//! use docker_api::{api::LogsOpts, container::ContainerCreateOpts, testing::FakeEngine};
+//! use futures_util::TryStreamExt;
+//!
+//! # async fn run() -> docker_api::Result<()> {
+//! let engine = FakeEngine::tcp().await?;
+//! engine.add_image("ubuntu:latest");
+//! let docker = engine.docker();
+//!
+//! let container = docker
+//!     .containers()
+//!     .create(
+//!         &ContainerCreateOpts::builder("ubuntu")
+//!             .cmd(vec!["echo", "hello"])
+//!             .build(),
+//!     )
+//!     .await?;
+//! container.start().await?;
+//! assert_eq!(container.wait().await?.status_code, 0);
+//!
+//! let logs: Vec<_> = container
+//!     .logs(&LogsOpts::builder().stdout(true).build())
+//!     .try_collect()
+//!     .await?;
+//! assert!(!logs.is_empty());
+//! # Ok(())
+//! # }
+//! # tokio::runtime::Runtime::new().unwrap().block_on(run()).unwrap();
+//! ```
+
+mod process;
+mod routes;
+mod state;
+
+use crate::{conn::client::Connection, Docker, Result};
+use state::Engine;
+
+use hyper::{server::conn::Http, service::service_fn};
+use tokio::{net::TcpListener, task::JoinHandle};
+
+#[cfg(unix)]
+use std::path::{Path, PathBuf};
+use std::{convert::Infallible, io, sync::Arc};
+
+/// A docker engine emulated in memory, listening for requests until it's dropped.
+///
+/// Each engine has its own state, so tests using separate engines don't interfere with each
+/// other.
+pub struct FakeEngine {
+    engine: Arc<Engine>,
+    host: String,
+    server: JoinHandle<()>,
+    #[cfg(unix)]
+    socket: Option<PathBuf>,
+}
+
+enum Listener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(tokio::net::UnixListener),
+}
+
+impl Listener {
+    async fn accept(&self) -> io::Result<Box<dyn Connection>> {
+        match self {
+            Listener::Tcp(listener) => {
+                let (stream, _) = listener.accept().await?;
+                Ok(Box::new(stream))
+            }
+            #[cfg(unix)]
+            Listener::Unix(listener) => {
+                let (stream, _) = listener.accept().await?;
+                Ok(Box::new(stream))
+            }
+        }
+    }
+}
+
+impl FakeEngine {
+    /// Starts an engine listening on a random port of `127.0.0.1`.
+    pub async fn tcp() -> Result<FakeEngine> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let host = format!("tcp://{}", listener.local_addr()?);
+        Ok(Self::serve(
+            Listener::Tcp(listener),
+            host,
+            #[cfg(unix)]
+            None,
+        ))
+    }
+
+    #[cfg(unix)]
+    #[cfg_attr(docsrs, doc(cfg(unix)))]
+    /// Starts an engine listening on a Unix socket created at `path`. The socket is removed when
+    /// the engine is dropped.
+    pub async fn unix<P>(path: P) -> Result<FakeEngine>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref().to_path_buf();
+        let listener = tokio::net::UnixListener::bind(&path)?;
+        let host = format!("unix://{}", path.display());
+        Ok(Self::serve(Listener::Unix(listener), host, Some(path)))
+    }
+
+    fn serve(listener: Listener, host: String, #[cfg(unix)] socket: Option<PathBuf>) -> FakeEngine {
+        let engine = Arc::new(Engine::new());
+        let server = {
+            let engine = engine.clone();
+            tokio::spawn(async move {
+                while let Ok(connection) = listener.accept().await {
+                    let engine = engine.clone();
+                    let service = service_fn(move |req| {
+                        let engine = engine.clone();
+                        async move { Ok::<_, Infallible>(routes::handle(engine, req).await) }
+                    });
+                    tokio::spawn(async move {
+                        if let Err(e) = Http::new()
+                            .http1_only(true)
+                            .serve_connection(connection, service)
+                            .with_upgrades()
+                            .await
+                        {
+                            log::trace!("fake engine connection failed: {}", e);
+                        }
+                    });
+                }
+            })
+        };
+
+        FakeEngine {
+            engine,
+            host,
+            server,
+            #[cfg(unix)]
+            socket,
+        }
+    }
+
+    /// The address of this engine, like `tcp://127.0.0.1:49153` or `unix:///tmp/docker.sock`.
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    /// Returns a [`Docker`](Docker) instance connected to this engine.
+    pub fn docker(&self) -> Docker {
+        Docker::new(&self.host).expect("the host of a fake engine is a valid docker host")
+    }
+
+    /// Adds an image to the engine as if it was pulled, returning its ID. Containers can only be
+    /// created from images known to the engine.
+    pub fn add_image(&self, reference: impl AsRef<str>) -> String {
+        let id = self.engine.lock().add_image(reference.as_ref());
+        self.engine.notify();
+        id
+    }
+}
+
+impl Drop for FakeEngine {
+    fn drop(&mut self) {
+        self.server.abort();
+        #[cfg(unix)]
+        if let Some(socket) = &self.socket {
+            let _ = std::fs::remove_file(socket);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        api::{EventsOpts, LogStream, LogsOpts},
+        conn::TtyChunk,
+        container::{AttachOpts, ContainerCreateOpts, ContainerResizeOpts, ContainerUpdateOpts},
+        network::NetworkCreateOpts,
+        volume::VolumeCreateOpts,
+        Error, ExecContainerOpts,
+    };
+
+    use futures_util::{AsyncWriteExt, StreamExt, TryStreamExt};
+
+    async fn collect(stream: impl futures_util::Stream<Item = Result<TtyChunk>>) -> String {
+        let chunks: Vec<_> = stream.try_collect().await.unwrap();
+        chunks
+            .iter()
+            .map(|c| String::from_utf8_lossy(c).into_owned())
+            .collect()
+    }
+
+    async fn run_container(docker: &Docker) {
+        #[cfg(feature = "chrono")]
+        let start = chrono::Utc::now();
+
+        let container = docker
+            .containers()
+            .create(
+                &ContainerCreateOpts::builder("ubuntu")
+                    .name("greeter")
+                    .cmd(vec!["sh", "-c", "echo hello; echo oops >&2; exit 3"])
+                    .build(),
+            )
+            .await
+            .unwrap();
+        assert!(matches!(
+            docker
+                .containers()
+                .create(
+                    &ContainerCreateOpts::builder("ubuntu")
+                        .name("greeter")
+                        .build()
+                )
+                .await,
+            Err(Error::Conflict { .. })
+        ));
+        container.start().await.unwrap();
+        assert_eq!(container.wait().await.unwrap().status_code, 3);
+
+        let logs: Vec<_> = container
+            .logs(&LogsOpts::builder().stdout(true).build())
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(
+            logs.concat(),
+            TtyChunk::StdOut(b"hello\n".to_vec()).encode()
+        );
+
+        let lines: Vec<_> = container
+            .log_lines(
+                &LogsOpts::builder()
+                    .stdout(true)
+                    .timestamps(true)
+                    .details(true)
+                    .build(),
+            )
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].stream, LogStream::StdOut);
+        assert_eq!(lines[0].message, b"hello");
+        assert!(lines[0].timestamp.is_some());
+        assert_eq!(lines[0].details, Some(Default::default()));
+
+        let details = container.inspect().await.unwrap();
+        assert_eq!(details.name, "/greeter");
+        assert_eq!(details.state.exit_code, 3);
+
+        container.delete().await.unwrap();
+        assert!(matches!(
+            container.inspect().await,
+            Err(Error::NotFound { .. })
+        ));
+
+        #[cfg(feature = "chrono")]
+        {
+            let until = chrono::Utc::now() + chrono::Duration::seconds(1);
+            let actions: Vec<_> = docker
+                .events(&EventsOpts::builder().since(&start).until(&until).build())
+                .map_ok(|event| event.action)
+                .try_collect()
+                .await
+                .unwrap();
+            assert_eq!(actions, ["create", "start", "die", "destroy"]);
+        }
+    }
+
+    async fn exec_and_attach(docker: &Docker) {
+        let container = docker
+            .containers()
+            .create(
+                &ContainerCreateOpts::builder("ubuntu")
+                    .cmd(vec!["cat"])
+                    .attach_stdin(true)
+                    .build(),
+            )
+            .await
+            .unwrap();
+        container.start().await.unwrap();
+
+        let warnings = container
+            .update(
+                &ContainerUpdateOpts::builder()
+                    .memory(64 << 20)
+                    .restart_policy("on-failure", 3)
+                    .build(),
+            )
+            .await
+            .unwrap();
+        assert!(warnings.is_empty());
+        let host_config = container.inspect().await.unwrap().host_config;
+        assert_eq!(host_config.memory, Some(64 << 20));
+        assert_eq!(host_config.restart_policy.maximum_retry_count, 3);
+
+        container
+            .resize(&ContainerResizeOpts::builder().height(40).width(120).build())
+            .await
+            .unwrap();
+        let host_config = container.inspect().await.unwrap().host_config;
+        assert_eq!(host_config.console_size, Some(vec![40, 120]));
+
+        let output = collect(
+            container.exec(
+                &ExecContainerOpts::builder()
+                    .cmd(vec!["printenv", "GREETING"])
+                    .env(vec!["GREETING=hi"])
+                    .attach_stdout(true)
+                    .build(),
+            ),
+        )
+        .await;
+        assert_eq!(output, "hi\n");
+
+        let (mut reader, mut writer) = container.attach().await.unwrap().split();
+        writer.write_all(b"ping\n").await.unwrap();
+        let chunk = reader.next().await.unwrap().unwrap();
+        assert!(matches!(chunk, TtyChunk::StdOut(data) if data == b"ping\n"));
+
+        #[cfg(feature = "websocket")]
+        {
+            use futures_util::SinkExt;
+
+            let websocket = container
+                .attach_websocket(
+                    &AttachOpts::builder()
+                        .stream(true)
+                        .logs(true)
+                        .stdin(true)
+                        .stdout(true)
+                        .build(),
+                )
+                .await
+                .unwrap();
+            let (mut sink, mut stream) = websocket.split();
+            assert_eq!(stream.next().await.unwrap().unwrap(), b"ping\n");
+            sink.send(b"pong\n".to_vec()).await.unwrap();
+            assert_eq!(stream.next().await.unwrap().unwrap(), b"pong\n");
+        }
+
+        container.stop(None).await.unwrap();
+        assert_eq!(container.wait().await.unwrap().status_code, 143);
+        container.delete().await.unwrap();
+    }
+
+    async fn detach(docker: &Docker) {
+        let container = docker
+            .containers()
+            .create(
+                &ContainerCreateOpts::builder("ubuntu")
+                    .cmd(vec!["cat"])
+                    .tty(true)
+                    .open_stdin(true)
+                    .attach_stdin(true)
+                    .build(),
+            )
+            .await
+            .unwrap();
+        container.start().await.unwrap();
+
+        let mut session = container
+            .attach_with_opts(
+                &AttachOpts::builder()
+                    .stream(true)
+                    .stdin(true)
+                    .stdout(true)
+                    .detach_keys("ctrl-x,x")
+                    .build(),
+            )
+            .await
+            .unwrap();
+        session.write_all(&[0x18, b'x']).await.unwrap();
+        assert!(session.next().await.is_none());
+        assert!(session.detached());
+        assert!(container.inspect().await.unwrap().state.running);
+
+        container.stop(None).await.unwrap();
+        container.delete().await.unwrap();
+    }
+
+    async fn networks_and_volumes(docker: &Docker) {
+        let network = docker
+            .networks()
+            .create(&NetworkCreateOpts::builder("backend").build())
+            .await
+            .unwrap();
+        assert_eq!(
+            network.inspect().await.unwrap().name.as_deref(),
+            Some("backend")
+        );
+        network.delete().await.unwrap();
+
+        let volume = docker
+            .volumes()
+            .create(&VolumeCreateOpts::builder().name("data").build())
+            .await
+            .unwrap();
+        assert_eq!(volume.name(), "data");
+        let volumes = docker.volumes().list(&Default::default()).await.unwrap();
+        assert_eq!(volumes.volumes.len(), 1);
+        docker.volumes().get("data").delete().await.unwrap();
+    }
+
+    /// Starts an engine over TCP with the `ubuntu` image.
+    async fn engine() -> FakeEngine {
+        let engine = FakeEngine::tcp().await.unwrap();
+        engine.add_image("ubuntu");
+        engine
+    }
+
+    #[tokio::test]
+    async fn serves_the_api_over_tcp() {
+        let engine = FakeEngine::tcp().await.unwrap();
+        let docker = engine.docker();
+        docker.ping().await.unwrap();
+        assert_eq!(
+            docker.version().await.unwrap().version,
+            state::ENGINE_VERSION
+        );
+        docker.info().await.unwrap();
+
+        assert!(matches!(
+            docker
+                .containers()
+                .create(&ContainerCreateOpts::builder("ubuntu").build())
+                .await,
+            Err(Error::NotFound { .. })
+        ));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn serves_the_api_over_a_unix_socket() {
+        let path = std::env::temp_dir().join(format!("fake-docker-{}.sock", std::process::id()));
+        let engine = FakeEngine::unix(&path).await.unwrap();
+        assert_eq!(engine.host(), format!("unix://{}", path.display()));
+        engine.add_image("ubuntu");
+        let docker = engine.docker();
+        run_container(&docker).await;
+        exec_and_attach(&docker).await;
+        detach(&docker).await;
+        networks_and_volumes(&docker).await;
+        drop(engine);
+        assert!(!path.exists());
+    }
+
+    #[tokio::test]
+    async fn runs_containers() {
+        run_container(&engine().await.docker()).await;
+    }
+
+    #[tokio::test]
+    async fn execs_and_attaches() {
+        exec_and_attach(&engine().await.docker()).await;
+    }
+
+    #[tokio::test]
+    async fn detaches_from_containers() {
+        detach(&engine().await.docker()).await;
+    }
+
+    #[tokio::test]
+    async fn manages_networks_and_volumes() {
+        networks_and_volumes(&engine().await.docker()).await;
+    }
+}
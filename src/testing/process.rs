@@ -0,0 +1,294 @@
+//! Emulation of the processes run in containers of the fake engine.
+//!
+//! Containers don't run real programs, instead a handful of common commands is emulated:
+//! `echo`, `true`, `false`, `sleep`, `cat`, `printenv`, `env`, `hostname`, `pwd`, `whoami` and
+//! `sh`/`bash`. The shell runs scripts given with `-c` or read line by line from stdin, supports
+//! sequences separated by `;`, `exit` and redirecting output to stderr with `>&2`.
+
+use crate::{
+    conn::TtyChunk,
+    testing::state::{Engine, LogEntry},
+};
+
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+
+use std::{sync::Arc, time::Duration, time::SystemTime};
+
+const PROGRAMS: &[&str] = &[
+    "bash", "cat", "echo", "env", "false", "hostname", "printenv", "pwd", "sh", "sleep", "true",
+    "whoami",
+];
+
+/// A program with the environment it runs in.
+pub(crate) struct Process {
+    pub argv: Vec<String>,
+    pub env: Vec<String>,
+    pub hostname: String,
+    pub user: String,
+    pub working_dir: String,
+}
+
+/// Where the output of a process goes.
+pub(crate) enum Output {
+    /// The log of a container, only kept while the container is still in the same run.
+    Container {
+        engine: Arc<Engine>,
+        id: String,
+        run: u64,
+    },
+    Channel(UnboundedSender<TtyChunk>),
+}
+
+impl Output {
+    fn write(&self, chunk: TtyChunk) {
+        match self {
+            Output::Container { engine, id, run } => {
+                {
+                    let mut state = engine.lock();
+                    match state.containers.iter_mut().find(|c| &c.id == id) {
+                        Some(container) if container.run == *run => container.log.push(LogEntry {
+                            chunk,
+                            time: SystemTime::now(),
+                        }),
+                        _ => return,
+                    }
+                }
+                engine.notify();
+            }
+            Output::Channel(tx) => {
+                let _ = tx.send(chunk);
+            }
+        }
+    }
+
+    fn stdout(&self, data: impl Into<Vec<u8>>) {
+        self.write(TtyChunk::StdOut(data.into()))
+    }
+
+    fn stderr(&self, data: impl Into<Vec<u8>>) {
+        self.write(TtyChunk::StdErr(data.into()))
+    }
+}
+
+/// Checks if `program` can be run, the way a missing executable is detected before a container
+/// starts.
+pub(crate) fn exists(program: &str) -> bool {
+    PROGRAMS.contains(&basename(program))
+}
+
+fn basename(program: &str) -> &str {
+    program.rsplit('/').next().unwrap_or(program)
+}
+
+/// Runs `process` to completion and returns its exit code.
+pub(crate) async fn run(
+    process: Process,
+    mut stdin: Option<UnboundedReceiver<Vec<u8>>>,
+    out: Output,
+) -> i64 {
+    let argv = process.argv.clone();
+    match (
+        argv.first().map(|p| basename(p)),
+        argv.get(1).map(String::as_str),
+    ) {
+        (Some("sh"), Some("-c")) | (Some("bash"), Some("-c")) => {
+            let source = argv.get(2).map(String::as_str).unwrap_or_default();
+            match script(source, &process, &out).await {
+                Flow::Continue(code) | Flow::Exit(code) => code,
+            }
+        }
+        (Some("sh"), None) | (Some("bash"), None) => {
+            // read commands from stdin until it's closed or the script exits
+            let mut buffer = Vec::new();
+            let mut code = 0;
+            while let Some(chunk) = recv(&mut stdin).await {
+                buffer.extend(chunk);
+                while let Some(end) = buffer.iter().position(|b| *b == b'\n') {
+                    let line: Vec<u8> = buffer.drain(..=end).collect();
+                    let line = String::from_utf8_lossy(&line);
+                    match script(&line, &process, &out).await {
+                        Flow::Continue(c) => code = c,
+                        Flow::Exit(c) => return c,
+                    }
+                }
+            }
+            code
+        }
+        _ => command(&argv, &process, &mut stdin, &out, false).await,
+    }
+}
+
+async fn recv(stdin: &mut Option<UnboundedReceiver<Vec<u8>>>) -> Option<Vec<u8>> {
+    match stdin {
+        Some(stdin) => stdin.recv().await,
+        None => None,
+    }
+}
+
+enum Flow {
+    Continue(i64),
+    Exit(i64),
+}
+
+/// Runs a shell script, returns the exit code of the last command or the one given to `exit`.
+async fn script(script: &str, process: &Process, out: &Output) -> Flow {
+    let mut code = 0;
+    for line in script.split([';', '\n']) {
+        let mut words = split_words(line);
+        if words.is_empty() {
+            continue;
+        }
+        let to_stderr = words.iter().any(|w| w == ">&2" || w == "1>&2");
+        words.retain(|w| w != ">&2" && w != "1>&2");
+
+        if words[0] == "exit" {
+            let code = words.get(1).and_then(|c| c.parse().ok()).unwrap_or(code);
+            return Flow::Exit(code);
+        }
+        if !exists(&words[0]) {
+            out.stderr(format!("sh: 1: {}: not found\n", words[0]));
+            code = 127;
+            continue;
+        }
+        code = command(&words, process, &mut None, out, to_stderr).await;
+    }
+    Flow::Continue(code)
+}
+
+/// Splits a shell command into words, handling single and double quotes.
+fn split_words(line: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut quote = None;
+    for c in line.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => word.push(c),
+            (None, '\'') | (None, '"') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            (None, c) => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+    words
+}
+
+/// Runs a single program other than the shell.
+async fn command(
+    argv: &[String],
+    process: &Process,
+    stdin: &mut Option<UnboundedReceiver<Vec<u8>>>,
+    out: &Output,
+    to_stderr: bool,
+) -> i64 {
+    let print = |line: String| {
+        if to_stderr {
+            out.stderr(line)
+        } else {
+            out.stdout(line)
+        }
+    };
+    let args = &argv[1.min(argv.len())..];
+
+    match argv.first().map(|p| basename(p)) {
+        Some("echo") => {
+            match args.first().map(String::as_str) {
+                Some("-n") => print(args[1..].join(" ")),
+                _ => print(format!("{}\n", args.join(" "))),
+            }
+            0
+        }
+        Some("true") => 0,
+        Some("false") => 1,
+        Some("sleep") => {
+            match args.first().map(String::as_str) {
+                Some("infinity") => futures_util::future::pending::<()>().await,
+                Some(secs) => match secs.trim_end_matches('s').parse::<f64>() {
+                    Ok(secs) => tokio::time::sleep(Duration::from_secs_f64(secs)).await,
+                    Err(_) => {
+                        out.stderr(format!("sleep: invalid time interval '{}'\n", secs));
+                        return 1;
+                    }
+                },
+                None => {
+                    out.stderr("sleep: missing operand\n");
+                    return 1;
+                }
+            }
+            0
+        }
+        Some("cat") => {
+            while let Some(chunk) = recv(stdin).await {
+                out.stdout(chunk);
+            }
+            0
+        }
+        Some("printenv") | Some("env") if args.is_empty() => {
+            for var in &process.env {
+                print(format!("{}\n", var));
+            }
+            print(format!("HOSTNAME={}\n", process.hostname));
+            0
+        }
+        Some("printenv") => {
+            let mut code = 0;
+            for name in args {
+                let value = process
+                    .env
+                    .iter()
+                    .filter_map(|var| var.split_once('='))
+                    .find(|(key, _)| key == name)
+                    .map(|(_, value)| value.to_string())
+                    .or_else(|| (name == "HOSTNAME").then(|| process.hostname.clone()));
+                match value {
+                    Some(value) => print(format!("{}\n", value)),
+                    None => code = 1,
+                }
+            }
+            code
+        }
+        Some("hostname") => {
+            print(format!("{}\n", process.hostname));
+            0
+        }
+        Some("pwd") => {
+            let dir = match process.working_dir.as_str() {
+                "" => "/",
+                dir => dir,
+            };
+            print(format!("{}\n", dir));
+            0
+        }
+        Some("whoami") => {
+            let user = match process.user.as_str() {
+                "" => "root",
+                user => user,
+            };
+            print(format!("{}\n", user));
+            0
+        }
+        Some("sh") | Some("bash") => {
+            out.stderr("sh: nested shells are not supported\n");
+            2
+        }
+        Some(program) => {
+            out.stderr(format!("{}: not found\n", program));
+            127
+        }
+        None => 0,
+    }
+}
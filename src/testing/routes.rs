@@ -0,0 +1,1896 @@
+//! HTTP endpoints of the fake engine.
+
+use crate::{
+    conn::{tty::DetachKeys, TtyChunk},
+    testing::{
+        process::{self, Output, Process},
+        state::{
+            normalize_reference, random_id, rfc3339, ApiError, ApiResult, Container,
+            ContainerConfig, Engine, Exec, MountPoint, Network, State, Status, Volume,
+            ENGINE_VERSION,
+        },
+    },
+    util::url::strip_version,
+    LATEST_API_VERSION,
+};
+
+use hyper::{
+    body::{Bytes, Sender},
+    header::{self, HeaderValue},
+    Body, Request, Response, StatusCode,
+};
+use serde_json::{json, Value};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+type Query = HashMap<String, String>;
+type Filters = HashMap<String, Vec<String>>;
+
+/// Answers a request to the fake engine.
+pub(crate) async fn handle(engine: Arc<Engine>, mut req: Request<Body>) -> Response<Body> {
+    let path = strip_version(req.uri().path()).to_string();
+    let method = req.method().as_str().to_string();
+    let query: Query = req
+        .uri()
+        .query()
+        .map(|q| {
+            url::form_urlencoded::parse(q.as_bytes())
+                .into_owned()
+                .collect()
+        })
+        .unwrap_or_default();
+    let body = match hyper::body::to_bytes(req.body_mut()).await {
+        Ok(body) => body,
+        Err(e) => return error(ApiError::bad_request(e.to_string())),
+    };
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+
+    let result = match (method.as_str(), segments.as_slice()) {
+        ("GET", ["_ping"]) | ("HEAD", ["_ping"]) => Ok(ping()),
+        ("GET", ["version"]) => Ok(ok(version())),
+        ("GET", ["info"]) => Ok(ok(info(&engine.lock()))),
+        ("GET", ["events"]) => events(&engine, &query),
+
+        ("GET", ["containers", "json"]) => list_containers(&engine.lock(), &query),
+        ("POST", ["containers", "create"]) => create_container(&engine, &query, &body),
+        ("GET", ["containers", id, "json"]) => {
+            let mut state = engine.lock();
+            let networks = std::mem::take(&mut state.networks);
+            let details = state.container(id).map(|c| c.details(&networks));
+            state.networks = networks;
+            details.map(ok)
+        }
+        ("GET", ["containers", id, "top"]) => top(&mut engine.lock(), id),
+        ("GET", ["containers", id, "logs"]) => logs(&engine, id, &query),
+        ("POST", ["containers", id, "start"]) => start(&engine, id),
+        ("POST", ["containers", id, "stop"]) => stop(&engine, id),
+        ("POST", ["containers", id, "restart"]) => restart(&engine, id),
+        ("POST", ["containers", id, "kill"]) => kill(&engine, id, &query),
+        ("POST", ["containers", id, "pause"]) => pause(&engine, id, true),
+        ("POST", ["containers", id, "unpause"]) => pause(&engine, id, false),
+        ("POST", ["containers", id, "rename"]) => rename(&engine, id, &query),
+        ("POST", ["containers", id, "resize"]) => resize(&engine, id, &query),
+        ("POST", ["containers", id, "update"]) => update_container(&engine, id, &body),
+        ("POST", ["containers", id, "wait"]) => wait(&engine, id, &query).await,
+        ("POST", ["containers", id, "attach"]) => attach(&engine, id, &query, &mut req),
+        #[cfg(feature = "websocket")]
+        ("GET", ["containers", id, "attach", "ws"]) => {
+            attach_websocket(&engine, id, &query, &mut req)
+        }
+        ("POST", ["containers", id, "exec"]) => create_exec(&engine, id, &body),
+        ("DELETE", ["containers", id]) => delete_container(&engine, id, &query),
+
+        ("POST", ["exec", id, "start"]) => start_exec(&engine, id, &body),
+        ("GET", ["exec", id, "json"]) => engine.lock().exec(id).map(|e| ok(e.details())),
+
+        ("GET", ["images", "json"]) => Ok(ok(list_images(&engine.lock()))),
+        ("POST", ["images", "create"]) => pull(&engine, &query),
+        ("GET", ["images", name @ .., "json"]) => engine
+            .lock()
+            .image(&name.join("/"))
+            .map(|i| ok(i.details())),
+        ("POST", ["images", name @ .., "tag"]) => tag(&engine, &name.join("/"), &query),
+        ("DELETE", ["images", name @ ..]) => delete_image(&engine, &name.join("/"), &query),
+
+        ("GET", ["networks"]) => {
+            let state = engine.lock();
+            let networks: Vec<_> = state
+                .networks
+                .iter()
+                .map(|n| n.details(&state.containers))
+                .collect();
+            Ok(ok(json!(networks)))
+        }
+        ("POST", ["networks", "create"]) => create_network(&engine, &body),
+        ("GET", ["networks", id]) => {
+            let mut state = engine.lock();
+            let containers = std::mem::take(&mut state.containers);
+            let details = state.network(id).map(|n| n.details(&containers));
+            state.containers = containers;
+            details.map(ok)
+        }
+        ("DELETE", ["networks", id]) => delete_network(&engine, id),
+        ("POST", ["networks", id, "connect"]) => connect(&engine, id, &body, true),
+        ("POST", ["networks", id, "disconnect"]) => connect(&engine, id, &body, false),
+
+        ("GET", ["volumes"]) => {
+            let state = engine.lock();
+            let volumes: Vec<_> = state.volumes.iter().map(Volume::details).collect();
+            Ok(ok(json!({"Volumes": volumes, "Warnings": null})))
+        }
+        ("POST", ["volumes", "create"]) => create_volume(&engine, &body),
+        ("GET", ["volumes", name]) => engine.lock().volume(name).map(|v| ok(v.details())),
+        ("DELETE", ["volumes", name]) => delete_volume(&engine, name),
+
+        _ => Err(ApiError::not_found("page not found")),
+    };
+
+    result.unwrap_or_else(error)
+}
+
+//####################################################################################################
+//
+// Responses
+//
+//####################################################################################################
+
+fn json_response(status: StatusCode, body: &Value) -> Response<Body> {
+    let mut response = Response::new(Body::from(body.to_string()));
+    *response.status_mut() = status;
+    response.headers_mut().insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/json"),
+    );
+    response
+}
+
+fn ok(body: Value) -> Response<Body> {
+    json_response(StatusCode::OK, &body)
+}
+
+fn created(body: Value) -> Response<Body> {
+    json_response(StatusCode::CREATED, &body)
+}
+
+fn status(status: StatusCode) -> Response<Body> {
+    let mut response = Response::new(Body::empty());
+    *response.status_mut() = status;
+    response
+}
+
+fn error(error: ApiError) -> Response<Body> {
+    json_response(error.status, &json!({ "message": error.message }))
+}
+
+/// A response streaming its body from a spawned task.
+fn streaming(content_type: &'static str) -> (Sender, Response<Body>) {
+    let (sender, body) = Body::channel();
+    let mut response = Response::new(body);
+    response
+        .headers_mut()
+        .insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+    (sender, response)
+}
+
+fn flag(query: &Query, key: &str) -> bool {
+    matches!(
+        query.get(key).map(String::as_str),
+        Some("1") | Some("true") | Some("True")
+    )
+}
+
+/// Parses a timestamp like `1622548800` or `1622548800.000000001` given as query parameter.
+fn timestamp(query: &Query, key: &str) -> ApiResult<Option<SystemTime>> {
+    query
+        .get(key)
+        .filter(|t| !t.is_empty())
+        .map(|t| {
+            t.parse::<f64>()
+                .map(|secs| UNIX_EPOCH + Duration::from_secs_f64(secs.max(0.)))
+                .map_err(|_| ApiError::bad_request(format!("invalid value for {}: {}", key, t)))
+        })
+        .transpose()
+}
+
+/// Parses the `filters` query parameter, accepting both `{"key": ["value"]}` and the older
+/// `{"key": {"value": true}}` form.
+fn filters(query: &Query) -> ApiResult<Filters> {
+    let filters = match query.get("filters").filter(|f| !f.is_empty()) {
+        Some(filters) => filters,
+        None => return Ok(Filters::new()),
+    };
+    let value: HashMap<String, Value> = serde_json::from_str(filters)
+        .map_err(|e| ApiError::bad_request(format!("invalid filter: {}", e)))?;
+    Ok(value
+        .into_iter()
+        .map(|(key, values)| {
+            let values = match values {
+                Value::Array(values) => values
+                    .into_iter()
+                    .filter_map(|v| v.as_str().map(str::to_string))
+                    .collect(),
+                Value::Object(values) => values.into_iter().map(|(v, _)| v).collect(),
+                _ => vec![],
+            };
+            (key, values)
+        })
+        .collect())
+}
+
+/// Checks if `value` passes the filter `key`, absent filters match everything.
+fn matches(filters: &Filters, key: &str, value: impl Fn(&str) -> bool) -> bool {
+    filters
+        .get(key)
+        .map(|values| values.iter().any(|v| value(v)))
+        .unwrap_or(true)
+}
+
+fn body_json(body: &Bytes) -> ApiResult<Value> {
+    if body.is_empty() {
+        return Ok(Value::Null);
+    }
+    serde_json::from_slice(body).map_err(|e| ApiError::bad_request(format!("invalid JSON: {}", e)))
+}
+
+fn strings(value: &Value) -> Vec<String> {
+    match value {
+        Value::String(s) => vec![s.clone()],
+        Value::Array(values) => values
+            .iter()
+            .filter_map(|v| v.as_str().map(str::to_string))
+            .collect(),
+        _ => vec![],
+    }
+}
+
+/// Fields of the host config that can be changed with `POST /containers/{id}/update`.
+const RESOURCE_FIELDS: &[&str] = &[
+    "CpuShares",
+    "CpuPeriod",
+    "CpuQuota",
+    "NanoCpus",
+    "CpusetCpus",
+    "CpusetMems",
+    "Memory",
+    "MemoryReservation",
+    "MemorySwap",
+    "KernelMemory",
+    "PidsLimit",
+    "BlkioWeight",
+    "BlkioWeightDevice",
+    "RestartPolicy",
+];
+
+fn resources(value: &Value) -> serde_json::Map<String, Value> {
+    value
+        .as_object()
+        .map(|map| {
+            map.iter()
+                .filter(|(k, _)| RESOURCE_FIELDS.contains(&k.as_str()))
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn string_map(value: &Value) -> BTreeMap<String, String> {
+    value
+        .as_object()
+        .map(|map| {
+            map.iter()
+                .filter_map(|(k, v)| v.as_str().map(|v| (k.clone(), v.to_string())))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Encodes output of a container the way it's sent to clients, multiplexed unless the container
+/// has a TTY.
+fn encode(chunk: &TtyChunk, tty: bool, timestamp: Option<SystemTime>) -> Vec<u8> {
+    let data: &[u8] = chunk;
+    let data = match timestamp {
+        Some(time) => [format!("{} ", rfc3339(time)).as_bytes(), data].concat(),
+        None => data.to_vec(),
+    };
+    match (tty, chunk) {
+        (true, _) => data,
+        (false, TtyChunk::StdErr(_)) => TtyChunk::StdErr(data).encode(),
+        (false, _) => TtyChunk::StdOut(data).encode(),
+    }
+}
+
+//####################################################################################################
+//
+// System
+//
+//####################################################################################################
+
+fn ping() -> Response<Body> {
+    let mut response = Response::new(Body::from("OK"));
+    let headers = response.headers_mut();
+    for (key, value) in [
+        ("api-version", LATEST_API_VERSION.to_string()),
+        ("docker-experimental", "false".to_string()),
+        ("ostype", "linux".to_string()),
+        ("server", format!("Docker/{} (linux)", ENGINE_VERSION)),
+        (
+            "cache-control",
+            "no-cache, no-store, must-revalidate".to_string(),
+        ),
+        ("pragma", "no-cache".to_string()),
+    ] {
+        headers.insert(key, HeaderValue::from_str(&value).expect("valid header"));
+    }
+    response
+}
+
+fn version() -> Value {
+    json!({
+        "Platform": {"Name": "Docker Engine - Community"},
+        "Version": ENGINE_VERSION,
+        "ApiVersion": LATEST_API_VERSION.to_string(),
+        "MinAPIVersion": "1.12",
+        "GitCommit": "b0f5bc3",
+        "GoVersion": "go1.13.15",
+        "Os": "linux",
+        "Arch": "amd64",
+        "KernelVersion": "5.10.0",
+        "BuildTime": "2021-06-02T11:54:50.000000000+00:00",
+    })
+}
+
+fn info(state: &State) -> Value {
+    let count = |status: &[Status]| {
+        state
+            .containers
+            .iter()
+            .filter(|c| status.contains(&c.status))
+            .count()
+    };
+    let commit = |id: &str| json!({"ID": id, "Expected": id});
+    let mut info = json!({
+        "ID": "FAKE:ENGINE:0000:0000:0000:0000:0000:0000:0000:0000:0000:0000",
+        "Containers": state.containers.len(),
+        "ContainersRunning": count(&[Status::Running]),
+        "ContainersPaused": count(&[Status::Paused]),
+        "ContainersStopped": count(&[Status::Created, Status::Exited]),
+        "Images": state.images.len(),
+        "Driver": "overlay2",
+        "DriverStatus": [["Backing Filesystem", "extfs"]],
+        "DockerRootDir": "/var/lib/docker",
+        "Debug": false,
+        "NFd": 24,
+        "NGoroutines": 34,
+        "SystemTime": rfc3339(SystemTime::now()),
+        "LoggingDriver": "json-file",
+        "CgroupDriver": "cgroupfs",
+        "CgroupVersion": "1",
+        "NEventsListener": 0,
+        "KernelVersion": "5.10.0",
+        "OperatingSystem": "Fake Engine",
+        "OSVersion": "",
+        "OSType": "linux",
+        "Architecture": "x86_64",
+        "NCPU": 4,
+        "MemTotal": 8_589_934_592u64,
+        "IndexServerAddress": "https://index.docker.io/v1/",
+        "HttpProxy": "",
+        "HttpsProxy": "",
+        "NoProxy": "",
+        "Name": "fake-engine",
+        "Labels": [],
+        "ExperimentalBuild": false,
+        "ServerVersion": ENGINE_VERSION,
+        "DefaultRuntime": "runc",
+        "LiveRestoreEnabled": false,
+        "InitBinary": "docker-init",
+        "ContainerdCommit": commit("d71fcd7"),
+        "RuncCommit": commit("b9ee9c6"),
+        "InitCommit": commit("de40ad0"),
+        "SecurityOptions": ["name=seccomp,profile=default"],
+        "Warnings": null,
+    });
+    // the host supports every resource limit
+    for feature in &[
+        "MemoryLimit",
+        "SwapLimit",
+        "KernelMemory",
+        "KernelMemoryTCP",
+        "CpuCfsPeriod",
+        "CpuCfsQuota",
+        "CPUShares",
+        "CPUSet",
+        "PidsLimit",
+        "OomKillDisable",
+        "IPv4Forwarding",
+        "BridgeNfIptables",
+        "BridgeNfIp6tables",
+    ] {
+        info[*feature] = Value::Bool(true);
+    }
+    info
+}
+
+fn events(engine: &Arc<Engine>, query: &Query) -> ApiResult<Response<Body>> {
+    let since = timestamp(query, "since")?;
+    let until = timestamp(query, "until")?;
+    let filters = filters(query)?;
+    let (mut sender, response) = streaming("application/json");
+
+    let engine = engine.clone();
+    let mut changes = engine.subscribe();
+    // without `since` only events that happen from now on are sent
+    let mut index = match since {
+        Some(_) => 0,
+        None => engine.lock().events.len(),
+    };
+    tokio::spawn(async move {
+        loop {
+            let batch: Vec<_> = {
+                let state = engine.lock();
+                let batch = state.events[index..]
+                    .iter()
+                    .filter(|e| since.map(|since| e.time >= since).unwrap_or(true))
+                    .filter(|e| until.map(|until| e.time <= until).unwrap_or(true))
+                    .filter(|e| {
+                        let attribute = |key: &str| {
+                            e.attributes
+                                .get(key)
+                                .and_then(Value::as_str)
+                                .unwrap_or_default()
+                                .to_string()
+                        };
+                        let object = |typ: &str, v: &str| {
+                            e.typ == typ && (e.id == v || attribute("name") == v)
+                        };
+                        matches(&filters, "type", |v| e.typ == v)
+                            && matches(&filters, "event", |v| e.action == v)
+                            && matches(&filters, "container", |v| object("container", v))
+                            && matches(&filters, "image", |v| {
+                                object("image", v) || attribute("image") == v
+                            })
+                            && matches(&filters, "network", |v| object("network", v))
+                            && matches(&filters, "volume", |v| object("volume", v))
+                    })
+                    .map(|e| format!("{}\n", e.to_json()))
+                    .collect();
+                index = state.events.len();
+                batch
+            };
+            for event in batch {
+                if sender.send_data(Bytes::from(event)).await.is_err() {
+                    return;
+                }
+            }
+
+            let changed = match until.and_then(|until| until.duration_since(SystemTime::now()).ok())
+            {
+                Some(remaining) => match tokio::time::timeout(remaining, changes.changed()).await {
+                    Ok(changed) => changed,
+                    Err(_) => return,
+                },
+                None if until.is_some() => return,
+                None => changes.changed().await,
+            };
+            if changed.is_err() {
+                return;
+            }
+        }
+    });
+
+    Ok(response)
+}
+
+//####################################################################################################
+//
+// Containers
+//
+//####################################################################################################
+
+fn list_containers(state: &State, query: &Query) -> ApiResult<Response<Body>> {
+    let all = flag(query, "all");
+    let filters = filters(query)?;
+    let containers: Vec<_> = state
+        .containers
+        .iter()
+        .rev()
+        .filter(|c| all || c.is_running() || filters.contains_key("status"))
+        .filter(|c| matches(&filters, "status", |v| c.status.as_str() == v))
+        .filter(|c| matches(&filters, "id", |v| c.id.starts_with(v)))
+        .filter(|c| {
+            matches(&filters, "name", |v| {
+                c.name.contains(v.trim_start_matches('/'))
+            })
+        })
+        .filter(|c| {
+            matches(&filters, "label", |v| match v.split_once('=') {
+                Some((key, value)) => c.config.labels.get(key).map(String::as_str) == Some(value),
+                None => c.config.labels.contains_key(v),
+            })
+        })
+        .map(|c| c.summary(&state.networks))
+        .collect();
+    Ok(ok(json!(containers)))
+}
+
+fn create_container(
+    engine: &Arc<Engine>,
+    query: &Query,
+    body: &Bytes,
+) -> ApiResult<Response<Body>> {
+    let body = body_json(body)?;
+    let mut state = engine.lock();
+
+    let name = match query.get("name").filter(|n| !n.is_empty()) {
+        Some(name) => {
+            let name = name.trim_start_matches('/');
+            let valid = name
+                .chars()
+                .next()
+                .map(|c| c.is_ascii_alphanumeric())
+                .unwrap_or(false)
+                && name.len() > 1
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "_.-".contains(c));
+            if !valid {
+                return Err(ApiError::bad_request(format!(
+                    "Invalid container name ({}), only [a-zA-Z0-9][a-zA-Z0-9_.-] are allowed",
+                    name
+                )));
+            }
+            if let Some(other) = state.containers.iter().find(|c| c.name == name) {
+                return Err(ApiError::conflict(format!(
+                    "Conflict. The container name \"/{}\" is already in use by container \"{}\". \
+                     You have to remove (or rename) that container to be able to reuse that name.",
+                    name, other.id
+                )));
+            }
+            name.to_string()
+        }
+        None => state.container_name(),
+    };
+
+    let image_name = body["Image"]
+        .as_str()
+        .filter(|i| !i.is_empty())
+        .ok_or_else(|| {
+            ApiError::bad_request("Config cannot be empty in order to create a container")
+        })?
+        .to_string();
+    let (image_id, image_cmd, image_env) = {
+        let image = state.image(&image_name)?;
+        (image.id.clone(), image.cmd.clone(), image.env.clone())
+    };
+
+    let host_config = &body["HostConfig"];
+    let entrypoint = strings(&body["Entrypoint"]);
+    let mut cmd = strings(&body["Cmd"]);
+    if cmd.is_empty() && entrypoint.is_empty() {
+        cmd = image_cmd;
+    }
+    if cmd.is_empty() && entrypoint.is_empty() {
+        return Err(ApiError::bad_request("No command specified"));
+    }
+    let network_mode = match host_config["NetworkMode"].as_str() {
+        Some("") | None => "default".to_string(),
+        Some(mode) => mode.to_string(),
+    };
+    let config = ContainerConfig {
+        entrypoint,
+        cmd,
+        env: image_env.into_iter().chain(strings(&body["Env"])).collect(),
+        labels: string_map(&body["Labels"]),
+        hostname: body["Hostname"].as_str().unwrap_or_default().to_string(),
+        user: body["User"].as_str().unwrap_or_default().to_string(),
+        working_dir: body["WorkingDir"].as_str().unwrap_or_default().to_string(),
+        tty: body["Tty"].as_bool().unwrap_or_default(),
+        open_stdin: body["OpenStdin"].as_bool().unwrap_or_default(),
+        stdin_once: body["StdinOnce"].as_bool().unwrap_or_default(),
+        attach_stdin: body["AttachStdin"].as_bool().unwrap_or_default(),
+        attach_stdout: body["AttachStdout"].as_bool().unwrap_or(true),
+        attach_stderr: body["AttachStderr"].as_bool().unwrap_or(true),
+        auto_remove: host_config["AutoRemove"].as_bool().unwrap_or_default(),
+        network_mode: network_mode.clone(),
+        binds: strings(&host_config["Binds"]),
+        console_size: serde_json::from_value(host_config["ConsoleSize"].clone())
+            .unwrap_or_default(),
+        resources: resources(host_config),
+    };
+
+    // resolve everything that can fail before changing the state
+    let mut networks = vec![match network_mode.as_str() {
+        "default" => "bridge".to_string(),
+        mode => state.network(mode)?.id.clone(),
+    }];
+    if let Some(endpoints) = body["NetworkingConfig"]["EndpointsConfig"].as_object() {
+        for name in endpoints.keys() {
+            let id = state.network(name)?.id.clone();
+            if !networks.contains(&id) {
+                networks.push(id);
+            }
+        }
+    }
+    let mut mounts = vec![];
+    for bind in &config.binds {
+        let mut parts = bind.split(':');
+        let (source, destination) = match (parts.next(), parts.next()) {
+            (Some(source), Some(destination)) => (source, destination),
+            _ => {
+                return Err(ApiError::bad_request(format!(
+                    "invalid volume specification: '{}'",
+                    bind
+                )))
+            }
+        };
+        mounts.push((
+            source.to_string(),
+            destination.to_string(),
+            parts.next() != Some("ro"),
+        ));
+    }
+    if let Some(specs) = host_config["Mounts"].as_array() {
+        for spec in specs {
+            let source = spec["Source"].as_str().unwrap_or_default().to_string();
+            let target = spec["Target"]
+                .as_str()
+                .ok_or_else(|| ApiError::bad_request("mount target is required"))?;
+            mounts.push((
+                source,
+                target.to_string(),
+                !spec["ReadOnly"].as_bool().unwrap_or_default(),
+            ));
+        }
+    }
+
+    let mut container = Container::new(name, image_name, image_id, config);
+    let id = container.id.clone();
+    for (source, destination, rw) in mounts {
+        if source.starts_with('/') {
+            container.mounts.push(MountPoint {
+                volume: None,
+                source,
+                destination,
+                rw,
+            });
+            continue;
+        }
+        let anonymous = source.is_empty();
+        let name = if anonymous { random_id() } else { source };
+        if state.volume(&name).is_err() {
+            let mut volume = Volume::new(name.clone(), "local".to_string());
+            volume.anonymous = anonymous;
+            state.volumes.push(volume);
+            state.event("volume", "create", &name, json!({"driver": "local"}));
+        }
+        let source = state.volume(&name)?.mountpoint();
+        container.mounts.push(MountPoint {
+            volume: Some(name),
+            source,
+            destination,
+            rw,
+        });
+    }
+    state.containers.push(container);
+    for network in networks {
+        if let Ok(network) = state.network(&network) {
+            network.connect(&id);
+        }
+    }
+    state.container_event(&id, "create", json!({}));
+    drop(state);
+    engine.notify();
+
+    Ok(created(json!({"Id": id, "Warnings": []})))
+}
+
+fn start(engine: &Arc<Engine>, id: &str) -> ApiResult<Response<Body>> {
+    let mut state = engine.lock();
+    let container = state.container(id)?;
+    if container.is_running() {
+        return Ok(status(StatusCode::NOT_MODIFIED));
+    }
+
+    let argv = container.config.argv();
+    if !process::exists(&argv[0]) {
+        let message = format!(
+            "OCI runtime create failed: container_linux.go:380: starting container process caused: \
+             exec: \"{}\": executable file not found in $PATH: unknown",
+            argv[0]
+        );
+        container.exit_code = 127;
+        container.error = message.clone();
+        return Err(ApiError::bad_request(message));
+    }
+
+    container.status = Status::Running;
+    container.run += 1;
+    container.pid = 1000 + container.run * 7 + container.exits;
+    container.exit_code = 0;
+    container.error.clear();
+    container.started_at = Some(SystemTime::now());
+
+    let stdin = if container.config.open_stdin {
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        container.stdin = Some(tx);
+        Some(rx)
+    } else {
+        None
+    };
+    let process = Process {
+        argv,
+        env: container.config.env.clone(),
+        hostname: container.config.hostname.clone(),
+        user: container.config.user.clone(),
+        working_dir: container.config.working_dir.clone(),
+    };
+    let (id, run) = (container.id.clone(), container.run);
+    let output = Output::Container {
+        engine: engine.clone(),
+        id: id.clone(),
+        run,
+    };
+    let task = {
+        let engine = engine.clone();
+        let id = id.clone();
+        tokio::spawn(async move {
+            let code = process::run(process, stdin, output).await;
+            exited(&engine, &id, run, code);
+        })
+    };
+    container.process = Some(task);
+
+    state.container_event(&id, "start", json!({}));
+    drop(state);
+    engine.notify();
+
+    Ok(status(StatusCode::NO_CONTENT))
+}
+
+/// Records that the process started in `run` of a container ended by itself.
+fn exited(engine: &Engine, id: &str, run: u64, code: i64) {
+    let mut state = engine.lock();
+    let auto_remove = match state.containers.iter_mut().find(|c| c.id == id) {
+        Some(container) if container.run == run && container.is_running() => {
+            // the task is finishing on its own, it doesn't need to be aborted
+            container.process.take();
+            container.exited(code);
+            container.config.auto_remove
+        }
+        _ => return,
+    };
+    state.container_event(id, "die", json!({"exitCode": code.to_string()}));
+    if auto_remove {
+        remove_container(&mut state, id, true);
+    }
+    drop(state);
+    engine.notify();
+}
+
+/// Terminates the process of a running container with `signal`.
+fn signal(state: &mut State, id: &str, signal: i64) {
+    let auto_remove = match state.containers.iter_mut().find(|c| c.id == id) {
+        Some(container) => {
+            container.exited(128 + signal);
+            container.config.auto_remove
+        }
+        None => return,
+    };
+    state.container_event(id, "kill", json!({"signal": signal.to_string()}));
+    state.container_event(id, "die", json!({"exitCode": (128 + signal).to_string()}));
+    if auto_remove {
+        remove_container(state, id, true);
+    }
+}
+
+fn stop(engine: &Arc<Engine>, id: &str) -> ApiResult<Response<Body>> {
+    let mut state = engine.lock();
+    let container = state.container(id)?;
+    if !container.is_running() {
+        return Ok(status(StatusCode::NOT_MODIFIED));
+    }
+    let id = container.id.clone();
+    // processes exit right away on SIGTERM so there is no need to wait for the timeout
+    signal(&mut state, &id, 15);
+    state.container_event(&id, "stop", json!({}));
+    drop(state);
+    engine.notify();
+    Ok(status(StatusCode::NO_CONTENT))
+}
+
+fn restart(engine: &Arc<Engine>, id: &str) -> ApiResult<Response<Body>> {
+    stop(engine, id)?;
+    start(engine, id)?;
+    let mut state = engine.lock();
+    let id = state.container(id)?.id.clone();
+    state.container_event(&id, "restart", json!({}));
+    drop(state);
+    engine.notify();
+    Ok(status(StatusCode::NO_CONTENT))
+}
+
+fn kill(engine: &Arc<Engine>, id: &str, query: &Query) -> ApiResult<Response<Body>> {
+    let name = query.get("signal").map(String::as_str).unwrap_or("SIGKILL");
+    let number = match name.trim_start_matches("SIG") {
+        "HUP" => 1,
+        "INT" => 2,
+        "QUIT" => 3,
+        "KILL" => 9,
+        "USR1" => 10,
+        "USR2" => 12,
+        "TERM" => 15,
+        other => other
+            .parse::<i64>()
+            .ok()
+            .filter(|n| (1..=31).contains(n))
+            .ok_or_else(|| ApiError::bad_request(format!("Invalid signal: {}", name)))?,
+    };
+
+    let mut state = engine.lock();
+    let container = state.container(id)?;
+    if !container.is_running() {
+        return Err(ApiError::conflict(format!(
+            "Cannot kill container: {}: Container {} is not running",
+            id, container.id
+        )));
+    }
+    let id = container.id.clone();
+    signal(&mut state, &id, number);
+    drop(state);
+    engine.notify();
+    Ok(status(StatusCode::NO_CONTENT))
+}
+
+fn pause(engine: &Arc<Engine>, id: &str, pause: bool) -> ApiResult<Response<Body>> {
+    let mut state = engine.lock();
+    let container = state.container(id)?;
+    let message = match (pause, container.status) {
+        (_, Status::Created) | (_, Status::Exited) => Some("is not running"),
+        (true, Status::Paused) => Some("is already paused"),
+        (false, Status::Running) => Some("is not paused"),
+        _ => None,
+    };
+    if let Some(message) = message {
+        return Err(ApiError::conflict(format!(
+            "Container {} {}",
+            container.id, message
+        )));
+    }
+    container.status = if pause {
+        Status::Paused
+    } else {
+        Status::Running
+    };
+    let id = container.id.clone();
+    state.container_event(&id, if pause { "pause" } else { "unpause" }, json!({}));
+    drop(state);
+    engine.notify();
+    Ok(status(StatusCode::NO_CONTENT))
+}
+
+fn rename(engine: &Arc<Engine>, id: &str, query: &Query) -> ApiResult<Response<Body>> {
+    let name = query
+        .get("name")
+        .filter(|n| !n.is_empty())
+        .ok_or_else(|| ApiError::bad_request("Neither old nor new names may be empty"))?
+        .trim_start_matches('/')
+        .to_string();
+    let mut state = engine.lock();
+    if let Some(other) = state.containers.iter().find(|c| c.name == name) {
+        return Err(ApiError::conflict(format!(
+            "Conflict. The container name \"/{}\" is already in use by container \"{}\". \
+             You have to remove (or rename) that container to be able to reuse that name.",
+            name, other.id
+        )));
+    }
+    let container = state.container(id)?;
+    let old = std::mem::replace(&mut container.name, name);
+    let id = container.id.clone();
+    state.container_event(&id, "rename", json!({ "oldName": format!("/{}", old) }));
+    drop(state);
+    engine.notify();
+    Ok(status(StatusCode::NO_CONTENT))
+}
+
+fn resize(engine: &Arc<Engine>, id: &str, query: &Query) -> ApiResult<Response<Body>> {
+    let mut size = [0; 2];
+    for (value, key) in size.iter_mut().zip(["h", "w"]) {
+        *value = query
+            .get(key)
+            .map(|v| v.parse())
+            .unwrap_or(Ok(0))
+            .map_err(|e| ApiError::bad_request(format!("invalid value for {}: {}", key, e)))?;
+    }
+    let mut state = engine.lock();
+    let container = state.container(id)?;
+    if !container.is_running() {
+        return Err(ApiError::conflict(format!(
+            "Container {} is not running",
+            container.id
+        )));
+    }
+    container.config.console_size = size;
+    Ok(status(StatusCode::OK))
+}
+
+fn update_container(engine: &Arc<Engine>, id: &str, body: &Bytes) -> ApiResult<Response<Body>> {
+    let update = resources(&body_json(body)?);
+    let mut state = engine.lock();
+    let container = state.container(id)?;
+    let restarts = update
+        .get("RestartPolicy")
+        .and_then(|policy| policy["Name"].as_str())
+        .is_some_and(|name| !name.is_empty() && name != "no");
+    if restarts && container.config.auto_remove {
+        return Err(ApiError::bad_request(
+            "Cannot update container: Restart policy cannot be updated because AutoRemove is \
+             enabled for the container",
+        ));
+    }
+    container.config.resources.extend(update);
+    let id = container.id.clone();
+    state.container_event(&id, "update", json!({}));
+    drop(state);
+    engine.notify();
+    Ok(ok(json!({ "Warnings": [] })))
+}
+
+async fn wait(engine: &Arc<Engine>, id: &str, query: &Query) -> ApiResult<Response<Body>> {
+    let condition = query
+        .get("condition")
+        .map(String::as_str)
+        .unwrap_or("not-running");
+    if !["not-running", "next-exit", "removed"].contains(&condition) {
+        return Err(ApiError::bad_request(format!(
+            "invalid condition: \"{}\"",
+            condition
+        )));
+    }
+
+    let mut changes = engine.subscribe();
+    let (id, exits, mut code) = {
+        let mut state = engine.lock();
+        let container = state.container(id)?;
+        if condition == "not-running" && !container.is_running() {
+            return Ok(ok(
+                json!({"StatusCode": container.exit_code, "Error": null}),
+            ));
+        }
+        (container.id.clone(), container.exits, container.exit_code)
+    };
+
+    loop {
+        if changes.changed().await.is_err() {
+            return Err(ApiError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "the engine shut down",
+            ));
+        }
+        let state = engine.lock();
+        match state.containers.iter().find(|c| c.id == id) {
+            Some(container) => {
+                code = container.exit_code;
+                if condition != "removed" && container.exits > exits {
+                    break;
+                }
+            }
+            None => break,
+        }
+    }
+    Ok(ok(json!({"StatusCode": code, "Error": null})))
+}
+
+fn top(state: &mut State, id: &str) -> ApiResult<Response<Body>> {
+    let container = state.container(id)?;
+    if !container.is_running() {
+        return Err(ApiError::conflict(format!(
+            "Container {} is not running",
+            container.id
+        )));
+    }
+    Ok(ok(json!({
+        "Titles": ["UID", "PID", "PPID", "C", "STIME", "TTY", "TIME", "CMD"],
+        "Processes": [[
+            if container.config.user.is_empty() { "root" } else { &container.config.user },
+            container.pid.to_string(),
+            "1",
+            "0",
+            "00:00",
+            "?",
+            "00:00:00",
+            container.config.argv().join(" "),
+        ]],
+    })))
+}
+
+fn logs(engine: &Arc<Engine>, id: &str, query: &Query) -> ApiResult<Response<Body>> {
+    let (stdout, stderr) = (flag(query, "stdout"), flag(query, "stderr"));
+    if !stdout && !stderr {
+        return Err(ApiError::bad_request(
+            "Bad parameters: you must choose at least one stream",
+        ));
+    }
+    let follow = flag(query, "follow");
+    let timestamps = flag(query, "timestamps");
+    let details = flag(query, "details");
+    let since = timestamp(query, "since")?;
+    let until = timestamp(query, "until")?;
+
+    let (id, tty, mut index) = {
+        let mut state = engine.lock();
+        let container = state.container(id)?;
+        let index = match query.get("tail").map(String::as_str) {
+            Some("all") | None => 0,
+            Some(n) => {
+                let n: usize = n.parse().unwrap_or(0);
+                container.log.len().saturating_sub(n)
+            }
+        };
+        (container.id.clone(), container.config.tty, index)
+    };
+
+    let (mut sender, response) = streaming("application/vnd.docker.raw-stream");
+    let engine = engine.clone();
+    let mut changes = engine.subscribe();
+    tokio::spawn(async move {
+        loop {
+            let (batch, done) = {
+                let state = engine.lock();
+                let container = match state.containers.iter().find(|c| c.id == id) {
+                    Some(container) => container,
+                    None => return,
+                };
+                let batch: Vec<_> = container.log[index.min(container.log.len())..]
+                    .iter()
+                    .filter(|e| since.map(|since| e.time >= since).unwrap_or(true))
+                    .filter(|e| until.map(|until| e.time <= until).unwrap_or(true))
+                    .filter(|e| match e.chunk {
+                        TtyChunk::StdErr(_) => stderr,
+                        _ => stdout,
+                    })
+                    .map(|e| {
+                        let mut chunk = e.chunk.clone();
+                        if details {
+                            // the fake engine has no logging driver adding attributes
+                            chunk.insert(0, b' ');
+                        }
+                        encode(&chunk, tty, timestamps.then_some(e.time))
+                    })
+                    .collect();
+                index = container.log.len();
+                let done =
+                    !follow || (!container.is_running() && container.status != Status::Created);
+                (batch, done)
+            };
+            for chunk in batch {
+                if sender.send_data(Bytes::from(chunk)).await.is_err() {
+                    return;
+                }
+            }
+            if done || changes.changed().await.is_err() {
+                return;
+            }
+        }
+    });
+
+    Ok(response)
+}
+
+fn attach(
+    engine: &Arc<Engine>,
+    id: &str,
+    query: &Query,
+    req: &mut Request<Body>,
+) -> ApiResult<Response<Body>> {
+    if !req.headers().contains_key(header::UPGRADE) {
+        return Err(ApiError::bad_request(
+            "the fake engine only supports attaching with an upgraded connection",
+        ));
+    }
+    let session = AttachSession::new(engine, id, query)?;
+
+    let upgrade = hyper::upgrade::on(req);
+    tokio::spawn(async move {
+        if let Ok(connection) = upgrade.await {
+            session.run(connection).await;
+        }
+    });
+
+    let mut response = status(StatusCode::SWITCHING_PROTOCOLS);
+    let headers = response.headers_mut();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/vnd.docker.raw-stream"),
+    );
+    headers.insert(header::CONNECTION, HeaderValue::from_static("Upgrade"));
+    headers.insert(header::UPGRADE, HeaderValue::from_static("tcp"));
+    Ok(response)
+}
+
+#[cfg(feature = "websocket")]
+fn attach_websocket(
+    engine: &Arc<Engine>,
+    id: &str,
+    query: &Query,
+    req: &mut Request<Body>,
+) -> ApiResult<Response<Body>> {
+    use futures_util::{SinkExt, StreamExt};
+    use tokio_tungstenite::{
+        tungstenite::{protocol::Role, Message},
+        WebSocketStream,
+    };
+
+    let accept = crate::conn::websocket::accept_key(req.headers())
+        .ok_or_else(|| ApiError::bad_request("missing Sec-WebSocket-Key header"))?;
+    let mut session = AttachSession::new(engine, id, query)?;
+    // output sent over websockets is never multiplexed
+    session.tty = true;
+
+    let upgrade = hyper::upgrade::on(req);
+    tokio::spawn(async move {
+        let connection = match upgrade.await {
+            Ok(connection) => connection,
+            Err(_) => return,
+        };
+        let websocket = WebSocketStream::from_raw_socket(connection, Role::Server, None).await;
+        let (mut sink, mut stream) = websocket.split();
+        let (session_end, bridge_end) = tokio::io::duplex(4096);
+        let (mut reader, mut writer) = tokio::io::split(bridge_end);
+
+        tokio::spawn(async move {
+            while let Some(Ok(message)) = stream.next().await {
+                let data = match message {
+                    Message::Binary(data) => data,
+                    Message::Text(text) => text.into_bytes(),
+                    Message::Close(_) => break,
+                    _ => continue,
+                };
+                if writer.write_all(&data).await.is_err() {
+                    break;
+                }
+            }
+            let _ = writer.shutdown().await;
+        });
+        tokio::spawn(async move {
+            let mut buffer = vec![0; 4096];
+            while let Ok(n @ 1..) = reader.read(&mut buffer).await {
+                if sink
+                    .send(Message::Binary(buffer[..n].to_vec()))
+                    .await
+                    .is_err()
+                {
+                    return;
+                }
+            }
+            let _ = sink.close().await;
+        });
+        session.run(session_end).await;
+    });
+
+    let mut response = status(StatusCode::SWITCHING_PROTOCOLS);
+    let headers = response.headers_mut();
+    headers.insert(header::CONNECTION, HeaderValue::from_static("Upgrade"));
+    headers.insert(header::UPGRADE, HeaderValue::from_static("websocket"));
+    headers.insert(header::SEC_WEBSOCKET_ACCEPT, accept);
+    Ok(response)
+}
+
+/// A client attached to the streams of a container.
+struct AttachSession {
+    engine: Arc<Engine>,
+    id: String,
+    stdin: bool,
+    stdout: bool,
+    stderr: bool,
+    follow: bool,
+    /// Whether the output is sent as is instead of multiplexed.
+    tty: bool,
+    stdin_once: bool,
+    detach_keys: DetachKeys,
+    /// Number of exits of the container when attaching, the session ends with the next one.
+    exits: u64,
+    /// Index of the next log entry to send.
+    index: usize,
+}
+
+impl AttachSession {
+    fn new(engine: &Arc<Engine>, id: &str, query: &Query) -> ApiResult<Self> {
+        let detach_keys = match query.get("detachKeys").filter(|k| !k.is_empty()) {
+            Some(keys) => {
+                DetachKeys::parse(keys).map_err(|e| ApiError::bad_request(e.to_string()))?
+            }
+            None => DetachKeys::default(),
+        };
+        let mut state = engine.lock();
+        let container = state.container(id)?;
+        Ok(AttachSession {
+            engine: engine.clone(),
+            id: container.id.clone(),
+            stdin: flag(query, "stdin"),
+            stdout: flag(query, "stdout"),
+            stderr: flag(query, "stderr"),
+            follow: flag(query, "stream"),
+            tty: container.config.tty,
+            stdin_once: container.config.stdin_once,
+            detach_keys,
+            exits: container.exits,
+            index: if flag(query, "logs") {
+                0
+            } else {
+                container.log.len()
+            },
+        })
+    }
+
+    async fn run<C>(mut self, connection: C)
+    where
+        C: tokio::io::AsyncRead + tokio::io::AsyncWrite + Send + 'static,
+    {
+        let (mut reader, mut writer) = tokio::io::split(connection);
+        let engine = self.engine.clone();
+        let detached = Arc::new(AtomicBool::new(false));
+
+        let input = {
+            let engine = engine.clone();
+            let id = self.id.clone();
+            let detached = detached.clone();
+            let (stdin, stdin_once, tty) = (self.stdin, self.stdin_once, self.tty);
+            let mut detach_keys = self.detach_keys.clone();
+            tokio::spawn(async move {
+                let mut buffer = vec![0; 4096];
+                loop {
+                    let n = reader.read(&mut buffer).await.unwrap_or(0);
+                    let mut state = engine.lock();
+                    let container = match state.containers.iter_mut().find(|c| c.id == id) {
+                        Some(container) => container,
+                        None => return,
+                    };
+                    if n == 0 {
+                        if stdin && stdin_once {
+                            container.stdin = None;
+                        }
+                        return;
+                    }
+                    // like the daemon only containers with a TTY can be detached from
+                    let detach = if stdin && tty {
+                        detach_keys.feed(&buffer[..n])
+                    } else {
+                        None
+                    };
+                    let input = &buffer[..detach.unwrap_or(n)];
+                    if let (true, Some(tx), false) = (stdin, &container.stdin, input.is_empty()) {
+                        let _ = tx.send(input.to_vec());
+                    }
+                    if detach.is_some() {
+                        detached.store(true, Ordering::SeqCst);
+                        drop(state);
+                        engine.notify();
+                        return;
+                    }
+                }
+            })
+        };
+
+        let mut changes = engine.subscribe();
+        loop {
+            let (batch, done) = {
+                let state = engine.lock();
+                let container = match state.containers.iter().find(|c| c.id == self.id) {
+                    Some(container) => container,
+                    None => break,
+                };
+                let batch: Vec<_> = container.log[self.index.min(container.log.len())..]
+                    .iter()
+                    .filter(|e| match e.chunk {
+                        TtyChunk::StdErr(_) => self.stderr,
+                        _ => self.stdout,
+                    })
+                    .map(|e| encode(&e.chunk, self.tty, None))
+                    .collect();
+                self.index = container.log.len();
+                (batch, container.exits > self.exits)
+            };
+            for chunk in batch {
+                if writer.write_all(&chunk).await.is_err() {
+                    input.abort();
+                    return;
+                }
+            }
+            if done
+                || !self.follow
+                || detached.load(Ordering::SeqCst)
+                || changes.changed().await.is_err()
+            {
+                break;
+            }
+        }
+        input.abort();
+        let _ = writer.shutdown().await;
+    }
+}
+
+/// Removes a container that isn't running anymore.
+fn remove_container(state: &mut State, id: &str, remove_volumes: bool) {
+    let position = match state.containers.iter().position(|c| c.id == id) {
+        Some(position) => position,
+        None => return,
+    };
+    state.container_event(id, "destroy", json!({}));
+    let container = state.containers.remove(position);
+    for network in &mut state.networks {
+        network.endpoints.remove(id);
+    }
+    for exec in &container.exec_ids {
+        state.execs.remove(exec);
+    }
+    if remove_volumes {
+        for mount in &container.mounts {
+            let name = match &mount.volume {
+                Some(name) => name,
+                None => continue,
+            };
+            let in_use = state
+                .containers
+                .iter()
+                .any(|c| c.mounts.iter().any(|m| m.volume.as_ref() == Some(name)));
+            if let Some(position) = state
+                .volumes
+                .iter()
+                .position(|v| &v.name == name && v.anonymous)
+            {
+                if !in_use {
+                    state.volumes.remove(position);
+                    state.event("volume", "destroy", name, json!({}));
+                }
+            }
+        }
+    }
+}
+
+fn delete_container(engine: &Arc<Engine>, id: &str, query: &Query) -> ApiResult<Response<Body>> {
+    let mut state = engine.lock();
+    let container = state.container(id)?;
+    let id = container.id.clone();
+    if container.is_running() {
+        if !flag(query, "force") {
+            return Err(ApiError::conflict(format!(
+                "You cannot remove a running container {}. Stop the container before attempting \
+                 removal or force remove",
+                id
+            )));
+        }
+        signal(&mut state, &id, 9);
+    }
+    remove_container(&mut state, &id, flag(query, "v"));
+    drop(state);
+    engine.notify();
+    Ok(status(StatusCode::NO_CONTENT))
+}
+
+//####################################################################################################
+//
+// Exec
+//
+//####################################################################################################
+
+fn create_exec(engine: &Arc<Engine>, id: &str, body: &Bytes) -> ApiResult<Response<Body>> {
+    let body = body_json(body)?;
+    let argv = strings(&body["Cmd"]);
+    if argv.is_empty() {
+        return Err(ApiError::bad_request("No exec command specified"));
+    }
+
+    let mut state = engine.lock();
+    let container = state.container(id)?;
+    match container.status {
+        Status::Running => {}
+        Status::Paused => {
+            return Err(ApiError::conflict(format!(
+                "Container {} is paused, unpause the container before exec",
+                container.id
+            )))
+        }
+        _ => {
+            return Err(ApiError::conflict(format!(
+                "Container {} is not running",
+                container.id
+            )))
+        }
+    }
+    let exec = Exec {
+        id: random_id(),
+        container_id: container.id.clone(),
+        argv,
+        env: strings(&body["Env"]),
+        user: body["User"].as_str().unwrap_or_default().to_string(),
+        working_dir: body["WorkingDir"].as_str().unwrap_or_default().to_string(),
+        tty: body["Tty"].as_bool().unwrap_or_default(),
+        attach_stdout: body["AttachStdout"].as_bool().unwrap_or_default(),
+        attach_stderr: body["AttachStderr"].as_bool().unwrap_or_default(),
+        running: false,
+        started: false,
+        exit_code: None,
+    };
+    let exec_id = exec.id.clone();
+    container.exec_ids.push(exec_id.clone());
+    let container_id = container.id.clone();
+    let action = format!("exec_create: {}", exec.argv.join(" "));
+    state.execs.insert(exec_id.clone(), exec);
+    state.container_event(&container_id, action, json!({"execID": exec_id}));
+    drop(state);
+    engine.notify();
+
+    Ok(created(json!({ "Id": exec_id })))
+}
+
+fn start_exec(engine: &Arc<Engine>, id: &str, body: &Bytes) -> ApiResult<Response<Body>> {
+    let body = body_json(body)?;
+    let detach = body["Detach"].as_bool().unwrap_or_default();
+
+    let mut state = engine.lock();
+    let exec = state.exec(id)?;
+    if exec.started {
+        return Err(ApiError::conflict(format!(
+            "Exec instance {} has already been started",
+            id
+        )));
+    }
+    let (container_id, argv, exec_env, user, working_dir, tty, stdout, stderr) = (
+        exec.container_id.clone(),
+        exec.argv.clone(),
+        exec.env.clone(),
+        exec.user.clone(),
+        exec.working_dir.clone(),
+        exec.tty,
+        exec.attach_stdout,
+        exec.attach_stderr,
+    );
+    let container = state.container(&container_id)?;
+    if !container.is_running() {
+        return Err(ApiError::conflict(format!(
+            "Container {} is not running",
+            container_id
+        )));
+    }
+    let process = Process {
+        argv: argv.clone(),
+        env: container
+            .config
+            .env
+            .iter()
+            .cloned()
+            .chain(exec_env)
+            .collect(),
+        hostname: container.config.hostname.clone(),
+        user: if user.is_empty() {
+            container.config.user.clone()
+        } else {
+            user
+        },
+        working_dir: if working_dir.is_empty() {
+            container.config.working_dir.clone()
+        } else {
+            working_dir
+        },
+    };
+    let exec = state.exec(id)?;
+    exec.started = true;
+    exec.running = true;
+    let id = exec.id.clone();
+    state.container_event(
+        &container_id,
+        format!("exec_start: {}", argv.join(" ")),
+        json!({"execID": id}),
+    );
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    {
+        let engine = engine.clone();
+        tokio::spawn(async move {
+            let code = process::run(process, None, Output::Channel(tx.clone())).await;
+            let mut state = engine.lock();
+            if let Ok(exec) = state.exec(&id) {
+                exec.running = false;
+                exec.exit_code = Some(code);
+            }
+            state.container_event(
+                &container_id,
+                "exec_die",
+                json!({"execID": id, "exitCode": code.to_string()}),
+            );
+            drop(state);
+            engine.notify();
+            // the output ends once the exit code is known
+            drop(tx);
+        });
+    }
+    drop(state);
+    engine.notify();
+
+    if detach {
+        return Ok(status(StatusCode::OK));
+    }
+    let (mut sender, response) = streaming("application/vnd.docker.raw-stream");
+    tokio::spawn(async move {
+        while let Some(chunk) = rx.recv().await {
+            let attached = match chunk {
+                TtyChunk::StdErr(_) => stderr,
+                _ => stdout,
+            };
+            if attached
+                && sender
+                    .send_data(Bytes::from(encode(&chunk, tty, None)))
+                    .await
+                    .is_err()
+            {
+                return;
+            }
+        }
+    });
+    Ok(response)
+}
+
+//####################################################################################################
+//
+// Images
+//
+//####################################################################################################
+
+fn list_images(state: &State) -> Value {
+    state.images.iter().rev().map(|i| i.summary()).collect()
+}
+
+fn pull(engine: &Arc<Engine>, query: &Query) -> ApiResult<Response<Body>> {
+    let image = query
+        .get("fromImage")
+        .filter(|i| !i.is_empty())
+        .ok_or_else(|| {
+            ApiError::bad_request("the fake engine only supports pulling images with `fromImage`")
+        })?;
+    let tag = query.get("tag").filter(|t| !t.is_empty());
+    let has_tag = image.rsplit('/').next().unwrap_or(image).contains(':') || image.contains('@');
+    let reference = match tag {
+        Some(tag) if !has_tag => normalize_reference(&format!("{}:{}", image, tag)),
+        _ => normalize_reference(image),
+    };
+    let (repository, tag) = match reference.rsplit_once(':') {
+        Some((repository, tag)) if !tag.contains('/') && !reference.contains('@') => {
+            (repository.to_string(), tag.to_string())
+        }
+        _ => (reference.clone(), String::new()),
+    };
+    let path = if repository.contains('/') {
+        repository.clone()
+    } else {
+        format!("library/{}", repository)
+    };
+
+    let mut state = engine.lock();
+    let up_to_date = state.image(&reference).is_ok();
+    let id = state.add_image(&reference);
+    let digest = state.image(&id)?.digest.clone();
+    state.event("image", "pull", &reference, json!({ "name": reference }));
+    drop(state);
+    engine.notify();
+
+    let layer = &random_id()[..12];
+    let mut messages = vec![json!({"status": format!("Pulling from {}", path), "id": tag})];
+    if !up_to_date {
+        messages.extend(vec![
+            json!({"status": "Pulling fs layer", "progressDetail": {}, "id": layer}),
+            json!({
+                "status": "Downloading",
+                "progressDetail": {"current": 1_048_576, "total": 2_097_152},
+                "progress": "[=========================>                         ]  1.049MB/2.097MB",
+                "id": layer,
+            }),
+            json!({"status": "Download complete", "progressDetail": {}, "id": layer}),
+            json!({"status": "Pull complete", "progressDetail": {}, "id": layer}),
+        ]);
+    }
+    messages.push(json!({
+        "status": format!("Digest: {}", digest.rsplit('@').next().unwrap_or_default())
+    }));
+    messages.push(json!({
+        "status": if up_to_date {
+            format!("Status: Image is up to date for {}", reference)
+        } else {
+            format!("Status: Downloaded newer image for {}", reference)
+        }
+    }));
+
+    let chunks = messages
+        .into_iter()
+        .map(|m| Ok::<_, std::io::Error>(format!("{}\r\n", m)));
+    let mut response = Response::new(Body::wrap_stream(futures_util::stream::iter(chunks)));
+    response.headers_mut().insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/json"),
+    );
+    Ok(response)
+}
+
+fn tag(engine: &Arc<Engine>, name: &str, query: &Query) -> ApiResult<Response<Body>> {
+    let repo = query
+        .get("repo")
+        .filter(|r| !r.is_empty())
+        .ok_or_else(|| ApiError::bad_request("repository name must have at least one component"))?;
+    let tag = query
+        .get("tag")
+        .filter(|t| !t.is_empty())
+        .map(String::as_str)
+        .unwrap_or("latest");
+    let reference = normalize_reference(&format!("{}:{}", repo, tag));
+
+    let mut state = engine.lock();
+    let id = state.image(name)?.id.clone();
+    for image in &mut state.images {
+        image.tags.retain(|t| t != &reference);
+    }
+    state.image(&id)?.tags.push(reference.clone());
+    state.event("image", "tag", &id, json!({ "name": reference }));
+    drop(state);
+    engine.notify();
+    Ok(status(StatusCode::CREATED))
+}
+
+fn delete_image(engine: &Arc<Engine>, name: &str, query: &Query) -> ApiResult<Response<Body>> {
+    let force = flag(query, "force");
+    let mut state = engine.lock();
+    let image = state.image(name)?;
+    let id = image.id.clone();
+    let reference = normalize_reference(name);
+    let by_tag = image.tags.contains(&reference);
+
+    // removing one of several tags only untags the image
+    if by_tag && image.tags.len() > 1 {
+        image.tags.retain(|t| t != &reference);
+        state.event("image", "untag", &id, json!({ "name": reference }));
+        drop(state);
+        engine.notify();
+        return Ok(ok(json!([{ "Untagged": reference }])));
+    }
+
+    if let Some(container) = state.containers.iter().find(|c| c.image_id == id) {
+        if !force || container.is_running() {
+            return Err(ApiError::conflict(format!(
+                "conflict: unable to remove repository reference \"{}\" (must force) - container \
+                 {} is using its referenced image {}",
+                name,
+                &container.id[..12],
+                &id[7..19]
+            )));
+        }
+    }
+
+    let position = state
+        .images
+        .iter()
+        .position(|i| i.id == id)
+        .expect("image exists");
+    let image = state.images.remove(position);
+    let mut deleted: Vec<_> = image
+        .tags
+        .iter()
+        .chain(std::iter::once(&image.digest))
+        .map(|t| json!({ "Untagged": t }))
+        .collect();
+    deleted.push(json!({ "Deleted": id }));
+    for tag in &image.tags {
+        state.event("image", "untag", &id, json!({ "name": tag }));
+    }
+    state.event("image", "delete", &id, json!({ "name": id }));
+    drop(state);
+    engine.notify();
+    Ok(ok(json!(deleted)))
+}
+
+//####################################################################################################
+//
+// Networks
+//
+//####################################################################################################
+
+fn create_network(engine: &Arc<Engine>, body: &Bytes) -> ApiResult<Response<Body>> {
+    let body = body_json(body)?;
+    let name = body["Name"]
+        .as_str()
+        .filter(|n| !n.is_empty())
+        .ok_or_else(|| ApiError::bad_request("network name must be specified"))?;
+    let driver = body["Driver"]
+        .as_str()
+        .filter(|d| !d.is_empty())
+        .unwrap_or("bridge");
+
+    let mut state = engine.lock();
+    if state.networks.iter().any(|n| n.name == name) {
+        return Err(ApiError::conflict(format!(
+            "network with name {} already exists",
+            name
+        )));
+    }
+    let subnet = match driver {
+        "bridge" => (18..=31).find(|s| state.networks.iter().all(|n| n.subnet != Some(*s))),
+        _ => None,
+    };
+    let mut network = Network::new(name, driver, subnet);
+    network.labels = string_map(&body["Labels"]);
+    network.internal = body["Internal"].as_bool().unwrap_or_default();
+    network.attachable = body["Attachable"].as_bool().unwrap_or_default();
+    let id = network.id.clone();
+    state.networks.push(network);
+    state.event(
+        "network",
+        "create",
+        &id,
+        json!({"name": name, "type": driver}),
+    );
+    drop(state);
+    engine.notify();
+
+    Ok(created(json!({"Id": id, "Warning": ""})))
+}
+
+fn delete_network(engine: &Arc<Engine>, id: &str) -> ApiResult<Response<Body>> {
+    let mut state = engine.lock();
+    let network = state.network(id)?;
+    if network.is_predefined() {
+        return Err(ApiError::new(
+            StatusCode::FORBIDDEN,
+            format!(
+                "{} is a pre-defined network and cannot be removed",
+                network.name
+            ),
+        ));
+    }
+    if !network.endpoints.is_empty() {
+        return Err(ApiError::new(
+            StatusCode::FORBIDDEN,
+            format!(
+                "error while removing network: network {} id {} has active endpoints",
+                network.name, network.id
+            ),
+        ));
+    }
+    let (id, name, driver) = (
+        network.id.clone(),
+        network.name.clone(),
+        network.driver.clone(),
+    );
+    state.networks.retain(|n| n.id != id);
+    state.event(
+        "network",
+        "destroy",
+        &id,
+        json!({"name": name, "type": driver}),
+    );
+    drop(state);
+    engine.notify();
+    Ok(status(StatusCode::NO_CONTENT))
+}
+
+fn connect(
+    engine: &Arc<Engine>,
+    id: &str,
+    body: &Bytes,
+    connect: bool,
+) -> ApiResult<Response<Body>> {
+    let body = body_json(body)?;
+    let container = body["Container"]
+        .as_str()
+        .ok_or_else(|| ApiError::bad_request("container is required"))?;
+
+    let mut state = engine.lock();
+    let container = state.container(container)?;
+    let (container_id, container_name) = (container.id.clone(), container.name.clone());
+    let network = state.network(id)?;
+    let connected = network.endpoints.contains_key(&container_id);
+    match (connect, connected) {
+        (true, true) => {
+            return Err(ApiError::new(
+                StatusCode::FORBIDDEN,
+                format!(
+                    "endpoint with name {} already exists in network {}",
+                    container_name, network.name
+                ),
+            ))
+        }
+        (false, false) => {
+            return Err(ApiError::new(
+                StatusCode::FORBIDDEN,
+                format!(
+                    "container {} is not connected to network {}",
+                    container_id, network.name
+                ),
+            ))
+        }
+        (true, false) => network.connect(&container_id),
+        (false, true) => {
+            network.endpoints.remove(&container_id);
+        }
+    }
+    let (network_id, attributes) = (
+        network.id.clone(),
+        json!({"container": container_id, "name": network.name, "type": network.driver}),
+    );
+    state.event(
+        "network",
+        if connect { "connect" } else { "disconnect" },
+        &network_id,
+        attributes,
+    );
+    drop(state);
+    engine.notify();
+    Ok(status(StatusCode::OK))
+}
+
+//####################################################################################################
+//
+// Volumes
+//
+//####################################################################################################
+
+fn create_volume(engine: &Arc<Engine>, body: &Bytes) -> ApiResult<Response<Body>> {
+    let body = body_json(body)?;
+    let name = match body["Name"].as_str().filter(|n| !n.is_empty()) {
+        Some(name) => name.to_string(),
+        None => random_id(),
+    };
+    let driver = body["Driver"]
+        .as_str()
+        .filter(|d| !d.is_empty())
+        .unwrap_or("local")
+        .to_string();
+
+    let mut state = engine.lock();
+    if let Ok(volume) = state.volume(&name) {
+        // creating an existing volume returns it like the docker daemon does
+        if volume.driver != driver {
+            return Err(ApiError::conflict(format!(
+                "a volume with the name {} already exists with driver \"{}\"",
+                name, volume.driver
+            )));
+        }
+        return Ok(created(volume.details()));
+    }
+    let mut volume = Volume::new(name.clone(), driver.clone());
+    volume.labels = string_map(&body["Labels"]);
+    volume.options = string_map(&body["DriverOpts"]);
+    let details = volume.details();
+    state.volumes.push(volume);
+    state.event("volume", "create", &name, json!({ "driver": driver }));
+    drop(state);
+    engine.notify();
+    Ok(created(details))
+}
+
+fn delete_volume(engine: &Arc<Engine>, name: &str) -> ApiResult<Response<Body>> {
+    let mut state = engine.lock();
+    state.volume(name)?;
+    let users: Vec<_> = state
+        .containers
+        .iter()
+        .filter(|c| c.mounts.iter().any(|m| m.volume.as_deref() == Some(name)))
+        .map(|c| c.id.clone())
+        .collect();
+    if !users.is_empty() {
+        return Err(ApiError::conflict(format!(
+            "remove {}: volume is in use - [{}]",
+            name,
+            users.join(", ")
+        )));
+    }
+    state.volumes.retain(|v| v.name != name);
+    state.event("volume", "destroy", name, json!({}));
+    drop(state);
+    engine.notify();
+    Ok(status(StatusCode::NO_CONTENT))
+}
@@ -0,0 +1,1037 @@
+//! In-memory state of the fake engine and its JSON representation.
+
+use crate::conn::TtyChunk;
+
+use hyper::StatusCode;
+use serde_json::{json, Value};
+use tokio::{
+    sync::{mpsc::UnboundedSender, watch},
+    task::JoinHandle,
+};
+
+use std::{
+    collections::{hash_map::RandomState, BTreeMap},
+    hash::{BuildHasher, Hasher},
+    sync::{Mutex, MutexGuard},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Version of the docker daemon impersonated by the fake engine.
+pub(crate) const ENGINE_VERSION: &str = "20.10.7";
+/// Timestamp of objects that never reached a state, like a container that never started.
+const ZERO_TIME: &str = "0001-01-01T00:00:00Z";
+
+const ADJECTIVES: &[&str] = &[
+    "brave", "calm", "eager", "focused", "gifted", "happy", "jolly", "keen", "quirky", "zen",
+];
+const SURNAMES: &[&str] = &[
+    "curie", "darwin", "hopper", "lovelace", "noether", "pasteur", "ritchie", "turing",
+];
+
+/// An error answered with a status code and a JSON message like the docker daemon does.
+#[derive(Debug)]
+pub(crate) struct ApiError {
+    pub status: StatusCode,
+    pub message: String,
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        ApiError {
+            status,
+            message: message.into(),
+        }
+    }
+
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, message)
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::NOT_FOUND, message)
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::CONFLICT, message)
+    }
+}
+
+pub(crate) type ApiResult<T> = std::result::Result<T, ApiError>;
+
+/// State shared by all connections to a fake engine.
+pub(crate) struct Engine {
+    state: Mutex<State>,
+    changes: watch::Sender<u64>,
+}
+
+impl Engine {
+    pub fn new() -> Self {
+        Engine {
+            state: Mutex::new(State::new()),
+            changes: watch::channel(0).0,
+        }
+    }
+
+    pub fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().expect("fake engine state lock")
+    }
+
+    /// Wakes up everyone waiting for a change of the state, like log followers or `wait` calls.
+    pub fn notify(&self) {
+        self.changes.send_modify(|generation| *generation += 1);
+    }
+
+    /// Returns a receiver notified about every change of the state made after this call.
+    pub fn subscribe(&self) -> watch::Receiver<u64> {
+        self.changes.subscribe()
+    }
+}
+
+pub(crate) struct State {
+    pub containers: Vec<Container>,
+    pub images: Vec<Image>,
+    pub networks: Vec<Network>,
+    pub volumes: Vec<Volume>,
+    pub execs: BTreeMap<String, Exec>,
+    pub events: Vec<Event>,
+}
+
+impl State {
+    fn new() -> Self {
+        let mut state = State {
+            containers: vec![],
+            images: vec![],
+            networks: vec![],
+            volumes: vec![],
+            execs: BTreeMap::new(),
+            events: vec![],
+        };
+        for (name, driver, subnet) in [
+            ("bridge", "bridge", Some(17)),
+            ("host", "host", None),
+            ("none", "null", None),
+        ] {
+            state.networks.push(Network::new(name, driver, subnet));
+        }
+        state
+    }
+
+    /// Finds a container by its full id, name or a unique prefix of its id.
+    pub fn container(&mut self, id: &str) -> ApiResult<&mut Container> {
+        let name = id.trim_start_matches('/');
+        let position = self
+            .containers
+            .iter()
+            .position(|c| c.id == id || c.name == name)
+            .or_else(|| unique(self.containers.iter().map(|c| c.id.as_str()), id));
+        match position {
+            Some(i) => Ok(&mut self.containers[i]),
+            None => Err(ApiError::not_found(format!("No such container: {}", id))),
+        }
+    }
+
+    /// Finds an image by a reference like `ubuntu` or `ubuntu:20.04`, its id or a unique prefix
+    /// of its id.
+    pub fn image(&mut self, reference: &str) -> ApiResult<&mut Image> {
+        let normalized = normalize_reference(reference);
+        let id = reference.trim_start_matches("sha256:");
+        let position = self
+            .images
+            .iter()
+            .position(|image| image.tags.contains(&normalized))
+            .or_else(|| {
+                unique(
+                    self.images
+                        .iter()
+                        .map(|image| image.id.trim_start_matches("sha256:")),
+                    id,
+                )
+            });
+        match position {
+            Some(i) => Ok(&mut self.images[i]),
+            None => Err(ApiError::not_found(format!(
+                "No such image: {}",
+                normalized
+            ))),
+        }
+    }
+
+    /// Adds an image tagged with `reference` unless one already exists and returns its id.
+    pub fn add_image(&mut self, reference: &str) -> String {
+        let reference = normalize_reference(reference);
+        if let Ok(image) = self.image(&reference) {
+            return image.id.clone();
+        }
+        let image = Image::new(&reference);
+        let id = image.id.clone();
+        self.images.push(image);
+        id
+    }
+
+    /// Finds a network by its full id, name or a unique prefix of its id.
+    pub fn network(&mut self, id: &str) -> ApiResult<&mut Network> {
+        let position = self
+            .networks
+            .iter()
+            .position(|n| n.id == id || n.name == id)
+            .or_else(|| unique(self.networks.iter().map(|n| n.id.as_str()), id));
+        match position {
+            Some(i) => Ok(&mut self.networks[i]),
+            None => Err(ApiError::not_found(format!("network {} not found", id))),
+        }
+    }
+
+    pub fn volume(&mut self, name: &str) -> ApiResult<&mut Volume> {
+        self.volumes
+            .iter_mut()
+            .find(|v| v.name == name)
+            .ok_or_else(|| ApiError::not_found(format!("get {}: no such volume", name)))
+    }
+
+    pub fn exec(&mut self, id: &str) -> ApiResult<&mut Exec> {
+        self.execs
+            .get_mut(id)
+            .ok_or_else(|| ApiError::not_found(format!("No such exec instance: {}", id)))
+    }
+
+    /// Returns a name for a container that isn't used yet.
+    pub fn container_name(&self) -> String {
+        let random = random();
+        let name = format!(
+            "{}_{}",
+            ADJECTIVES[random as usize % ADJECTIVES.len()],
+            SURNAMES[(random >> 32) as usize % SURNAMES.len()]
+        );
+        let mut candidate = name.clone();
+        let mut n = 1;
+        while self.containers.iter().any(|c| c.name == candidate) {
+            n += 1;
+            candidate = format!("{}{}", name, n);
+        }
+        candidate
+    }
+
+    /// Records an event of an object of `typ`.
+    pub fn event(&mut self, typ: &str, action: impl Into<String>, id: &str, attributes: Value) {
+        self.events.push(Event {
+            typ: typ.to_string(),
+            action: action.into(),
+            id: id.to_string(),
+            attributes,
+            time: SystemTime::now(),
+        });
+    }
+
+    /// Records an event of a container, its image and name are added to the attributes.
+    pub fn container_event(&mut self, id: &str, action: impl Into<String>, mut attributes: Value) {
+        if let Some(container) = self.containers.iter().find(|c| c.id == id) {
+            attributes["image"] = json!(container.image);
+            attributes["name"] = json!(container.name);
+            for (key, value) in &container.config.labels {
+                attributes[key] = json!(value);
+            }
+        }
+        self.event("container", action, id, attributes);
+    }
+}
+
+/// Returns the index of the only id starting with `prefix`.
+fn unique<'a>(ids: impl Iterator<Item = &'a str>, prefix: &str) -> Option<usize> {
+    if prefix.is_empty() {
+        return None;
+    }
+    let mut matches = ids.enumerate().filter(|(_, id)| id.starts_with(prefix));
+    match (matches.next(), matches.next()) {
+        (Some((i, _)), None) => Some(i),
+        _ => None,
+    }
+}
+
+/// Turns references like `docker.io/library/ubuntu` into `ubuntu:latest`.
+pub(crate) fn normalize_reference(reference: &str) -> String {
+    let reference = reference
+        .trim_start_matches("docker.io/")
+        .trim_start_matches("library/");
+    let name = reference.rsplit('/').next().unwrap_or(reference);
+    if name.contains(':') || name.contains('@') {
+        reference.to_string()
+    } else {
+        format!("{}:latest", reference)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Status {
+    Created,
+    Running,
+    Paused,
+    Exited,
+}
+
+impl Status {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Status::Created => "created",
+            Status::Running => "running",
+            Status::Paused => "paused",
+            Status::Exited => "exited",
+        }
+    }
+}
+
+/// Configuration of a container as given when creating it.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ContainerConfig {
+    pub entrypoint: Vec<String>,
+    pub cmd: Vec<String>,
+    pub env: Vec<String>,
+    pub labels: BTreeMap<String, String>,
+    pub hostname: String,
+    pub user: String,
+    pub working_dir: String,
+    pub tty: bool,
+    pub open_stdin: bool,
+    pub stdin_once: bool,
+    pub attach_stdin: bool,
+    pub attach_stdout: bool,
+    pub attach_stderr: bool,
+    pub auto_remove: bool,
+    pub network_mode: String,
+    pub binds: Vec<String>,
+    /// Height and width of the TTY.
+    pub console_size: [u64; 2],
+    /// Resource limits and restart policy, the part of the host config that can be updated.
+    pub resources: serde_json::Map<String, Value>,
+}
+
+impl ContainerConfig {
+    /// The command run when the container starts.
+    pub fn argv(&self) -> Vec<String> {
+        self.entrypoint.iter().chain(&self.cmd).cloned().collect()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct MountPoint {
+    pub volume: Option<String>,
+    pub source: String,
+    pub destination: String,
+    pub rw: bool,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct LogEntry {
+    pub chunk: TtyChunk,
+    pub time: SystemTime,
+}
+
+pub(crate) struct Container {
+    pub id: String,
+    pub name: String,
+    pub created: SystemTime,
+    pub image: String,
+    pub image_id: String,
+    pub config: ContainerConfig,
+    pub mounts: Vec<MountPoint>,
+    pub status: Status,
+    pub pid: u64,
+    pub exit_code: i64,
+    pub error: String,
+    pub started_at: Option<SystemTime>,
+    pub finished_at: Option<SystemTime>,
+    /// Incremented every time the container starts, output of older runs is discarded.
+    pub run: u64,
+    /// Incremented every time the container stops.
+    pub exits: u64,
+    pub log: Vec<LogEntry>,
+    pub stdin: Option<UnboundedSender<Vec<u8>>>,
+    pub process: Option<JoinHandle<()>>,
+    pub exec_ids: Vec<String>,
+}
+
+impl Container {
+    pub fn new(name: String, image: String, image_id: String, config: ContainerConfig) -> Self {
+        let id = random_id();
+        let mut config = config;
+        if config.hostname.is_empty() {
+            config.hostname = id[..12].to_string();
+        }
+        Container {
+            id,
+            name,
+            created: SystemTime::now(),
+            image,
+            image_id,
+            config,
+            mounts: vec![],
+            status: Status::Created,
+            pid: 0,
+            exit_code: 0,
+            error: String::new(),
+            started_at: None,
+            finished_at: None,
+            run: 0,
+            exits: 0,
+            log: vec![],
+            stdin: None,
+            process: None,
+            exec_ids: vec![],
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        matches!(self.status, Status::Running | Status::Paused)
+    }
+
+    /// Records that the process of the container ended with `exit_code`.
+    pub fn exited(&mut self, exit_code: i64) {
+        if let Some(process) = self.process.take() {
+            process.abort();
+        }
+        self.status = Status::Exited;
+        self.exit_code = exit_code;
+        self.pid = 0;
+        self.stdin = None;
+        self.finished_at = Some(SystemTime::now());
+        self.exits += 1;
+    }
+
+    fn status_text(&self) -> String {
+        let since = |time: Option<SystemTime>| {
+            human_duration(time.and_then(|t| t.elapsed().ok()).unwrap_or_default())
+        };
+        match self.status {
+            Status::Created => "Created".to_string(),
+            Status::Running => format!("Up {}", since(self.started_at)),
+            Status::Paused => format!("Up {} (Paused)", since(self.started_at)),
+            Status::Exited => format!(
+                "Exited ({}) {} ago",
+                self.exit_code,
+                since(self.finished_at)
+            ),
+        }
+    }
+
+    fn command(&self) -> String {
+        self.config.argv().join(" ")
+    }
+
+    fn mounts_json(&self) -> Value {
+        self.mounts
+            .iter()
+            .map(|m| {
+                json!({
+                    "Type": if m.volume.is_some() { "volume" } else { "bind" },
+                    "Name": m.volume,
+                    "Source": m.source,
+                    "Destination": m.destination,
+                    "Driver": m.volume.as_ref().map(|_| "local"),
+                    "Mode": "",
+                    "RW": m.rw,
+                    "Propagation": if m.volume.is_some() { "" } else { "rprivate" },
+                })
+            })
+            .collect()
+    }
+
+    fn networks_json(&self, networks: &[Network]) -> Value {
+        let running = self.is_running();
+        networks
+            .iter()
+            .filter_map(|network| {
+                let endpoint = network.endpoints.get(&self.id)?;
+                let (ip, gateway, prefix, mac) = match (&network.subnet, running) {
+                    (Some(_), true) => (
+                        endpoint.ip.clone(),
+                        network.gateway(),
+                        16,
+                        endpoint.mac.clone(),
+                    ),
+                    _ => (String::new(), String::new(), 0, String::new()),
+                };
+                Some((
+                    network.name.clone(),
+                    json!({
+                        "NetworkID": network.id,
+                        "EndpointID": if running { endpoint.id.clone() } else { String::new() },
+                        "Gateway": gateway,
+                        "IPAddress": ip,
+                        "IPPrefixLen": prefix,
+                        "IPv6Gateway": "",
+                        "GlobalIPv6Address": "",
+                        "GlobalIPv6PrefixLen": 0,
+                        "MacAddress": mac,
+                    }),
+                ))
+            })
+            .collect::<serde_json::Map<_, _>>()
+            .into()
+    }
+
+    fn host_config_json(&self) -> Value {
+        let mut host_config = json!({
+            "Binds": if self.config.binds.is_empty() { Value::Null } else { json!(self.config.binds) },
+            "ContainerIDFile": "",
+            "LogConfig": {"Type": "json-file", "Config": {}},
+            "NetworkMode": self.config.network_mode,
+            "PortBindings": {},
+            "RestartPolicy": {"Name": "no", "MaximumRetryCount": 0},
+            "AutoRemove": self.config.auto_remove,
+            "VolumeDriver": "",
+            "CgroupnsMode": "host",
+            "IpcMode": "private",
+            "Cgroup": "",
+            "OomScoreAdj": 0,
+            "Privileged": false,
+            "PublishAllPorts": false,
+            "ReadonlyRootfs": false,
+            "UTSMode": "",
+            "UsernsMode": "",
+            "ShmSize": 67108864,
+            "Runtime": "runc",
+            "ConsoleSize": self.config.console_size,
+            "Isolation": "",
+            "CpuShares": 0,
+            "Memory": 0,
+            "NanoCpus": 0,
+            "BlkioWeight": 0,
+            "KernelMemory": 0,
+            "KernelMemoryTCP": 0,
+            "CpuCount": 0,
+            "CpuPercent": 0,
+            "IOMaximumIOps": 0,
+            "IOMaximumBandwidth": 0,
+            "MaskedPaths": ["/proc/asound", "/proc/acpi", "/proc/kcore"],
+            "ReadonlyPaths": ["/proc/bus", "/proc/fs", "/proc/irq"],
+        });
+        if let Some(fields) = host_config.as_object_mut() {
+            fields.extend(self.config.resources.clone());
+        }
+        host_config
+    }
+
+    /// The container as returned by `GET /containers/json`.
+    pub fn summary(&self, networks: &[Network]) -> Value {
+        json!({
+            "Id": self.id,
+            "Names": [format!("/{}", self.name)],
+            "Image": self.image,
+            "ImageID": self.image_id,
+            "Command": self.command(),
+            "Created": unix(self.created),
+            "Ports": [],
+            "Labels": self.config.labels,
+            "State": self.status.as_str(),
+            "Status": self.status_text(),
+            "HostConfig": {"NetworkMode": self.config.network_mode},
+            "NetworkSettings": {"Networks": self.networks_json(networks)},
+            "Mounts": self.mounts_json(),
+        })
+    }
+
+    /// The container as returned by `GET /containers/{id}/json`.
+    pub fn details(&self, networks: &[Network]) -> Value {
+        let argv = self.config.argv();
+        let primary = networks
+            .iter()
+            .find(|n| n.name == "bridge" && n.endpoints.contains_key(&self.id))
+            .and_then(|n| n.endpoints.get(&self.id).map(|e| (n, e)))
+            .filter(|_| self.is_running());
+        let (ip, gateway, prefix, mac, endpoint) = match primary {
+            Some((network, endpoint)) => (
+                endpoint.ip.clone(),
+                network.gateway(),
+                16,
+                endpoint.mac.clone(),
+                endpoint.id.clone(),
+            ),
+            None => Default::default(),
+        };
+        let dir = format!("/var/lib/docker/containers/{}", self.id);
+
+        json!({
+            "Id": self.id,
+            "Created": rfc3339(self.created),
+            "Path": argv.first().cloned().unwrap_or_default(),
+            "Args": argv.iter().skip(1).collect::<Vec<_>>(),
+            "State": {
+                "Status": self.status.as_str(),
+                "Running": self.is_running(),
+                "Paused": self.status == Status::Paused,
+                "Restarting": false,
+                "OOMKilled": false,
+                "Dead": false,
+                "Pid": self.pid,
+                "ExitCode": self.exit_code,
+                "Error": self.error,
+                "StartedAt": self.started_at.map(rfc3339).unwrap_or_else(|| ZERO_TIME.to_string()),
+                "FinishedAt": self.finished_at.map(rfc3339).unwrap_or_else(|| ZERO_TIME.to_string()),
+            },
+            "Image": self.image_id,
+            "ResolvConfPath": format!("{}/resolv.conf", dir),
+            "HostnamePath": format!("{}/hostname", dir),
+            "HostsPath": format!("{}/hosts", dir),
+            "LogPath": format!("{}/{}-json.log", dir, self.id),
+            "Name": format!("/{}", self.name),
+            "RestartCount": 0,
+            "Driver": "overlay2",
+            "Platform": "linux",
+            "MountLabel": "",
+            "ProcessLabel": "",
+            "AppArmorProfile": "",
+            "ExecIDs": if self.exec_ids.is_empty() { Value::Null } else { json!(self.exec_ids) },
+            "HostConfig": self.host_config_json(),
+            "GraphDriver": {
+                "Name": "overlay2",
+                "Data": {
+                    "MergedDir": format!("/var/lib/docker/overlay2/{}/merged", self.id),
+                    "UpperDir": format!("/var/lib/docker/overlay2/{}/diff", self.id),
+                    "WorkDir": format!("/var/lib/docker/overlay2/{}/work", self.id),
+                },
+            },
+            "Mounts": self.mounts_json(),
+            "Config": {
+                "Hostname": self.config.hostname,
+                "Domainname": "",
+                "User": self.config.user,
+                "AttachStdin": self.config.attach_stdin,
+                "AttachStdout": self.config.attach_stdout,
+                "AttachStderr": self.config.attach_stderr,
+                "Tty": self.config.tty,
+                "OpenStdin": self.config.open_stdin,
+                "StdinOnce": self.config.stdin_once,
+                "Env": self.config.env,
+                "Cmd": self.config.cmd,
+                "Image": self.image,
+                "Volumes": null,
+                "WorkingDir": self.config.working_dir,
+                "Entrypoint": if self.config.entrypoint.is_empty() { Value::Null } else { json!(self.config.entrypoint) },
+                "OnBuild": null,
+                "Labels": self.config.labels,
+            },
+            "NetworkSettings": {
+                "Bridge": "",
+                "SandboxID": if self.is_running() { self.id.clone() } else { String::new() },
+                "HairpinMode": false,
+                "LinkLocalIPv6Address": "",
+                "LinkLocalIPv6PrefixLen": 0,
+                "Ports": {},
+                "SandboxKey": if self.is_running() { format!("/var/run/docker/netns/{}", &self.id[..12]) } else { String::new() },
+                "SecondaryIPAddresses": null,
+                "SecondaryIPv6Addresses": null,
+                "EndpointID": endpoint,
+                "Gateway": gateway,
+                "GlobalIPv6Address": "",
+                "GlobalIPv6PrefixLen": 0,
+                "IPAddress": ip,
+                "IPPrefixLen": prefix,
+                "IPv6Gateway": "",
+                "MacAddress": mac,
+                "Networks": self.networks_json(networks),
+            },
+        })
+    }
+}
+
+pub(crate) struct Exec {
+    pub id: String,
+    pub container_id: String,
+    pub argv: Vec<String>,
+    pub env: Vec<String>,
+    pub user: String,
+    pub working_dir: String,
+    pub tty: bool,
+    pub attach_stdout: bool,
+    pub attach_stderr: bool,
+    pub running: bool,
+    pub started: bool,
+    pub exit_code: Option<i64>,
+}
+
+impl Exec {
+    pub fn details(&self) -> Value {
+        json!({
+            "ID": self.id,
+            "Running": self.running,
+            "ExitCode": self.exit_code,
+            "ProcessConfig": {
+                "tty": self.tty,
+                "entrypoint": self.argv.first().cloned().unwrap_or_default(),
+                "arguments": self.argv.iter().skip(1).collect::<Vec<_>>(),
+                "privileged": false,
+                "user": if self.user.is_empty() { None } else { Some(&self.user) },
+            },
+            "OpenStdin": false,
+            "OpenStderr": self.attach_stderr,
+            "OpenStdout": self.attach_stdout,
+            "CanRemove": false,
+            "ContainerID": self.container_id,
+            "DetachKeys": "",
+            "Pid": if self.running { 4242 } else { 0 },
+        })
+    }
+}
+
+pub(crate) struct Image {
+    pub id: String,
+    pub tags: Vec<String>,
+    pub digest: String,
+    pub created: SystemTime,
+    pub size: u64,
+    pub cmd: Vec<String>,
+    pub env: Vec<String>,
+}
+
+impl Image {
+    fn new(reference: &str) -> Self {
+        let repository = reference.split([':', '@']).next().unwrap_or(reference);
+        let cmd = match repository.rsplit('/').next() {
+            Some("ubuntu") | Some("debian") => "bash",
+            _ => "sh",
+        };
+        Image {
+            id: format!("sha256:{}", random_id()),
+            tags: if reference.contains('@') {
+                vec![]
+            } else {
+                vec![reference.to_string()]
+            },
+            digest: format!("{}@sha256:{}", repository, random_id()),
+            created: SystemTime::now(),
+            size: 5_000_000 + random() % 70_000_000,
+            cmd: vec![cmd.to_string()],
+            env: vec![
+                "PATH=/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin:/sbin:/bin".to_string(),
+            ],
+        }
+    }
+
+    fn config_json(&self) -> Value {
+        json!({
+            "Hostname": "",
+            "Domainname": "",
+            "User": "",
+            "AttachStdin": false,
+            "AttachStdout": false,
+            "AttachStderr": false,
+            "Tty": false,
+            "OpenStdin": false,
+            "StdinOnce": false,
+            "Env": self.env,
+            "Cmd": self.cmd,
+            "Image": "",
+            "Volumes": null,
+            "WorkingDir": "",
+            "Entrypoint": null,
+            "OnBuild": null,
+            "Labels": null,
+        })
+    }
+
+    /// The image as returned by `GET /images/json`.
+    pub fn summary(&self) -> Value {
+        json!({
+            "Id": self.id,
+            "ParentId": "",
+            "RepoTags": self.tags,
+            "RepoDigests": [self.digest],
+            "Created": unix(self.created),
+            "Size": self.size,
+            "SharedSize": -1,
+            "VirtualSize": self.size,
+            "Labels": null,
+            "Containers": -1,
+        })
+    }
+
+    /// The image as returned by `GET /images/{name}/json`.
+    pub fn details(&self) -> Value {
+        json!({
+            "Id": self.id,
+            "RepoTags": self.tags,
+            "RepoDigests": [self.digest],
+            "Parent": "",
+            "Comment": "",
+            "Created": rfc3339(self.created),
+            "Container": "",
+            "ContainerConfig": self.config_json(),
+            "DockerVersion": ENGINE_VERSION,
+            "Author": "",
+            "Config": self.config_json(),
+            "Architecture": "amd64",
+            "Os": "linux",
+            "Size": self.size,
+            "VirtualSize": self.size,
+            "GraphDriver": {
+                "Name": "overlay2",
+                "Data": {
+                    "MergedDir": format!("/var/lib/docker/overlay2/{}/merged", &self.id[7..]),
+                    "UpperDir": format!("/var/lib/docker/overlay2/{}/diff", &self.id[7..]),
+                    "WorkDir": format!("/var/lib/docker/overlay2/{}/work", &self.id[7..]),
+                },
+            },
+            "RootFS": {"Type": "layers", "Layers": [format!("sha256:{}", random_id())]},
+            "Metadata": {"LastTagTime": ZERO_TIME},
+        })
+    }
+}
+
+pub(crate) struct Endpoint {
+    pub id: String,
+    pub ip: String,
+    pub mac: String,
+}
+
+pub(crate) struct Network {
+    pub id: String,
+    pub name: String,
+    pub driver: String,
+    /// Second octet of the `172.x.0.0/16` subnet of the network, if it has one.
+    pub subnet: Option<u8>,
+    pub created: SystemTime,
+    pub labels: BTreeMap<String, String>,
+    pub internal: bool,
+    pub attachable: bool,
+    pub endpoints: BTreeMap<String, Endpoint>,
+    next_host: u32,
+}
+
+impl Network {
+    pub fn new(name: &str, driver: &str, subnet: Option<u8>) -> Self {
+        Network {
+            id: random_id(),
+            name: name.to_string(),
+            driver: driver.to_string(),
+            subnet,
+            created: SystemTime::now(),
+            labels: BTreeMap::new(),
+            internal: false,
+            attachable: false,
+            endpoints: BTreeMap::new(),
+            next_host: 2,
+        }
+    }
+
+    /// Whether this is one of the networks every docker daemon has.
+    pub fn is_predefined(&self) -> bool {
+        matches!(self.name.as_str(), "bridge" | "host" | "none")
+    }
+
+    pub fn gateway(&self) -> String {
+        self.subnet
+            .map(|subnet| format!("172.{}.0.1", subnet))
+            .unwrap_or_default()
+    }
+
+    /// Connects the container with `id` to this network, assigning it an address.
+    pub fn connect(&mut self, id: &str) {
+        let host = self.next_host;
+        self.next_host += 1;
+        let ip = self
+            .subnet
+            .map(|subnet| format!("172.{}.{}.{}", subnet, host / 256, host % 256))
+            .unwrap_or_default();
+        let mac = self
+            .subnet
+            .map(|subnet| {
+                format!(
+                    "02:42:ac:{:02x}:{:02x}:{:02x}",
+                    subnet,
+                    host / 256,
+                    host % 256
+                )
+            })
+            .unwrap_or_default();
+        self.endpoints.insert(
+            id.to_string(),
+            Endpoint {
+                id: random_id(),
+                ip,
+                mac,
+            },
+        );
+    }
+
+    /// The network as returned by `GET /networks` and `GET /networks/{id}`.
+    pub fn details(&self, containers: &[Container]) -> Value {
+        let config = match self.subnet {
+            Some(subnet) => json!([{
+                "Subnet": format!("172.{}.0.0/16", subnet),
+                "Gateway": self.gateway(),
+            }]),
+            None => json!([]),
+        };
+        let endpoints: serde_json::Map<_, _> = self
+            .endpoints
+            .iter()
+            .filter_map(|(id, endpoint)| {
+                let container = containers.iter().find(|c| &c.id == id)?;
+                if !container.is_running() {
+                    return None;
+                }
+                Some((
+                    id.clone(),
+                    json!({
+                        "Name": container.name,
+                        "EndpointID": endpoint.id,
+                        "MacAddress": endpoint.mac,
+                        "IPv4Address": if endpoint.ip.is_empty() { String::new() } else { format!("{}/16", endpoint.ip) },
+                        "IPv6Address": "",
+                    }),
+                ))
+            })
+            .collect();
+
+        json!({
+            "Name": self.name,
+            "Id": self.id,
+            "Created": rfc3339(self.created),
+            "Scope": "local",
+            "Driver": self.driver,
+            "EnableIPv6": false,
+            "IPAM": {"Driver": "default", "Options": null, "Config": config},
+            "Internal": self.internal,
+            "Attachable": self.attachable,
+            "Ingress": false,
+            "ConfigFrom": {"Network": ""},
+            "ConfigOnly": false,
+            "Containers": endpoints,
+            "Options": {},
+            "Labels": self.labels,
+        })
+    }
+}
+
+pub(crate) struct Volume {
+    pub name: String,
+    pub driver: String,
+    pub created: SystemTime,
+    pub labels: BTreeMap<String, String>,
+    pub options: BTreeMap<String, String>,
+    /// Whether the volume was created for a container without giving it a name.
+    pub anonymous: bool,
+}
+
+impl Volume {
+    pub fn new(name: String, driver: String) -> Self {
+        Volume {
+            name,
+            driver,
+            created: SystemTime::now(),
+            labels: BTreeMap::new(),
+            options: BTreeMap::new(),
+            anonymous: false,
+        }
+    }
+
+    pub fn mountpoint(&self) -> String {
+        format!("/var/lib/docker/volumes/{}/_data", self.name)
+    }
+
+    /// The volume as returned by `GET /volumes` and `GET /volumes/{name}`.
+    pub fn details(&self) -> Value {
+        json!({
+            "Name": self.name,
+            "Driver": self.driver,
+            "Mountpoint": self.mountpoint(),
+            "CreatedAt": rfc3339(self.created),
+            "Labels": self.labels,
+            "Options": self.options,
+            "Scope": "local",
+        })
+    }
+}
+
+pub(crate) struct Event {
+    pub typ: String,
+    pub action: String,
+    pub id: String,
+    pub attributes: Value,
+    pub time: SystemTime,
+}
+
+impl Event {
+    /// The event as returned by `GET /events`.
+    pub fn to_json(&self) -> Value {
+        let nanos = self
+            .time
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos() as u64;
+        let mut event = json!({
+            "Type": self.typ,
+            "Action": self.action,
+            "Actor": {"ID": self.id, "Attributes": self.attributes},
+            "scope": "local",
+            "time": nanos / 1_000_000_000,
+            "timeNano": nanos,
+        });
+        // older clients expect these fields for container and image events
+        if self.typ == "container" || self.typ == "image" {
+            event["status"] = json!(self.action);
+            event["id"] = json!(self.id);
+            if let Some(image) = self.attributes.get("image") {
+                event["from"] = image.clone();
+            }
+        }
+        event
+    }
+}
+
+fn random() -> u64 {
+    RandomState::new().build_hasher().finish()
+}
+
+/// Returns a random 64 character hex id like the ones docker uses for containers and images.
+pub(crate) fn random_id() -> String {
+    (0..4).map(|_| format!("{:016x}", random())).collect()
+}
+
+pub(crate) fn unix(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
+}
+
+/// Formats `time` as an RFC 3339 timestamp with nanoseconds like `2021-06-01T12:30:00.123456789Z`.
+pub(crate) fn rfc3339(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since_epoch.as_secs() as i64;
+    let (days, secs_of_day) = (secs.div_euclid(86400), secs.rem_euclid(86400));
+
+    // civil date from days since the epoch, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:09}Z",
+        year,
+        month,
+        day,
+        secs_of_day / 3600,
+        secs_of_day % 3600 / 60,
+        secs_of_day % 60,
+        since_epoch.subsec_nanos()
+    )
+}
+
+/// Formats a duration the way `docker ps` does, like `5 seconds` or `About a minute`.
+fn human_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match secs {
+        0 => "Less than a second".to_string(),
+        1 => "1 second".to_string(),
+        2..=59 => format!("{} seconds", secs),
+        60..=119 => "About a minute".to_string(),
+        120..=3599 => format!("{} minutes", secs / 60),
+        3600..=7199 => "About an hour".to_string(),
+        _ => format!("{} hours", secs / 3600),
+    }
+}
@@ -22,6 +22,20 @@ pub mod url {
         ep.push_str(query.as_ref());
     }
 
+    /// Removes the `/v1.xx` prefix from `path`.
+    pub fn strip_version(path: &str) -> &str {
+        let rest = path.trim_start_matches('/');
+        match rest.split_once('/') {
+            Some((version, endpoint))
+                if version.starts_with('v')
+                    && version[1..].parse::<crate::ApiVersion>().is_ok() =>
+            {
+                &path[path.len() - endpoint.len() - 1..]
+            }
+            _ => path,
+        }
+    }
+
     pub fn encoded_pair<K, V>(key: K, val: V) -> String
     where
         K: AsRef<str> + 'static,
@@ -88,6 +102,124 @@ pub mod datetime {
     }
 }
 
+pub mod body {
+    use crate::conn::Runtime;
+
+    use bytes::{Bytes, BytesMut};
+    use futures_util::{
+        io::{AsyncRead, AsyncReadExt},
+        stream::{self, Stream},
+    };
+    use std::{
+        any::Any,
+        io::{self, Write},
+        panic::{self, AssertUnwindSafe},
+        sync::Arc,
+    };
+    use tokio::sync::mpsc;
+
+    /// Size of the chunks streamed bodies are split into.
+    const CHUNK_SIZE: usize = 64 * 1024;
+    /// Number of chunks buffered between the producer of a body and the request.
+    const BUFFERED_CHUNKS: usize = 4;
+
+    /// Runs `write` with [`Runtime::spawn_blocking`](crate::conn::Runtime::spawn_blocking) once
+    /// the body is first polled and streams what it writes in chunks. The task blocks while the
+    /// request doesn't keep up so at most a few chunks are held in memory. If `write` fails or
+    /// panics the stream ends with an error so a truncated body is never sent as a complete one.
+    pub fn from_writer<F>(
+        runtime: Arc<dyn Runtime>,
+        write: F,
+    ) -> impl Stream<Item = io::Result<Bytes>> + Send + 'static
+    where
+        F: FnOnce(&mut dyn Write) -> io::Result<()> + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel(BUFFERED_CHUNKS);
+        let task: Box<dyn FnOnce() + Send> = Box::new(move || {
+            let mut writer = ChannelWriter {
+                sender,
+                buffer: BytesMut::with_capacity(CHUNK_SIZE),
+            };
+            let result = panic::catch_unwind(AssertUnwindSafe(|| write(&mut writer)))
+                .unwrap_or_else(|panic| Err(panic_error(panic)))
+                .and_then(|_| writer.flush());
+            if let Err(e) = result {
+                let _ = writer.sender.blocking_send(Err(e));
+            }
+        });
+        stream::unfold(
+            (Some(task), runtime, receiver),
+            |(task, runtime, mut receiver)| async move {
+                if let Some(task) = task {
+                    runtime.spawn_blocking(task);
+                }
+                let chunk = receiver.recv().await?;
+                Some((chunk, (None, runtime, receiver)))
+            },
+        )
+    }
+
+    /// Turns the payload of a panic into an error.
+    fn panic_error(panic: Box<dyn Any + Send>) -> io::Error {
+        let message = panic
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("unknown panic");
+        io::Error::other(format!("writing the request body panicked: {}", message))
+    }
+
+    /// Streams the content of `reader` in chunks.
+    pub fn from_reader<R>(reader: R) -> impl Stream<Item = io::Result<Bytes>> + Send + 'static
+    where
+        R: AsyncRead + Send + Unpin + 'static,
+    {
+        stream::unfold(Some(reader), |reader| async move {
+            let mut reader = reader?;
+            let mut buffer = vec![0; CHUNK_SIZE];
+            match reader.read(&mut buffer).await {
+                Ok(0) => None,
+                Ok(n) => {
+                    buffer.truncate(n);
+                    Some((Ok(buffer.into()), Some(reader)))
+                }
+                Err(e) => Some((Err(e), None)),
+            }
+        })
+    }
+
+    struct ChannelWriter {
+        sender: mpsc::Sender<io::Result<Bytes>>,
+        buffer: BytesMut,
+    }
+
+    impl ChannelWriter {
+        fn send_buffer(&mut self) -> io::Result<()> {
+            let chunk = self.buffer.split().freeze();
+            self.sender
+                .blocking_send(Ok(chunk))
+                .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "request body was dropped"))
+        }
+    }
+
+    impl Write for ChannelWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.buffer.extend_from_slice(buf);
+            if self.buffer.len() >= CHUNK_SIZE {
+                self.send_buffer()?;
+            }
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            if self.buffer.is_empty() {
+                return Ok(());
+            }
+            self.send_buffer()
+        }
+    }
+}
+
 pub mod tarball {
     use flate2::{write::GzEncoder, Compression};
     use std::{
@@ -1,6 +1,6 @@
 use crate::{Error, Result};
 
-use std::str::FromStr;
+use std::{collections::HashMap, str::FromStr};
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
 /// Docker API version used to determine compatibility between a client and a server.
@@ -74,9 +74,78 @@ impl FromStr for ApiVersion {
     }
 }
 
+#[derive(Clone, Debug)]
+/// Path of an API endpoint together with the lowest API version it can be used with.
+pub(crate) struct Endpoint {
+    path: String,
+    min_version: Option<ApiVersion>,
+}
+
+impl Endpoint {
+    /// Raises the lowest API version required by this endpoint to `version` if it's higher.
+    pub fn since(mut self, version: impl Into<Option<ApiVersion>>) -> Self {
+        self.min_version = self.min_version.max(version.into());
+        self
+    }
+
+    /// Returns the path prefixed with `version`. Fails if the endpoint is not available in it.
+    pub fn versioned(&self, version: ApiVersion) -> Result<String> {
+        match self.min_version {
+            Some(required) if required > version => Err(Error::UnsupportedApiVersion {
+                required,
+                actual: version,
+            }),
+            _ => Ok(version.make_endpoint(&self.path)),
+        }
+    }
+}
+
+impl From<String> for Endpoint {
+    fn from(path: String) -> Self {
+        Endpoint {
+            path,
+            min_version: None,
+        }
+    }
+}
+
+impl<T: AsRef<str> + ?Sized> From<&T> for Endpoint {
+    fn from(path: &T) -> Self {
+        Endpoint::from(path.as_ref().to_string())
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+/// Lowest API versions required by the options set on an options builder.
+pub(crate) struct FieldVersions(HashMap<&'static str, ApiVersion>);
+
+impl FieldVersions {
+    pub fn insert(&mut self, field: &'static str, version: ApiVersion) {
+        self.0.insert(field, version);
+    }
+
+    /// Returns the lowest API version that supports all of the set options.
+    pub fn required(&self) -> Option<ApiVersion> {
+        self.0.values().max().copied()
+    }
+
+    /// Returns the names of options that are not available in API `version`.
+    pub fn unsupported(&self, version: ApiVersion) -> Vec<&'static str> {
+        let mut fields: Vec<_> = self
+            .0
+            .iter()
+            .filter(|(_, required)| **required > version)
+            .map(|(field, _)| *field)
+            .collect();
+        fields.sort_unstable();
+        fields
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::ApiVersion;
+    use super::{ApiVersion, Endpoint, FieldVersions};
+    use crate::Error;
 
     #[test]
     fn compares_versions() {
@@ -85,4 +154,34 @@ mod tests {
         assert!(ApiVersion::new(0, 41) < ApiVersion::new(1, 40));
         assert!(ApiVersion::new(2, 0) > ApiVersion::new(1, 41));
     }
+
+    #[test]
+    fn checks_endpoint_version() {
+        let ep = Endpoint::from("/build/prune").since(ApiVersion::new(1, 31));
+        assert_eq!(
+            ep.versioned(ApiVersion::new(1, 41)).unwrap(),
+            "/v1.41/build/prune"
+        );
+        match ep
+            .since(None)
+            .versioned(ApiVersion::new(1, 30))
+            .unwrap_err()
+        {
+            Error::UnsupportedApiVersion { required, actual } => {
+                assert_eq!(required, ApiVersion::new(1, 31));
+                assert_eq!(actual, ApiVersion::new(1, 30));
+            }
+            e => panic!("Expected Error::UnsupportedApiVersion, got {}", e),
+        }
+
+        let mut versions = FieldVersions::default();
+        versions.insert("outputs", ApiVersion::new(1, 40));
+        versions.insert("platform", ApiVersion::new(1, 32));
+        assert_eq!(versions.required(), Some(ApiVersion::new(1, 40)));
+        assert_eq!(
+            versions.unsupported(ApiVersion::new(1, 30)),
+            vec!["outputs", "platform"]
+        );
+        assert!(versions.unsupported(ApiVersion::new(1, 40)).is_empty());
+    }
 }